@@ -1,6 +1,7 @@
 use enigo::{
+    Coordinate,
     Direction::{Click, Press, Release},
-    Enigo, Key, Keyboard, Settings,
+    Enigo, Key, Keyboard, Mouse, Settings,
 };
 use std::{
     thread,
@@ -29,4 +30,12 @@ fn main() {
     enigo.key(control_or_command, Press).unwrap();
     enigo.key(Key::Unicode('a'), Click).unwrap();
     enigo.key(control_or_command, Release).unwrap();
+
+    // move the mouse in 100 small steps
+    let now = Instant::now();
+    for _ in 0..100 {
+        enigo.move_mouse(1, 1, Coordinate::Rel).unwrap();
+    }
+    let time = now.elapsed();
+    println!("{time:?}");
 }
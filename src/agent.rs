@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::{Axis, Button, Coordinate, Direction, Enigo, InputResult, Key, Keyboard, Mouse};
 
 #[cfg(feature = "serde")]
@@ -17,12 +19,16 @@ pub enum Token {
         Key,
         #[cfg_attr(feature = "serde", serde(default))] Direction,
     ),
-    /// Call the [`Keyboard::raw`] fn with the given keycode and direction
+    /// Call the [`Keyboard::raw`] fn with the given keycode and direction. If
+    /// the optional timestamp in milliseconds is set, the
+    /// [`Keyboard::raw_at`] fn is called instead so recorded events can be
+    /// replayed with their original timing
     #[cfg_attr(feature = "serde", serde(alias = "R"))]
     #[cfg_attr(feature = "serde", serde(alias = "r"))]
     Raw(
         u16,
         #[cfg_attr(feature = "serde", serde(default))] Direction,
+        #[cfg_attr(feature = "serde", serde(default))] Option<u32>,
     ),
     /// Call the [`Mouse::button`] fn with the given mouse button and direction
     #[cfg_attr(feature = "serde", serde(alias = "B"))]
@@ -46,6 +52,35 @@ pub enum Token {
     #[cfg_attr(feature = "serde", serde(alias = "S"))]
     #[cfg_attr(feature = "serde", serde(alias = "s"))]
     Scroll(i32, #[cfg_attr(feature = "serde", serde(default))] Axis),
+    /// Call the [`Mouse::long_press`] fn. The u64 is the duration in
+    /// milliseconds for which the button is held.
+    #[cfg_attr(feature = "serde", serde(alias = "L"))]
+    #[cfg_attr(feature = "serde", serde(alias = "l"))]
+    LongPress(Button, u64),
+}
+
+impl Token {
+    /// Expand a token with [`Direction::Click`] into a token with
+    /// [`Direction::Press`] followed by a token with [`Direction::Release`].
+    /// All other tokens are returned unchanged.
+    #[must_use]
+    pub fn expand(&self) -> Vec<Token> {
+        match self {
+            Token::Key(key, Direction::Click) => vec![
+                Token::Key(*key, Direction::Press),
+                Token::Key(*key, Direction::Release),
+            ],
+            Token::Raw(keycode, Direction::Click, time) => vec![
+                Token::Raw(*keycode, Direction::Press, *time),
+                Token::Raw(*keycode, Direction::Release, *time),
+            ],
+            Token::Button(button, Direction::Click) => vec![
+                Token::Button(*button, Direction::Press),
+                Token::Button(*button, Direction::Release),
+            ],
+            token => vec![token.clone()],
+        }
+    }
 }
 
 pub trait Agent
@@ -62,15 +97,75 @@ where
     /// Same as the individual functions. Have a look at [`InputResult`] for a
     /// list of possible errors
     fn execute(&mut self, token: &Token) -> InputResult<()> {
-        match token {
-            Token::Text(text) => self.text(text),
-            Token::Key(key, direction) => self.key(*key, *direction),
-            Token::Raw(keycode, direction) => self.raw(*keycode, *direction),
-            Token::Button(button, direction) => self.button(*button, *direction),
-            Token::MoveMouse(x, y, coordinate) => self.move_mouse(*x, *y, *coordinate),
-            Token::Scroll(length, axis) => self.scroll(*length, *axis),
+        for token in token.expand() {
+            execute_expanded(self, &token)?;
         }
+        Ok(())
     }
 }
 
 impl Agent for Enigo {}
+
+fn execute_expanded<A: Agent + ?Sized>(agent: &mut A, token: &Token) -> InputResult<()> {
+    match token {
+        Token::Text(text) => agent.text(text),
+        Token::Key(key, direction) => agent.key(*key, *direction),
+        Token::Raw(keycode, direction, None) => agent.raw(*keycode, *direction),
+        Token::Raw(keycode, direction, Some(time)) => agent.raw_at(*keycode, *direction, *time),
+        Token::Button(button, direction) => agent.button(*button, *direction),
+        Token::MoveMouse(x, y, coordinate) => agent.move_mouse(*x, *y, *coordinate),
+        Token::Scroll(length, axis) => agent.scroll(*length, *axis).map(|_| ()),
+        Token::LongPress(button, duration) => {
+            agent.long_press(*button, Duration::from_millis(*duration))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_key_click() {
+        assert_eq!(
+            Token::Key(Key::Unicode('a'), Direction::Click).expand(),
+            vec![
+                Token::Key(Key::Unicode('a'), Direction::Press),
+                Token::Key(Key::Unicode('a'), Direction::Release),
+            ]
+        );
+        assert_eq!(
+            Token::Raw(42, Direction::Click, Some(1000)).expand(),
+            vec![
+                Token::Raw(42, Direction::Press, Some(1000)),
+                Token::Raw(42, Direction::Release, Some(1000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_button_click() {
+        assert_eq!(
+            Token::Button(Button::Right, Direction::Click).expand(),
+            vec![
+                Token::Button(Button::Right, Direction::Press),
+                Token::Button(Button::Right, Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_leaves_other_tokens_unchanged() {
+        let tokens = [
+            Token::Key(Key::Shift, Direction::Press),
+            Token::Button(Button::Left, Direction::Release),
+            Token::MoveMouse(10, -5, Coordinate::Rel),
+            Token::Scroll(3, Axis::Horizontal),
+            Token::LongPress(Button::Left, 500),
+            Token::Text("hello".to_string()),
+        ];
+        for token in tokens {
+            assert_eq!(token.expand(), vec![token]);
+        }
+    }
+}
@@ -132,6 +132,10 @@ pub enum Key {
     /// command key on macOS (super key on Linux, windows key on Windows)
     #[cfg_attr(feature = "serde", serde(alias = "cmd"))]
     Command,
+    /// compose key (`Multi_key`) that starts a compose sequence
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[doc(alias = "Multi_key")]
+    Compose,
     #[cfg(target_os = "macos")]
     ContrastUp,
     #[cfg(target_os = "macos")]
@@ -181,6 +185,9 @@ pub enum Key {
     DownArrow,
     #[cfg(target_os = "macos")]
     Eject,
+    /// Opens the emoji picker. On Windows and macOS the shortcut for it is
+    /// entered (`Win+.` and `Ctrl+Cmd+Space`)
+    EmojiPicker,
     /// end key
     End,
     #[cfg(target_os = "windows")]
@@ -356,6 +363,11 @@ pub enum Key {
     LaunchApp1,
     #[cfg(target_os = "windows")]
     LaunchApp2,
+    /// Opens the assistant. On Windows the shortcut for it is entered
+    /// (`Win+C`). There is no key or shortcut for it on macOS, so an
+    /// [`InputError::Unsupported`](crate::InputError::Unsupported) is
+    /// returned there
+    LaunchAssistant,
     #[cfg(target_os = "windows")]
     LaunchMail,
     #[cfg(target_os = "windows")]
@@ -365,6 +377,9 @@ pub enum Key {
     Launchpad,
     #[cfg(target_os = "macos")]
     LaunchPanel,
+    /// Opens the search. On macOS the shortcut for Spotlight is entered
+    /// (`Cmd+Space`)
+    LaunchSearch,
     #[cfg(target_os = "windows")]
     LButton,
     LControl,
@@ -372,7 +387,13 @@ pub enum Key {
     LeftArrow,
     #[cfg(all(unix, not(target_os = "macos")))]
     Linefeed,
+    /// left alt key on Windows (`VK_LMENU`). On Linux it is the menu key,
+    /// which is why it is deprecated there
     #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    #[cfg_attr(
+        all(unix, not(target_os = "macos")),
+        deprecated(note = "use Key::Menu for the menu key")
+    )]
     LMenu,
     LShift,
     #[cfg(target_os = "windows")]
@@ -388,6 +409,9 @@ pub enum Key {
     MediaRewind,
     #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
     MediaStop,
+    /// menu key that opens the context menu (also known as the application
+    /// key)
+    Menu,
     /// meta key (also known as "windows", "super", and "command")
     Meta,
     #[cfg(target_os = "macos")]
@@ -441,6 +465,10 @@ pub enum Key {
     Numpad8,
     #[cfg(target_os = "windows")]
     Numpad9,
+    /// enter key on the numeric keypad. Applications can tell it apart from
+    /// [`Key::Return`]
+    #[doc(alias = "KP_Enter")]
+    NumpadEnter,
     #[cfg(target_os = "windows")]
     OEM1,
     #[cfg(target_os = "windows")]
@@ -523,7 +551,10 @@ pub enum Key {
     Pause,
     #[cfg(target_os = "windows")]
     Play,
-    #[cfg(target_os = "macos")]
+    /// power key. It is a system key, see [`Settings::allow_system_keys`]
+    ///
+    /// [`Settings::allow_system_keys`]: crate::Settings::allow_system_keys
+    #[cfg(any(target_os = "macos", all(unix, not(target_os = "macos"))))]
     Power,
     #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
     Print,
@@ -562,7 +593,10 @@ pub enum Key {
     #[cfg(all(unix, not(target_os = "macos")))]
     /// Lock shift key
     ShiftLock,
-    #[cfg(target_os = "windows")]
+    /// sleep key. It is a system key, see [`Settings::allow_system_keys`]
+    ///
+    /// [`Settings::allow_system_keys`]: crate::Settings::allow_system_keys
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
     Sleep,
     #[cfg(target_os = "windows")]
     Snapshot,
@@ -586,6 +620,11 @@ pub enum Key {
     VolumeDown,
     VolumeMute,
     VolumeUp,
+    /// wake up key. It is a system key, see [`Settings::allow_system_keys`]
+    ///
+    /// [`Settings::allow_system_keys`]: crate::Settings::allow_system_keys
+    #[cfg(all(unix, not(target_os = "macos")))]
+    WakeUp,
     #[cfg(all(unix, not(target_os = "macos")))]
     /// microphone mute toggle on linux
     MicMute,
@@ -611,9 +650,939 @@ pub enum Key {
     /// On Linux, this will result in a keysym,
     /// On Windows, this will result in a `Virtual_Key` and
     /// On macOS, this will yield a `KeyCode`
+    ///
+    /// On Linux any keysym can be entered this way, even the ones without a
+    /// name. If the keysym is not part of the current keymap, it gets mapped
+    /// just like a [`Key::Unicode`].
+    #[doc(alias = "RawKeysym")]
     Other(u32),
 }
 
+impl Key {
+    /// Check if the key triggers an action of the system (e.g. shutting it down
+    /// or putting it to sleep)
+    pub(crate) fn is_system_key(self) -> bool {
+        match self {
+            #[cfg(any(target_os = "macos", all(unix, not(target_os = "macos"))))]
+            Key::Power => true,
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Sleep => true,
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::WakeUp => true,
+            _ => false,
+        }
+    }
+
+    /// Return an error if the key is a char that cannot be entered, because it
+    /// has no keysym. These are the control characters that are not the char
+    /// of a key (e.g. backspace or escape) and the noncharacters of Unicode.
+    /// The null byte is fine, because entering it is a noop
+    pub(crate) fn check_enterable(self) -> crate::InputResult<()> {
+        let Key::Unicode(c) = self else {
+            return Ok(());
+        };
+        let is_key = matches!(c, '\0' | '\u{8}'..='\u{b}' | '\r' | '\u{1b}' | '\u{7f}');
+        let is_noncharacter =
+            matches!(c, '\u{fdd0}'..='\u{fdef}') || u32::from(c) & 0xfffe == 0xfffe;
+        if (c.is_control() && !is_key) || is_noncharacter {
+            trace!("the char {c:?} has no keysym");
+            return Err(crate::InputError::InvalidInput("character has no keysym"));
+        }
+        Ok(())
+    }
+
+    /// Check if the key is a modifier (e.g. Shift or Control)
+    #[allow(deprecated)]
+    pub(crate) fn is_modifier(self) -> bool {
+        match self {
+            Key::Alt
+            | Key::Command
+            | Key::Control
+            | Key::LControl
+            | Key::LShift
+            | Key::Meta
+            | Key::Option
+            | Key::RControl
+            | Key::RShift
+            | Key::Shift
+            | Key::Super
+            | Key::Windows => true,
+            #[cfg(target_os = "macos")]
+            Key::Function | Key::RCommand | Key::ROption => true,
+            #[cfg(target_os = "windows")]
+            Key::LMenu | Key::LWin | Key::RMenu | Key::RWin => true,
+            _ => false,
+        }
+    }
+
+    /// Get the value of the `code` property of a web `KeyboardEvent` that a
+    /// browser reports for the key (e.g. `ControlLeft` for [`Key::Control`]).
+    /// The code identifies the physical key, so the keys that depend on the
+    /// layout (e.g. `Key::OEM1` on Windows) get the code of their position on
+    /// a US layout. `None` is returned for keys without a code and for
+    /// [`Key::Unicode`] and [`Key::Other`], because their physical key is
+    /// not known
+    #[must_use]
+    #[allow(deprecated, clippy::too_many_lines, clippy::match_same_arms)]
+    pub fn web_code(self) -> Option<&'static str> {
+        let code = match self {
+            #[cfg(target_os = "windows")]
+            Key::Num0 => "Digit0",
+            #[cfg(target_os = "windows")]
+            Key::Num1 => "Digit1",
+            #[cfg(target_os = "windows")]
+            Key::Num2 => "Digit2",
+            #[cfg(target_os = "windows")]
+            Key::Num3 => "Digit3",
+            #[cfg(target_os = "windows")]
+            Key::Num4 => "Digit4",
+            #[cfg(target_os = "windows")]
+            Key::Num5 => "Digit5",
+            #[cfg(target_os = "windows")]
+            Key::Num6 => "Digit6",
+            #[cfg(target_os = "windows")]
+            Key::Num7 => "Digit7",
+            #[cfg(target_os = "windows")]
+            Key::Num8 => "Digit8",
+            #[cfg(target_os = "windows")]
+            Key::Num9 => "Digit9",
+            #[cfg(target_os = "windows")]
+            Key::A => "KeyA",
+            #[cfg(target_os = "windows")]
+            Key::B => "KeyB",
+            #[cfg(target_os = "windows")]
+            Key::C => "KeyC",
+            #[cfg(target_os = "windows")]
+            Key::D => "KeyD",
+            #[cfg(target_os = "windows")]
+            Key::E => "KeyE",
+            #[cfg(target_os = "windows")]
+            Key::F => "KeyF",
+            #[cfg(target_os = "windows")]
+            Key::G => "KeyG",
+            #[cfg(target_os = "windows")]
+            Key::H => "KeyH",
+            #[cfg(target_os = "windows")]
+            Key::I => "KeyI",
+            #[cfg(target_os = "windows")]
+            Key::J => "KeyJ",
+            #[cfg(target_os = "windows")]
+            Key::K => "KeyK",
+            #[cfg(target_os = "windows")]
+            Key::L => "KeyL",
+            #[cfg(target_os = "windows")]
+            Key::M => "KeyM",
+            #[cfg(target_os = "windows")]
+            Key::N => "KeyN",
+            #[cfg(target_os = "windows")]
+            Key::O => "KeyO",
+            #[cfg(target_os = "windows")]
+            Key::P => "KeyP",
+            #[cfg(target_os = "windows")]
+            Key::Q => "KeyQ",
+            #[cfg(target_os = "windows")]
+            Key::R => "KeyR",
+            #[cfg(target_os = "windows")]
+            Key::S => "KeyS",
+            #[cfg(target_os = "windows")]
+            Key::T => "KeyT",
+            #[cfg(target_os = "windows")]
+            Key::U => "KeyU",
+            #[cfg(target_os = "windows")]
+            Key::V => "KeyV",
+            #[cfg(target_os = "windows")]
+            Key::W => "KeyW",
+            #[cfg(target_os = "windows")]
+            Key::X => "KeyX",
+            #[cfg(target_os = "windows")]
+            Key::Y => "KeyY",
+            #[cfg(target_os = "windows")]
+            Key::Z => "KeyZ",
+            #[cfg(target_os = "windows")]
+            Key::Add => "NumpadAdd",
+            Key::Alt | Key::Option => "AltLeft",
+            #[cfg(target_os = "windows")]
+            Key::Apps => "ContextMenu",
+            Key::Backspace => "Backspace",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::Break => "Pause",
+            #[cfg(target_os = "windows")]
+            Key::BrowserBack => "BrowserBack",
+            #[cfg(target_os = "windows")]
+            Key::BrowserFavorites => "BrowserFavorites",
+            #[cfg(target_os = "windows")]
+            Key::BrowserForward => "BrowserForward",
+            #[cfg(target_os = "windows")]
+            Key::BrowserHome => "BrowserHome",
+            #[cfg(target_os = "windows")]
+            Key::BrowserRefresh => "BrowserRefresh",
+            #[cfg(target_os = "windows")]
+            Key::BrowserSearch => "BrowserSearch",
+            #[cfg(target_os = "windows")]
+            Key::BrowserStop => "BrowserStop",
+            Key::CapsLock => "CapsLock",
+            Key::Command | Key::Meta | Key::Super | Key::Windows => "MetaLeft",
+            Key::Control | Key::LControl => "ControlLeft",
+            #[cfg(target_os = "windows")]
+            Key::Convert => "Convert",
+            #[cfg(target_os = "windows")]
+            Key::Decimal => "NumpadDecimal",
+            Key::Delete => "Delete",
+            #[cfg(target_os = "windows")]
+            Key::Divide => "NumpadDivide",
+            Key::DownArrow => "ArrowDown",
+            #[cfg(target_os = "macos")]
+            Key::Eject => "Eject",
+            Key::End => "End",
+            Key::Escape => "Escape",
+            Key::F1 => "F1",
+            Key::F2 => "F2",
+            Key::F3 => "F3",
+            Key::F4 => "F4",
+            Key::F5 => "F5",
+            Key::F6 => "F6",
+            Key::F7 => "F7",
+            Key::F8 => "F8",
+            Key::F9 => "F9",
+            Key::F10 => "F10",
+            Key::F11 => "F11",
+            Key::F12 => "F12",
+            Key::F13 => "F13",
+            Key::F14 => "F14",
+            Key::F15 => "F15",
+            Key::F16 => "F16",
+            Key::F17 => "F17",
+            Key::F18 => "F18",
+            Key::F19 => "F19",
+            Key::F20 => "F20",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::F21 => "F21",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::F22 => "F22",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::F23 => "F23",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::F24 => "F24",
+            #[cfg(target_os = "macos")]
+            Key::Function => "Fn",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::Find => "Find",
+            #[cfg(target_os = "windows")]
+            Key::Hangeul => "Lang1",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Hangul => "Lang1",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Hanja => "Lang2",
+            Key::Help => "Help",
+            Key::Home => "Home",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Insert => "Insert",
+            #[cfg(target_os = "windows")]
+            Key::Kana => "KanaMode",
+            #[cfg(target_os = "windows")]
+            Key::LaunchApp1 => "LaunchApp1",
+            #[cfg(target_os = "windows")]
+            Key::LaunchApp2 => "LaunchApp2",
+            #[cfg(target_os = "windows")]
+            Key::LaunchMail => "LaunchMail",
+            #[cfg(target_os = "windows")]
+            Key::LaunchMediaSelect => "MediaSelect",
+            Key::LeftArrow => "ArrowLeft",
+            #[cfg(target_os = "windows")]
+            Key::LMenu => "AltLeft",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::LMenu => "ContextMenu",
+            Key::LShift | Key::Shift => "ShiftLeft",
+            #[cfg(target_os = "windows")]
+            Key::LWin => "MetaLeft",
+            Key::MediaNextTrack => "MediaTrackNext",
+            Key::MediaPlayPause => "MediaPlayPause",
+            Key::MediaPrevTrack => "MediaTrackPrevious",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::MediaStop => "MediaStop",
+            Key::Menu => "ContextMenu",
+            #[cfg(target_os = "windows")]
+            Key::Multiply => "NumpadMultiply",
+            #[cfg(target_os = "windows")]
+            Key::NonConvert => "NonConvert",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Numlock => "NumLock",
+            #[cfg(target_os = "windows")]
+            Key::Numpad0 => "Numpad0",
+            #[cfg(target_os = "windows")]
+            Key::Numpad1 => "Numpad1",
+            #[cfg(target_os = "windows")]
+            Key::Numpad2 => "Numpad2",
+            #[cfg(target_os = "windows")]
+            Key::Numpad3 => "Numpad3",
+            #[cfg(target_os = "windows")]
+            Key::Numpad4 => "Numpad4",
+            #[cfg(target_os = "windows")]
+            Key::Numpad5 => "Numpad5",
+            #[cfg(target_os = "windows")]
+            Key::Numpad6 => "Numpad6",
+            #[cfg(target_os = "windows")]
+            Key::Numpad7 => "Numpad7",
+            #[cfg(target_os = "windows")]
+            Key::Numpad8 => "Numpad8",
+            #[cfg(target_os = "windows")]
+            Key::Numpad9 => "Numpad9",
+            Key::NumpadEnter => "NumpadEnter",
+            #[cfg(target_os = "windows")]
+            Key::OEM1 => "Semicolon",
+            #[cfg(target_os = "windows")]
+            Key::OEM102 => "IntlBackslash",
+            #[cfg(target_os = "windows")]
+            Key::OEM2 => "Slash",
+            #[cfg(target_os = "windows")]
+            Key::OEM3 => "Backquote",
+            #[cfg(target_os = "windows")]
+            Key::OEM4 => "BracketLeft",
+            #[cfg(target_os = "windows")]
+            Key::OEM5 => "Backslash",
+            #[cfg(target_os = "windows")]
+            Key::OEM6 => "BracketRight",
+            #[cfg(target_os = "windows")]
+            Key::OEM7 => "Quote",
+            #[cfg(target_os = "windows")]
+            Key::OEMComma => "Comma",
+            #[cfg(target_os = "windows")]
+            Key::OEMMinus => "Minus",
+            #[cfg(target_os = "windows")]
+            Key::OEMPeriod => "Period",
+            #[cfg(target_os = "windows")]
+            Key::OEMPlus => "Equal",
+            Key::PageDown => "PageDown",
+            Key::PageUp => "PageUp",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Pause => "Pause",
+            #[cfg(any(target_os = "macos", all(unix, not(target_os = "macos"))))]
+            Key::Power => "Power",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Print => "PrintScreen",
+            #[cfg(target_os = "macos")]
+            Key::RCommand => "MetaRight",
+            Key::RControl => "ControlRight",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::Redo => "Again",
+            Key::Return => "Enter",
+            Key::RightArrow => "ArrowRight",
+            #[cfg(target_os = "windows")]
+            Key::RMenu => "AltRight",
+            #[cfg(target_os = "macos")]
+            Key::ROption => "AltRight",
+            Key::RShift => "ShiftRight",
+            #[cfg(target_os = "windows")]
+            Key::RWin => "MetaRight",
+            #[cfg(target_os = "windows")]
+            Key::Scroll => "ScrollLock",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::ScrollLock => "ScrollLock",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Select => "Select",
+            #[cfg(target_os = "windows")]
+            Key::Separator => "NumpadComma",
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Key::Sleep => "Sleep",
+            #[cfg(target_os = "windows")]
+            Key::Snapshot => "PrintScreen",
+            Key::Space => "Space",
+            #[cfg(target_os = "windows")]
+            Key::Subtract => "NumpadSubtract",
+            Key::Tab => "Tab",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::Undo => "Undo",
+            Key::UpArrow => "ArrowUp",
+            Key::VolumeDown => "AudioVolumeDown",
+            Key::VolumeMute => "AudioVolumeMute",
+            Key::VolumeUp => "AudioVolumeUp",
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Key::WakeUp => "WakeUp",
+            _ => return None,
+        };
+        Some(code)
+    }
+
+    /// Get the key from its name in the notation of shortcuts (e.g. "Ctrl" or
+    /// "F5"). The names are case insensitive and common aliases of the
+    /// modifiers on the different platforms are supported. A single
+    /// character is the key of that character
+    pub(crate) fn from_shortcut_name(name: &str) -> Option<Key> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(Key::Unicode(c.to_ascii_lowercase()));
+        }
+        let key = match name.to_ascii_lowercase().as_str() {
+            "ctrl" => Key::Control,
+            "cmd" | "command" | "win" | "windows" | "super" => Key::Meta,
+            "opt" | "option" => Key::Alt,
+            "esc" => Key::Escape,
+            "del" => Key::Delete,
+            "enter" => Key::Return,
+            // The plus sign separates the keys, so it needs a name
+            "plus" => Key::Unicode('+'),
+            name => {
+                return NAMED_KEYS
+                    .iter()
+                    .copied()
+                    .find(|key| format!("{key:?}").eq_ignore_ascii_case(name))
+            }
+        };
+        Some(key)
+    }
+
+    /// Return an error if the key is a system key and they are not allowed
+    pub(crate) fn check_system_key(self, allowed: bool) -> crate::InputResult<()> {
+        if self.is_system_key() && !allowed {
+            return Err(crate::InputError::InvalidInput(
+                "system keys are only entered if Settings::allow_system_keys is set",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// All keys without any data that are available on the platform, except the
+/// deprecated ones
+pub(crate) const NAMED_KEYS: &[Key] = &[
+    #[cfg(target_os = "windows")]
+    Key::Num0,
+    #[cfg(target_os = "windows")]
+    Key::Num1,
+    #[cfg(target_os = "windows")]
+    Key::Num2,
+    #[cfg(target_os = "windows")]
+    Key::Num3,
+    #[cfg(target_os = "windows")]
+    Key::Num4,
+    #[cfg(target_os = "windows")]
+    Key::Num5,
+    #[cfg(target_os = "windows")]
+    Key::Num6,
+    #[cfg(target_os = "windows")]
+    Key::Num7,
+    #[cfg(target_os = "windows")]
+    Key::Num8,
+    #[cfg(target_os = "windows")]
+    Key::Num9,
+    #[cfg(target_os = "windows")]
+    Key::A,
+    #[cfg(target_os = "windows")]
+    Key::B,
+    #[cfg(target_os = "windows")]
+    Key::C,
+    #[cfg(target_os = "windows")]
+    Key::D,
+    #[cfg(target_os = "windows")]
+    Key::E,
+    #[cfg(target_os = "windows")]
+    Key::F,
+    #[cfg(target_os = "windows")]
+    Key::G,
+    #[cfg(target_os = "windows")]
+    Key::H,
+    #[cfg(target_os = "windows")]
+    Key::I,
+    #[cfg(target_os = "windows")]
+    Key::J,
+    #[cfg(target_os = "windows")]
+    Key::K,
+    #[cfg(target_os = "windows")]
+    Key::L,
+    #[cfg(target_os = "windows")]
+    Key::M,
+    #[cfg(target_os = "windows")]
+    Key::N,
+    #[cfg(target_os = "windows")]
+    Key::O,
+    #[cfg(target_os = "windows")]
+    Key::P,
+    #[cfg(target_os = "windows")]
+    Key::Q,
+    #[cfg(target_os = "windows")]
+    Key::R,
+    #[cfg(target_os = "windows")]
+    Key::S,
+    #[cfg(target_os = "windows")]
+    Key::T,
+    #[cfg(target_os = "windows")]
+    Key::U,
+    #[cfg(target_os = "windows")]
+    Key::V,
+    #[cfg(target_os = "windows")]
+    Key::W,
+    #[cfg(target_os = "windows")]
+    Key::X,
+    #[cfg(target_os = "windows")]
+    Key::Y,
+    #[cfg(target_os = "windows")]
+    Key::Z,
+    #[cfg(target_os = "windows")]
+    Key::AbntC1,
+    #[cfg(target_os = "windows")]
+    Key::AbntC2,
+    #[cfg(target_os = "windows")]
+    Key::Accept,
+    #[cfg(target_os = "windows")]
+    Key::Add,
+    Key::Alt,
+    #[cfg(target_os = "windows")]
+    Key::Apps,
+    #[cfg(target_os = "windows")]
+    Key::Attn,
+    Key::Backspace,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Break,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Begin,
+    #[cfg(target_os = "macos")]
+    Key::BrightnessDown,
+    #[cfg(target_os = "macos")]
+    Key::BrightnessUp,
+    #[cfg(target_os = "windows")]
+    Key::BrowserBack,
+    #[cfg(target_os = "windows")]
+    Key::BrowserFavorites,
+    #[cfg(target_os = "windows")]
+    Key::BrowserForward,
+    #[cfg(target_os = "windows")]
+    Key::BrowserHome,
+    #[cfg(target_os = "windows")]
+    Key::BrowserRefresh,
+    #[cfg(target_os = "windows")]
+    Key::BrowserSearch,
+    #[cfg(target_os = "windows")]
+    Key::BrowserStop,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Cancel,
+    Key::CapsLock,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Clear,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Compose,
+    #[cfg(target_os = "macos")]
+    Key::ContrastUp,
+    #[cfg(target_os = "macos")]
+    Key::ContrastDown,
+    Key::Control,
+    #[cfg(target_os = "windows")]
+    Key::Convert,
+    #[cfg(target_os = "windows")]
+    Key::Crsel,
+    #[cfg(target_os = "windows")]
+    Key::DBEAlphanumeric,
+    #[cfg(target_os = "windows")]
+    Key::DBECodeinput,
+    #[cfg(target_os = "windows")]
+    Key::DBEDetermineString,
+    #[cfg(target_os = "windows")]
+    Key::DBEEnterDLGConversionMode,
+    #[cfg(target_os = "windows")]
+    Key::DBEEnterIMEConfigMode,
+    #[cfg(target_os = "windows")]
+    Key::DBEEnterWordRegisterMode,
+    #[cfg(target_os = "windows")]
+    Key::DBEFlushString,
+    #[cfg(target_os = "windows")]
+    Key::DBEHiragana,
+    #[cfg(target_os = "windows")]
+    Key::DBEKatakana,
+    #[cfg(target_os = "windows")]
+    Key::DBENoCodepoint,
+    #[cfg(target_os = "windows")]
+    Key::DBENoRoman,
+    #[cfg(target_os = "windows")]
+    Key::DBERoman,
+    #[cfg(target_os = "windows")]
+    Key::DBESBCSChar,
+    #[cfg(target_os = "windows")]
+    Key::DBESChar,
+    #[cfg(target_os = "windows")]
+    Key::Decimal,
+    Key::Delete,
+    #[cfg(target_os = "windows")]
+    Key::Divide,
+    Key::DownArrow,
+    #[cfg(target_os = "macos")]
+    Key::Eject,
+    Key::EmojiPicker,
+    Key::End,
+    #[cfg(target_os = "windows")]
+    Key::Ereof,
+    Key::Escape,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Execute,
+    #[cfg(target_os = "windows")]
+    Key::Exsel,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::F21,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::F22,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::F23,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::F24,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F25,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F26,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F27,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F28,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F29,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F30,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F31,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F32,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F33,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F34,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::F35,
+    #[cfg(target_os = "macos")]
+    Key::Function,
+    #[cfg(target_os = "windows")]
+    Key::Final,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Find,
+    #[cfg(target_os = "windows")]
+    Key::GamepadA,
+    #[cfg(target_os = "windows")]
+    Key::GamepadB,
+    #[cfg(target_os = "windows")]
+    Key::GamepadDPadDown,
+    #[cfg(target_os = "windows")]
+    Key::GamepadDPadLeft,
+    #[cfg(target_os = "windows")]
+    Key::GamepadDPadRight,
+    #[cfg(target_os = "windows")]
+    Key::GamepadDPadUp,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftShoulder,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftThumbstickButton,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftThumbstickDown,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftThumbstickLeft,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftThumbstickRight,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftThumbstickUp,
+    #[cfg(target_os = "windows")]
+    Key::GamepadLeftTrigger,
+    #[cfg(target_os = "windows")]
+    Key::GamepadMenu,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightShoulder,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightThumbstickButton,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightThumbstickDown,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightThumbstickLeft,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightThumbstickRight,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightThumbstickUp,
+    #[cfg(target_os = "windows")]
+    Key::GamepadRightTrigger,
+    #[cfg(target_os = "windows")]
+    Key::GamepadView,
+    #[cfg(target_os = "windows")]
+    Key::GamepadX,
+    #[cfg(target_os = "windows")]
+    Key::GamepadY,
+    #[cfg(target_os = "windows")]
+    Key::Hangeul,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Hangul,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Hanja,
+    Key::Help,
+    Key::Home,
+    #[cfg(target_os = "windows")]
+    Key::Ico00,
+    #[cfg(target_os = "windows")]
+    Key::IcoClear,
+    #[cfg(target_os = "windows")]
+    Key::IcoHelp,
+    #[cfg(target_os = "macos")]
+    Key::IlluminationDown,
+    #[cfg(target_os = "macos")]
+    Key::IlluminationUp,
+    #[cfg(target_os = "macos")]
+    Key::IlluminationToggle,
+    #[cfg(target_os = "windows")]
+    Key::IMEOff,
+    #[cfg(target_os = "windows")]
+    Key::IMEOn,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Insert,
+    #[cfg(target_os = "windows")]
+    Key::Junja,
+    #[cfg(target_os = "windows")]
+    Key::Kana,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Kanji,
+    #[cfg(target_os = "windows")]
+    Key::LaunchApp1,
+    #[cfg(target_os = "windows")]
+    Key::LaunchApp2,
+    Key::LaunchAssistant,
+    #[cfg(target_os = "windows")]
+    Key::LaunchMail,
+    #[cfg(target_os = "windows")]
+    Key::LaunchMediaSelect,
+    #[cfg(target_os = "macos")]
+    Key::Launchpad,
+    #[cfg(target_os = "macos")]
+    Key::LaunchPanel,
+    Key::LaunchSearch,
+    #[cfg(target_os = "windows")]
+    Key::LButton,
+    Key::LControl,
+    Key::LeftArrow,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Linefeed,
+    #[cfg(target_os = "windows")]
+    Key::LMenu,
+    Key::LShift,
+    #[cfg(target_os = "windows")]
+    Key::LWin,
+    #[cfg(target_os = "windows")]
+    Key::MButton,
+    #[cfg(target_os = "macos")]
+    Key::MediaFast,
+    Key::MediaNextTrack,
+    Key::MediaPlayPause,
+    Key::MediaPrevTrack,
+    #[cfg(target_os = "macos")]
+    Key::MediaRewind,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::MediaStop,
+    Key::Menu,
+    Key::Meta,
+    #[cfg(target_os = "macos")]
+    Key::MissionControl,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::ModeChange,
+    #[cfg(target_os = "windows")]
+    Key::Multiply,
+    #[cfg(target_os = "windows")]
+    Key::NavigationAccept,
+    #[cfg(target_os = "windows")]
+    Key::NavigationCancel,
+    #[cfg(target_os = "windows")]
+    Key::NavigationDown,
+    #[cfg(target_os = "windows")]
+    Key::NavigationLeft,
+    #[cfg(target_os = "windows")]
+    Key::NavigationMenu,
+    #[cfg(target_os = "windows")]
+    Key::NavigationRight,
+    #[cfg(target_os = "windows")]
+    Key::NavigationUp,
+    #[cfg(target_os = "windows")]
+    Key::NavigationView,
+    #[cfg(target_os = "windows")]
+    Key::NoName,
+    #[cfg(target_os = "windows")]
+    Key::NonConvert,
+    #[cfg(target_os = "windows")]
+    Key::None,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Numlock,
+    #[cfg(target_os = "windows")]
+    Key::Numpad0,
+    #[cfg(target_os = "windows")]
+    Key::Numpad1,
+    #[cfg(target_os = "windows")]
+    Key::Numpad2,
+    #[cfg(target_os = "windows")]
+    Key::Numpad3,
+    #[cfg(target_os = "windows")]
+    Key::Numpad4,
+    #[cfg(target_os = "windows")]
+    Key::Numpad5,
+    #[cfg(target_os = "windows")]
+    Key::Numpad6,
+    #[cfg(target_os = "windows")]
+    Key::Numpad7,
+    #[cfg(target_os = "windows")]
+    Key::Numpad8,
+    #[cfg(target_os = "windows")]
+    Key::Numpad9,
+    Key::NumpadEnter,
+    #[cfg(target_os = "windows")]
+    Key::OEM1,
+    #[cfg(target_os = "windows")]
+    Key::OEM102,
+    #[cfg(target_os = "windows")]
+    Key::OEM2,
+    #[cfg(target_os = "windows")]
+    Key::OEM3,
+    #[cfg(target_os = "windows")]
+    Key::OEM4,
+    #[cfg(target_os = "windows")]
+    Key::OEM5,
+    #[cfg(target_os = "windows")]
+    Key::OEM6,
+    #[cfg(target_os = "windows")]
+    Key::OEM7,
+    #[cfg(target_os = "windows")]
+    Key::OEM8,
+    #[cfg(target_os = "windows")]
+    Key::OEMAttn,
+    #[cfg(target_os = "windows")]
+    Key::OEMAuto,
+    #[cfg(target_os = "windows")]
+    Key::OEMAx,
+    #[cfg(target_os = "windows")]
+    Key::OEMBacktab,
+    #[cfg(target_os = "windows")]
+    Key::OEMClear,
+    #[cfg(target_os = "windows")]
+    Key::OEMComma,
+    #[cfg(target_os = "windows")]
+    Key::OEMCopy,
+    #[cfg(target_os = "windows")]
+    Key::OEMCusel,
+    #[cfg(target_os = "windows")]
+    Key::OEMEnlw,
+    #[cfg(target_os = "windows")]
+    Key::OEMFinish,
+    #[cfg(target_os = "windows")]
+    Key::OEMFJJisho,
+    #[cfg(target_os = "windows")]
+    Key::OEMFJLoya,
+    #[cfg(target_os = "windows")]
+    Key::OEMFJMasshou,
+    #[cfg(target_os = "windows")]
+    Key::OEMFJRoya,
+    #[cfg(target_os = "windows")]
+    Key::OEMFJTouroku,
+    #[cfg(target_os = "windows")]
+    Key::OEMJump,
+    #[cfg(target_os = "windows")]
+    Key::OEMMinus,
+    #[cfg(target_os = "windows")]
+    Key::OEMNECEqual,
+    #[cfg(target_os = "windows")]
+    Key::OEMPA1,
+    #[cfg(target_os = "windows")]
+    Key::OEMPA2,
+    #[cfg(target_os = "windows")]
+    Key::OEMPA3,
+    #[cfg(target_os = "windows")]
+    Key::OEMPeriod,
+    #[cfg(target_os = "windows")]
+    Key::OEMPlus,
+    #[cfg(target_os = "windows")]
+    Key::OEMReset,
+    #[cfg(target_os = "windows")]
+    Key::OEMWsctrl,
+    Key::Option,
+    #[cfg(target_os = "windows")]
+    Key::PA1,
+    #[cfg(target_os = "windows")]
+    Key::Packet,
+    Key::PageDown,
+    Key::PageUp,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Pause,
+    #[cfg(target_os = "windows")]
+    Key::Play,
+    #[cfg(any(target_os = "macos", all(unix, not(target_os = "macos"))))]
+    Key::Power,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Print,
+    #[cfg(target_os = "windows")]
+    Key::Processkey,
+    #[cfg(target_os = "windows")]
+    Key::RButton,
+    #[cfg(target_os = "macos")]
+    Key::RCommand,
+    Key::RControl,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Redo,
+    Key::Return,
+    Key::RightArrow,
+    #[cfg(target_os = "windows")]
+    Key::RMenu,
+    #[cfg(target_os = "macos")]
+    Key::ROption,
+    Key::RShift,
+    #[cfg(target_os = "windows")]
+    Key::RWin,
+    #[cfg(target_os = "windows")]
+    Key::Scroll,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::ScrollLock,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Select,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::ScriptSwitch,
+    #[cfg(target_os = "windows")]
+    Key::Separator,
+    Key::Shift,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::ShiftLock,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Key::Sleep,
+    #[cfg(target_os = "windows")]
+    Key::Snapshot,
+    Key::Space,
+    #[cfg(target_os = "windows")]
+    Key::Subtract,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::SysReq,
+    Key::Tab,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::Undo,
+    Key::UpArrow,
+    #[cfg(target_os = "macos")]
+    Key::VidMirror,
+    Key::VolumeDown,
+    Key::VolumeMute,
+    Key::VolumeUp,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::WakeUp,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    Key::MicMute,
+    #[cfg(target_os = "windows")]
+    Key::XButton1,
+    #[cfg(target_os = "windows")]
+    Key::XButton2,
+    #[cfg(target_os = "windows")]
+    Key::Zoom,
+];
+
 #[cfg(all(unix, not(target_os = "macos")))]
 /// Converts a Key to a Keysym
 impl From<Key> for xkeysym::Keysym {
@@ -633,6 +1602,7 @@ impl From<Key> for xkeysym::Keysym {
             Key::Cancel => Keysym::Cancel,
             Key::CapsLock => Keysym::Caps_Lock,
             Key::Clear => Keysym::Clear,
+            Key::Compose => Keysym::Multi_key,
             Key::Control | Key::LControl => Keysym::Control_L,
             Key::Delete => Keysym::Delete,
             Key::DownArrow => Keysym::Down,
@@ -678,21 +1648,26 @@ impl From<Key> for xkeysym::Keysym {
             Key::Hangul => Keysym::Hangul,
             Key::Hanja => Keysym::Hangul_Hanja,
             Key::Help => Keysym::Help,
+            Key::EmojiPicker => Keysym::XF86_EmojiPicker,
+            Key::LaunchAssistant => Keysym::XF86_Assistant,
+            Key::LaunchSearch => Keysym::XF86_Search,
             Key::Home => Keysym::Home,
             Key::Insert => Keysym::Insert,
             Key::Kanji => Keysym::Kanji,
             Key::LeftArrow => Keysym::Left,
             Key::Linefeed => Keysym::Linefeed,
-            Key::LMenu => Keysym::Menu,
+            Key::LMenu | Key::Menu => Keysym::Menu,
             Key::ModeChange => Keysym::Mode_switch,
             Key::MediaNextTrack => Keysym::XF86_AudioNext,
             Key::MediaPlayPause => Keysym::XF86_AudioPlay,
             Key::MediaPrevTrack => Keysym::XF86_AudioPrev,
             Key::MediaStop => Keysym::XF86_AudioStop,
             Key::Numlock => Keysym::Num_Lock,
+            Key::NumpadEnter => Keysym::KP_Enter,
             Key::PageDown => Keysym::Page_Down,
             Key::PageUp => Keysym::Page_Up,
             Key::Pause => Keysym::Pause,
+            Key::Power => Keysym::XF86_PowerOff,
             Key::Print => Keysym::Print,
             Key::RControl => Keysym::Control_R,
             Key::Redo => Keysym::Redo,
@@ -704,6 +1679,7 @@ impl From<Key> for xkeysym::Keysym {
             Key::ScriptSwitch => Keysym::script_switch,
             Key::Shift | Key::LShift => Keysym::Shift_L,
             Key::ShiftLock => Keysym::Shift_Lock,
+            Key::Sleep => Keysym::XF86_Sleep,
             Key::Space => Keysym::space,
             Key::SysReq => Keysym::Sys_Req,
             Key::Tab => Keysym::Tab,
@@ -712,6 +1688,7 @@ impl From<Key> for xkeysym::Keysym {
             Key::VolumeDown => Keysym::XF86_AudioLowerVolume,
             Key::VolumeUp => Keysym::XF86_AudioRaiseVolume,
             Key::VolumeMute => Keysym::XF86_AudioMute,
+            Key::WakeUp => Keysym::XF86_WakeUp,
             Key::MicMute => Keysym::XF86_AudioMicMute,
             Key::Command | Key::Super | Key::Windows | Key::Meta => Keysym::Super_L,
             Key::Other(v) => Keysym::from(v),
@@ -719,6 +1696,27 @@ impl From<Key> for xkeysym::Keysym {
     }
 }
 
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+impl Key {
+    /// Some keys do not exist on the platform, but there is a shortcut that
+    /// does the same. Returns the modifiers and the key of the shortcut
+    pub(crate) fn shortcut(self) -> Option<(&'static [Key], Key)> {
+        #[cfg(target_os = "windows")]
+        match self {
+            Key::EmojiPicker => return Some((&[Key::Meta], Key::Unicode('.'))),
+            Key::LaunchAssistant => return Some((&[Key::Meta], Key::Unicode('c'))),
+            _ => {}
+        }
+        #[cfg(target_os = "macos")]
+        match self {
+            Key::EmojiPicker => return Some((&[Key::Control, Key::Meta], Key::Space)),
+            Key::LaunchSearch => return Some((&[Key::Meta], Key::Space)),
+            _ => {}
+        }
+        None
+    }
+}
+
 /// Converts a Key to a Virtual Key
 #[cfg(target_os = "windows")]
 impl TryFrom<Key> for windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY {
@@ -813,7 +1811,7 @@ impl TryFrom<Key> for windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY {
             Key::Accept => VK_ACCEPT,
             Key::Add => VK_ADD,
             Key::Alt | Key::Option => VK_MENU,
-            Key::Apps => VK_APPS,
+            Key::Apps | Key::Menu => VK_APPS,
             Key::Attn => VK_ATTN,
             Key::Backspace => VK_BACK,
             Key::BrowserBack => VK_BROWSER_BACK,
@@ -918,6 +1916,10 @@ impl TryFrom<Key> for windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY {
             Key::LaunchApp1 => VK_LAUNCH_APP1,
             Key::LaunchApp2 => VK_LAUNCH_APP2,
             Key::LaunchMail => VK_LAUNCH_MAIL,
+            Key::LaunchSearch => VK_BROWSER_SEARCH,
+            Key::EmojiPicker | Key::LaunchAssistant => {
+                return Err("this key has to be entered as a shortcut")
+            }
             Key::LaunchMediaSelect => VK_LAUNCH_MEDIA_SELECT,
             Key::LButton => VK_LBUTTON,
             Key::LControl => VK_LCONTROL,
@@ -953,6 +1955,8 @@ impl TryFrom<Key> for windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY {
             Key::Numpad7 => VK_NUMPAD7,
             Key::Numpad8 => VK_NUMPAD8,
             Key::Numpad9 => VK_NUMPAD9,
+            // The extended flag is added when the key is entered
+            Key::NumpadEnter => VK_RETURN,
             Key::OEM1 => VK_OEM_1,
             Key::OEM102 => VK_OEM_102,
             Key::OEM2 => VK_OEM_2,
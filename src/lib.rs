@@ -48,11 +48,14 @@
 #![allow(deprecated)]
 
 use std::{
+    borrow::Cow,
     error::Error,
     fmt::{self, Display, Formatter},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
 };
 
-use log::{debug, error};
+use log::{debug, error, warn};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -73,6 +76,9 @@ pub use platform::Enigo;
 #[cfg(target_os = "windows")]
 pub use platform::EXT;
 
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use platform::{PointerAcceleration, Stats, X11Device};
+
 mod keycodes;
 /// Contains the available keycodes
 pub use keycodes::Key;
@@ -80,6 +86,14 @@ pub use keycodes::Key;
 /// Arbitrary value to be able to distinguish events created by enigo
 pub const EVENT_MARKER: u32 = 100;
 
+/// Degrees a standard scroll wheel rotates per notch. It is the continuous
+/// value of the scroll events on Wayland
+pub const SCROLL_DEGREES_PER_NOTCH: f64 = 15.0;
+
+/// High resolution value of one notch of a scroll wheel. It is the
+/// `WHEEL_DELTA` of the scroll events on Windows
+pub const SCROLL_VALUE120_PER_NOTCH: i32 = 120;
+
 /// Represents a mouse button and is used in e.g
 /// [`Mouse::button`].
 
@@ -87,7 +101,7 @@ pub const EVENT_MARKER: u32 = 100;
 // need to change the size of the array in the macOS implementation of the Enigo
 // struct that stores the nth click for each Button
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[doc(alias = "MouseButton")]
 pub enum Button {
     /// Left mouse button
@@ -137,12 +151,78 @@ pub enum Button {
     ScrollRight,
 }
 
-impl fmt::Debug for Enigo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Enigo")
+impl Button {
+    /// Get the number that browsers use for the button in the `button` field
+    /// of a `MouseEvent`. The values are stable and do not depend on the order
+    /// of the variants:
+    ///
+    /// | Button      | Value |
+    /// |-------------|-------|
+    /// | `Left`      | 0     |
+    /// | `Middle`    | 1     |
+    /// | `Right`     | 2     |
+    /// | `Back`      | 3     |
+    /// | `Forward`   | 4     |
+    ///
+    /// The scroll buttons do not cause a `MouseEvent`, so `None` is returned
+    /// for them. `Back` and `Forward` do not exist on macOS
+    #[must_use]
+    pub fn to_web_button(&self) -> Option<u32> {
+        match self {
+            Button::Left => Some(0),
+            Button::Middle => Some(1),
+            Button::Right => Some(2),
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Button::Back => Some(3),
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            Button::Forward => Some(4),
+            Button::ScrollUp | Button::ScrollDown | Button::ScrollLeft | Button::ScrollRight => {
+                None
+            }
+        }
+    }
+
+    /// Get the button from the number that browsers use in the `button` field
+    /// of a `MouseEvent`. Have a look at [`Button::to_web_button`] for the
+    /// values. `None` is returned for unknown values
+    #[must_use]
+    pub fn from_web_button(button: u32) -> Option<Self> {
+        match button {
+            0 => Some(Button::Left),
+            1 => Some(Button::Middle),
+            2 => Some(Button::Right),
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            3 => Some(Button::Back),
+            #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+            4 => Some(Button::Forward),
+            _ => None,
+        }
+    }
+
+    /// Check if the button is one of the scroll buttons
+    pub(crate) fn is_scroll(self) -> bool {
+        matches!(
+            self,
+            Button::ScrollUp | Button::ScrollDown | Button::ScrollLeft | Button::ScrollRight
+        )
     }
 }
 
+/// All buttons of the platform
+pub(crate) const BUTTONS: &[Button] = &[
+    Button::Left,
+    Button::Middle,
+    Button::Right,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Button::Back,
+    #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+    Button::Forward,
+    Button::ScrollUp,
+    Button::ScrollDown,
+    Button::ScrollLeft,
+    Button::ScrollRight,
+];
+
 /// The direction of a key or button
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -173,6 +253,28 @@ pub enum Axis {
     Vertical,
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Specifies the arrow key to use for moving the text cursor
+#[doc(alias = "ArrowDir", alias = "Direction2D")]
+pub enum ArrowDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<ArrowDirection> for Key {
+    fn from(direction: ArrowDirection) -> Self {
+        match direction {
+            ArrowDirection::Up => Key::UpArrow,
+            ArrowDirection::Down => Key::DownArrow,
+            ArrowDirection::Left => Key::LeftArrow,
+            ArrowDirection::Right => Key::RightArrow,
+        }
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 /// Specifies if a coordinate is relative or absolute
@@ -210,6 +312,55 @@ pub trait Keyboard {
     #[doc(hidden)]
     fn fast_text(&mut self, text: &str) -> InputResult<Option<()>>;
 
+    /// Do not use this directly.
+    ///
+    /// Get the normalization form the text is converted to before it is
+    /// entered
+    #[cfg(feature = "unicode")]
+    #[doc(hidden)]
+    fn normalization_form(&self) -> Option<NormalizationForm> {
+        None
+    }
+
+    /// Do not use this directly.
+    ///
+    /// Get the maximum number of characters that are entered per call of the
+    /// functions to enter text
+    #[doc(hidden)]
+    fn max_text_length(&self) -> Option<usize> {
+        None
+    }
+
+    /// Check if the display server repeats a held key on its own. If it does
+    /// not, [`Keyboard::hold_key_with_repeat`] sends the repeated key presses
+    #[doc(hidden)]
+    fn native_key_repeat(&self) -> bool {
+        true
+    }
+
+    /// Do not use this directly.
+    ///
+    /// Take the modifiers that were latched with the `sticky_modifiers` of the
+    /// [`Settings`], so they can be held while the next text is entered
+    #[doc(hidden)]
+    fn take_sticky_modifiers(&mut self) -> Vec<Key> {
+        Vec::new()
+    }
+
+    /// Do not use this directly. Use the [`Keyboard::text_robust`] function.
+    ///
+    /// Paste the text instead of entering it with key events. Returns `None`
+    /// if pasting is not possible or was not enabled with the
+    /// `fallback_to_paste` field of the [`Settings`]
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[doc(hidden)]
+    fn paste_text(&mut self, _text: &str) -> InputResult<Option<()>> {
+        Ok(None)
+    }
+
     /// Enter the text
     /// Use a fast method to enter the text, if it is available. You can use
     /// unicode here like: ❤️. This works regardless of the current keyboard
@@ -217,6 +368,19 @@ pub trait Keyboard {
     /// something similar. For shortcuts, use the
     /// [`Keyboard::key`] method instead.
     ///
+    /// Newlines (`\n`) and tabs (`\t`) are entered as clicks of
+    /// [`Key::Return`] and [`Key::Tab`] on all platforms. Carriage returns
+    /// (`\r`) are entered as [`Key::Return`] as well, unless the
+    /// `ignore_carriage_return` field of the [`Settings`] was set.
+    ///
+    /// The chars are always entered in their logical order (the order in
+    /// memory) and never in their visual order. Text with mixed directions
+    /// (e.g. Arabic and Latin) is not reordered, the application is
+    /// responsible for displaying it correctly.
+    ///
+    /// If modifiers were latched with the `sticky_modifiers` of the
+    /// [`Settings`], they are held while the text is entered key by key.
+    ///
     /// # Errors
     /// The text should not contain any NULL bytes (`\0`). Have a look at the
     /// documentation of [`InputError`] to see under which other conditions an
@@ -227,26 +391,192 @@ pub trait Keyboard {
             debug!("The text to enter was empty");
             return Ok(()); // Nothing to simulate.
         }
+        let latched = self.take_sticky_modifiers();
+        if !latched.is_empty() {
+            return hold_modifiers(self, latched, |keyboard| keyboard.text_via_keys(text));
+        }
 
-        // Fall back to entering single keys if no fast text entry is available
-        let fast_text_res = self.fast_text(text);
-        match fast_text_res {
-            Ok(Some(())) => {
-                debug!("fast text entry was successful");
-                Ok(())
+        enter_text_with_progress(self, text, &mut |_, _| {})
+    }
+
+    /// Enter the text like [`Keyboard::text`] but always simulate a key
+    /// event for each character, even if a fast method to enter the text is
+    /// available (e.g. the `input_method` protocol on Wayland). This allows
+    /// testing applications that behave differently when receiving text
+    /// from an input method and when receiving key events. On platforms
+    /// without a fast method, it is the same as [`Keyboard::text`].
+    ///
+    /// # Errors
+    /// The text should not contain any NULL bytes (`\0`). Have a look at the
+    /// documentation of [`InputError`] to see under which other conditions an
+    /// error will be returned.
+    fn text_via_keys(&mut self, text: &str) -> InputResult<()> {
+        let text = normalize(self, text);
+        if text.contains('\0') {
+            return Err(InputError::InvalidInput("the text contained a NULL byte"));
+        }
+        let (text, cut_off) = limit_length(self, &text);
+        for c in text.chars() {
+            self.key(Key::Unicode(c), Direction::Click)?;
+        }
+        if cut_off.is_empty() {
+            Ok(())
+        } else {
+            Err(TEXT_TOO_LONG)
+        }
+    }
+
+    /// Enter the text like [`Keyboard::text`], but paste the characters that
+    /// were not entered yet if entering them failed and the
+    /// `fallback_to_paste` field of the [`Settings`] was set. This helps with
+    /// fields that reject synthetic key events (e.g. some password fields).
+    /// Pasting is only supported on X11 with x11rb and the `clipboard`
+    /// feature. The text is set as the PRIMARY selection and pasted with a
+    /// click of the middle mouse button, so the mouse has to be over the
+    /// field. On all other platforms and protocols it is the same as
+    /// [`Keyboard::text`].
+    ///
+    /// None of the backends can tell if the application rejected the text.
+    /// The events are accepted by the display server and the application
+    /// silently ignores them, so the fallback is only used if entering the
+    /// text returned an error. Invalid text (e.g. text with a NULL byte or
+    /// text that is too long) is never pasted.
+    ///
+    /// # Errors
+    /// The error of entering the text is returned if the text could not be
+    /// pasted. Have a look at the documentation of [`InputError`] to see
+    /// under which other conditions an error will be returned.
+    fn text_robust(&mut self, text: &str) -> InputResult<()> {
+        let mut entered = 0;
+        let Err(e) = enter_text_with_progress(self, text, &mut |done, _| entered = done) else {
+            return Ok(());
+        };
+        if matches!(e, InputError::InvalidInput(_)) {
+            return Err(e);
+        }
+        let text = normalize(self, text);
+        let (text, _) = limit_length(self, &text);
+        let rest: String = text.chars().skip(entered).collect();
+        debug!("entering the text failed after {entered} characters: {e}");
+        match self.paste_text(&rest)? {
+            Some(()) => Ok(()),
+            None => Err(e),
+        }
+    }
+
+    /// Enter the text like [`Keyboard::text`] and call `on_progress` with the
+    /// number of characters that were entered so far and the total number of
+    /// characters. If the text can be entered all at once (e.g. with the
+    /// `input_method` protocol on Wayland), it is only called once the text
+    /// was entered. Otherwise it is called after each character. This can be
+    /// used to show the progress of entering a long text.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn text_with_progress(
+        &mut self,
+        text: &str,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> InputResult<()>
+    where
+        Self: Sized,
+    {
+        enter_text_with_progress(self, text, &mut on_progress)
+    }
+
+    /// Read UTF-8 text from the reader and enter it like [`Keyboard::text`]
+    /// while it is read. The text is read in chunks, so it does not have to
+    /// fit into memory all at once. UTF-8 sequences that are split across two
+    /// reads are entered once they are complete. Returns the number of
+    /// entered characters.
+    ///
+    /// # Errors
+    /// [`InputError::InvalidInput`] is returned if reading failed or the text
+    /// is not valid UTF-8. The text that was read up to that point was
+    /// already entered. Have a look at the documentation of [`InputError`] to
+    /// see under which other conditions an error will be returned.
+    fn text_from_reader(&mut self, mut reader: impl std::io::Read) -> InputResult<usize>
+    where
+        Self: Sized,
+    {
+        let mut buffer = [0; 4096];
+        let mut pending = vec![];
+        let mut entered = 0;
+        loop {
+            let len = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    error!("{e}");
+                    return Err(InputError::InvalidInput("unable to read the text"));
+                }
+            };
+            pending.extend_from_slice(&buffer[..len]);
+            let text = complete_utf8(&pending)?;
+            if text.is_empty() {
+                continue;
             }
-            Ok(None) => {
-                debug!("fast text entry not available. Trying to enter individual letters now");
-                for c in text.chars() {
-                    self.key(Key::Unicode(c), Direction::Click)?;
+            self.text(text)?;
+            entered += text.chars().count();
+            let complete = text.len();
+            pending.drain(..complete);
+        }
+        if !pending.is_empty() {
+            return Err(InputError::InvalidInput(
+                "the text ended with an incomplete UTF-8 sequence",
+            ));
+        }
+        Ok(entered)
+    }
+
+    /// Enter the text like [`Keyboard::text`] but continue if a character
+    /// cannot be entered and return all characters that were not entered.
+    /// Characters are not entered if they cannot be mapped (e.g. because
+    /// there are no empty keycodes left), the connection does not support
+    /// them or they are NULL bytes (`\0`). This allows detecting if the text
+    /// was only partially entered.
+    ///
+    /// # Errors
+    /// An error is returned if simulating the input failed for any other
+    /// reason. Have a look at the documentation of [`InputError`] to see under
+    /// which conditions an error will be returned.
+    fn text_checked(&mut self, text: &str) -> InputResult<Vec<char>> {
+        let text = normalize(self, text);
+        let (text, cut_off) = limit_length(self, &text);
+        let mut dropped = vec![];
+        for part in text.split_inclusive(['\n', '\r', '\t']) {
+            let (part, control_char) = split_control_char(part);
+            if !part.contains('\0') && !part.is_empty() && self.fast_text(part)?.is_some() {
+                debug!("fast text entry was successful");
+            } else {
+                for c in part.chars() {
+                    if c == '\0' {
+                        dropped.push(c);
+                        continue;
+                    }
+                    match self.key(Key::Unicode(c), Direction::Click) {
+                        Ok(()) => {}
+                        Err(
+                            InputError::Mapping(_)
+                            | InputError::NoEmptyKeycodes
+                            | InputError::InvalidInput(_)
+                            | InputError::Unsupported(_),
+                        ) => {
+                            warn!("the character {c:?} could not be entered");
+                            dropped.push(c);
+                        }
+                        Err(e) => return Err(e),
+                    }
                 }
-                Ok(())
             }
-            Err(e) => {
-                error!("{e}");
-                Err(e)
+            if let Some(c) = control_char {
+                self.key(Key::Unicode(c), Direction::Click)?;
             }
         }
+        dropped.extend(cut_off.chars());
+        Ok(dropped)
     }
 
     /// Sends an individual key event. It will enter the keysym (virtual key).
@@ -255,12 +585,164 @@ pub trait Keyboard {
     ///
     /// Some of the keys are specific to a platform.
     ///
+    /// Entering `Key::Unicode('\0')` does nothing. Other chars that have no
+    /// keysym (e.g. most control characters) return an
+    /// [`InputError::InvalidInput`].
+    ///
     /// # Errors
     /// Have a look at the documentation of [`InputError`] to see under which
     /// conditions an error will be returned.
     #[doc(alias = "key_down", alias = "key_up", alias = "key_click")]
     fn key(&mut self, key: Key, direction: Direction) -> InputResult<()>;
 
+    /// Start a batch of text. The text that is entered with
+    /// [`Keyboard::append_text`] is collected and committed at once with
+    /// [`Keyboard::end_text`], like an input method commits multi-part input.
+    /// This is only supported with the `input_method` protocol on Wayland. On
+    /// all other platforms and protocols it is a noop and the text is entered
+    /// right away. Keys and mouse input that are entered during the batch
+    /// commit the text that was collected before them, so the order is kept
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn begin_text(&mut self) -> InputResult<()> {
+        Ok(())
+    }
+
+    /// Add the text to the batch that was started with
+    /// [`Keyboard::begin_text`]. Without a batch or if batches are not
+    /// supported, it is entered like with [`Keyboard::text`]
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn append_text(&mut self, text: &str) -> InputResult<()> {
+        self.text(text)
+    }
+
+    /// Commit the text of the batch that was started with
+    /// [`Keyboard::begin_text`] and end it
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn end_text(&mut self) -> InputResult<()> {
+        Ok(())
+    }
+
+    /// Press all of the keys so they are held at the same time (e.g. WASD in
+    /// games). The keys are pressed in the given order without releasing any
+    /// key in between. If one of the keys cannot be pressed, the keys that
+    /// were already pressed get released again so none of them is stuck.
+    /// The keys are tracked like keys pressed with [`Keyboard::key`], so they
+    /// are released when Enigo gets dropped.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn press_keys(&mut self, keys: &[Key]) -> InputResult<()> {
+        press_keys(self, keys)
+    }
+
+    /// Release all of the keys. All keys are tried to be released, even if
+    /// releasing one of them fails.
+    ///
+    /// # Errors
+    /// The first error is returned. Have a look at the documentation of
+    /// [`InputError`] to see under which conditions an error will be returned.
+    fn release_keys(&mut self, keys: &[Key]) -> InputResult<()> {
+        release_keys(self, keys)
+    }
+
+    /// Click the key while the modifiers are held (e.g. `Ctrl+A`). The
+    /// modifiers are pressed in the given order and released in the reverse
+    /// order. They are released even if clicking the key failed.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[doc(alias = "chord", alias = "shortcut", alias = "hotkey")]
+    fn key_combo(&mut self, modifiers: &[Key], key: Key) -> InputResult<()> {
+        self.press_keys(modifiers)?;
+        let result = self.key(key, Direction::Click);
+        let modifiers: Vec<Key> = modifiers.iter().rev().copied().collect();
+        result.and(self.release_keys(&modifiers))
+    }
+
+    /// Enter a shortcut given in human notation (e.g. "Ctrl+Shift+K" or
+    /// "Cmd+Option+Esc"). The keys are separated by a plus sign and the last
+    /// one is clicked while the others are held, just like with
+    /// [`Keyboard::key_combo`]. The names of the keys are case insensitive.
+    /// "Cmd", "Win" and "Super" are aliases of [`Key::Meta`] and "Opt" is an
+    /// alias of [`Key::Alt`]. Use "Plus" for the plus key.
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if the shortcut contains an
+    /// unknown key. Have a look at the documentation of [`InputError`] to see
+    /// under which other conditions an error will be returned.
+    #[doc(alias = "hotkey")]
+    fn shortcut(&mut self, shortcut: &str) -> InputResult<()> {
+        let (modifiers, key) = parse_shortcut(shortcut)?;
+        self.key_combo(&modifiers, key)
+    }
+
+    /// Move the text cursor by clicking the arrow key for the given direction
+    /// `count` times. Each click is sent as a separate press and release so
+    /// the display server processed the previous arrow key before the next
+    /// one is sent. Otherwise some of the keys could get lost.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn move_cursor(&mut self, direction: ArrowDirection, count: u32) -> InputResult<()> {
+        let key = Key::from(direction);
+        for _ in 0..count {
+            self.key(key, Direction::Press)?;
+            self.key(key, Direction::Release)?;
+        }
+        Ok(())
+    }
+
+    /// Press the key, hold it for the given duration and release it again.
+    /// If the display server does not repeat held keys on its own (e.g. on
+    /// Wayland), additional presses of the key are sent `rate_hz` times per
+    /// second while it is held, just like the X server would do. The key is
+    /// released even if one of the repeated presses failed.
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if `rate_hz` is zero. Have a
+    /// look at the documentation of [`InputError`] to see under which other
+    /// conditions an error will be returned.
+    #[doc(alias = "auto_repeat")]
+    fn hold_key_with_repeat(
+        &mut self,
+        key: Key,
+        duration: Duration,
+        rate_hz: u32,
+    ) -> InputResult<()> {
+        if rate_hz == 0 {
+            return Err(InputError::InvalidInput(
+                "the repeat rate has to be larger than zero",
+            ));
+        }
+        self.key(key, Direction::Press)?;
+        let start = Instant::now();
+        let end = start + duration;
+        let mut result = Ok(());
+        if !self.native_key_repeat() {
+            let interval = Duration::from_secs(1) / rate_hz;
+            let mut next = start + interval;
+            while next <= end && result.is_ok() {
+                std::thread::sleep(next.saturating_duration_since(Instant::now()));
+                result = self.key(key, Direction::Press);
+                next += interval;
+            }
+        }
+        std::thread::sleep(end.saturating_duration_since(Instant::now()));
+        result.and(self.key(key, Direction::Release))
+    }
+
     /// Sends a raw keycode. The keycode may or may not be mapped on the current
     /// layout. You have to make sure of that yourself. This can be useful if
     /// you want to simulate a press regardless of the layout (WASD on video
@@ -275,6 +757,22 @@ pub trait Keyboard {
     /// conditions an error will be returned.
     #[doc(alias = "Key::Raw")]
     fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()>;
+
+    /// Do not use this directly. Use a [`crate::agent::Token::Raw`] with a
+    /// timestamp instead.
+    ///
+    /// Sends a keycode like [`Keyboard::raw`] but with the given timestamp in
+    /// milliseconds. This allows replaying recorded events with their original
+    /// timing. Only Wayland uses the timestamp, all other platforms ignore it
+    /// and call [`Keyboard::raw`].
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn raw_at(&mut self, keycode: u16, direction: Direction, time: u32) -> InputResult<()> {
+        debug!("the timestamp {time} is ignored on this platform");
+        self.raw(keycode, direction)
+    }
 }
 
 /// Contains functions to control the mouse and to get the size of the display.
@@ -321,17 +819,66 @@ pub trait Mouse {
     /// * `axis` - The axis to scroll on
     /// * `length` - Number of 15° (click) rotations of the mouse wheel to
     ///   scroll. How many lines will be scrolled depends on the current setting
-    ///   of the operating system.
+    ///   of the operating system. On X11 and Wayland the application that
+    ///   receives the event decides it. Use `Enigo::scroll_lines_per_notch` to
+    ///   get the number of lines if it is known.
+    ///
+    /// A notch is converted like this, so the same length scrolls the same
+    /// distance as a real mouse wheel on every platform:
+    /// * X11: one click of the scroll button
+    /// * Windows: [`SCROLL_VALUE120_PER_NOTCH`] (`WHEEL_DELTA`)
+    /// * macOS: one line
+    /// * Wayland: [`SCROLL_DEGREES_PER_NOTCH`] and one discrete step
     ///
     /// With [`Axis::Vertical`], a positive length will result in scrolling down
     /// and negative ones up. With [`Axis::Horizontal`], a positive length
     /// will result in scrolling to the right and negative ones to the left
     ///
+    /// Returns the number of discrete scroll events that were sent. On X11
+    /// the scroll button is clicked once per unit of the length, so it is
+    /// the absolute value of the length. On all other platforms a single
+    /// event is sent for the whole length
+    ///
     /// # Errors
     /// Have a look at the documentation of [`InputError`] to see under which
     /// conditions an error will be returned.
     #[doc(alias = "mouse_scroll_x", alias = "mouse_scroll_y")]
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()>;
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32>;
+
+    /// Scroll `total` units like [`Mouse::scroll`], but split it into single
+    /// scroll events that are spread evenly over `duration`. This results in
+    /// a gradual scroll instead of a sudden jump (e.g. for screen recordings).
+    /// Before each step, `cancel` is checked and the scrolling stops early if
+    /// it was set to `true`.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[doc(alias = "smooth_scroll", alias = "scroll_animated")]
+    fn scroll_smooth(
+        &mut self,
+        total: i32,
+        axis: Axis,
+        duration: Duration,
+        cancel: &AtomicBool,
+    ) -> InputResult<()> {
+        let steps = total.unsigned_abs();
+        if steps == 0 {
+            return Ok(());
+        }
+        let interval = duration / steps;
+        for step in 0..steps {
+            if cancel.load(Ordering::Relaxed) {
+                debug!("smooth scrolling was cancelled after {step} steps");
+                return Ok(());
+            }
+            self.scroll(total.signum(), axis)?;
+            if step + 1 < steps {
+                std::thread::sleep(interval);
+            }
+        }
+        Ok(())
+    }
 
     /// Get the (width, height) of the main display in pixels. This currently
     /// only works on the main display
@@ -342,82 +889,631 @@ pub trait Mouse {
     #[doc(alias = "main_display_size")]
     fn main_display(&self) -> InputResult<(i32, i32)>;
 
-    /// Get the location of the mouse in pixels
+    /// Get the location of the mouse in pixels. On X11 it already includes a
+    /// move with [`Mouse::move_mouse`] that returned, so there is no need to
+    /// wait in between (except with [`Settings::x11_use_send_event`], which
+    /// does not move the pointer)
     ///
     /// # Errors
     /// Have a look at the documentation of [`InputError`] to see under which
     /// conditions an error will be returned.
     #[doc(alias = "mouse_location")]
     fn location(&self) -> InputResult<(i32, i32)>;
-}
 
-pub type InputResult<T> = Result<T, InputError>;
+    /// Get the bounds (`min_x`, `min_y`, `max_x`, `max_y`) of the area in
+    /// pixels that the mouse can be moved to with absolute coordinates. The
+    /// maximum values are still inside of the area. You can use this to check
+    /// the coordinates before calling [`Mouse::move_mouse`].
+    ///
+    /// On Windows this is the virtual desktop, which spans all monitors. If a
+    /// monitor is placed to the left of or above the primary monitor, the
+    /// minimum values are negative and moving the mouse to negative
+    /// coordinates is valid. On all other platforms only the main display is
+    /// taken into account, so the minimum values are always zero.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    fn virtual_desktop_bounds(&self) -> InputResult<(i32, i32, i32, i32)> {
+        let (width, height) = self.main_display()?;
+        Ok((0, 0, width - 1, height - 1))
+    }
 
-/// Error when simulating input
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum InputError {
-    /// Mapping a keycode to a keysym failed
-    Mapping(String),
-    /// Unmapping a keycode failed
-    Unmapping(String),
-    /// There was no space to map any keycodes
-    NoEmptyKeycodes,
-    /// There was an error with the protocol
-    Simulate(&'static str),
-    /// The input you want to simulate is invalid
-    /// This happens for example if you want to enter text that contains NULL
-    /// bytes (`\0`)
-    InvalidInput(&'static str),
-}
+    /// Move the mouse to a fraction of the width and height of a display. The
+    /// fractions have to be between 0.0 and 1.0, so (0.5, 0.5) moves the mouse
+    /// to the center of the display. This allows moving the mouse independent
+    /// of the resolution of the display. Only the main display (index 0) is
+    /// supported at the moment.
+    ///
+    /// # Errors
+    /// [`InputError::InvalidInput`] is returned if one of the fractions is not
+    /// between 0.0 and 1.0 and [`InputError::Unsupported`] is returned for
+    /// every other display than the main display. Have a look at the
+    /// documentation of [`InputError`] to see under which other conditions an
+    /// error will be returned.
+    #[doc(alias = "move_mouse_percent", alias = "move_mouse_relative_to_display")]
+    fn move_mouse_pct(&mut self, x_pct: f64, y_pct: f64, display_index: usize) -> InputResult<()> {
+        if !(0.0..=1.0).contains(&x_pct) || !(0.0..=1.0).contains(&y_pct) {
+            return Err(InputError::InvalidInput(
+                "the fractions of the display have to be between 0.0 and 1.0",
+            ));
+        }
+        if display_index != 0 {
+            return Err(InputError::Unsupported(
+                "only the main display is supported",
+            ));
+        }
+        let (width, height) = self.main_display()?;
+        #[allow(clippy::cast_possible_truncation)]
+        let (x, y) = (
+            (x_pct * f64::from(width - 1)).round() as i32,
+            (y_pct * f64::from(height - 1)).round() as i32,
+        );
+        self.move_mouse(x, y, Coordinate::Abs)
+    }
 
-impl Display for InputError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let string = match self {
-            InputError::Mapping(e) => format!("error when mapping keycode to keysym: ({e})"),
-            InputError::Unmapping(e) => format!("error when unmapping keysym: ({e})"),
-            InputError::NoEmptyKeycodes => {
-                "there were no empty keycodes that could be used".to_string()
-            }
-            InputError::Simulate(e) => format!("simulating input failed: ({e})"),
-            InputError::InvalidInput(e) => format!("you tried to simulate invalid input: ({e})"),
+    /// Move the mouse to the coordinates and click the button there. Relative
+    /// coordinates are an offset from the current location of the mouse. The
+    /// resulting location is clamped to the main display. If the location
+    /// cannot be queried (e.g. on Wayland before the mouse was moved to
+    /// absolute coordinates), the mouse is moved relative to its unknown
+    /// location instead
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned for relative coordinates if
+    /// the mouse is not on the main display, because the location can only
+    /// be clamped to the main display. Have a look at the documentation of
+    /// [`InputError`] to see under which other conditions an error will be
+    /// returned.
+    fn click_at(
+        &mut self,
+        x: i32,
+        y: i32,
+        coordinate: Coordinate,
+        button: Button,
+    ) -> InputResult<()> {
+        let (x, y) = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => match self.location() {
+                Ok((current_x, current_y)) => {
+                    let (width, height) = self.main_display()?;
+                    if width <= 0 || height <= 0 {
+                        return Err(InputError::Simulate("the main display has no size"));
+                    }
+                    // The main display starts at the origin, so the location
+                    // cannot be clamped if the mouse is on another display
+                    if !(0..width).contains(&current_x) || !(0..height).contains(&current_y) {
+                        return Err(InputError::InvalidInput(
+                            "the mouse is not on the main display",
+                        ));
+                    }
+                    (
+                        current_x.saturating_add(x).clamp(0, width - 1),
+                        current_y.saturating_add(y).clamp(0, height - 1),
+                    )
+                }
+                Err(InputError::Unsupported(_)) => {
+                    self.move_mouse(x, y, Coordinate::Rel)?;
+                    return self.button(button, Direction::Click);
+                }
+                Err(e) => return Err(e),
+            },
         };
-        write!(f, "{string}")
+        self.move_mouse(x, y, Coordinate::Abs)?;
+        self.button(button, Direction::Click)
     }
-}
 
-impl Error for InputError {}
+    /// Send the button event while the modifiers are held (e.g. Shift-click to
+    /// select a range). This is the equivalent of [`Keyboard::key_combo`] for
+    /// the mouse. The modifiers are pressed in the given order and released in
+    /// the reverse order. They are released even if the button event failed.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[doc(alias = "modifier_click", alias = "shift_click", alias = "ctrl_click")]
+    fn button_with_mods(
+        &mut self,
+        button: Button,
+        direction: Direction,
+        mods: &[Key],
+    ) -> InputResult<()>
+    where
+        Self: Keyboard + Sized,
+    {
+        self.press_keys(mods)?;
+        let result = self.button(button, direction);
+        let mods: Vec<Key> = mods.iter().rev().copied().collect();
+        result.and(self.release_keys(&mods))
+    }
 
-/// Error when establishing a new connection
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum NewConError {
-    /// Error while creating the connection
-    EstablishCon(&'static str),
-    /// Error when receiving a reply
-    Reply,
-    /// The keymap is full, so there was no space to map any keycodes to keysyms
-    NoEmptyKeycodes,
-}
+    /// Do not use this directly.
+    ///
+    /// Get the time between the clicks of [`Mouse::multi_click`]
+    #[doc(hidden)]
+    fn double_click_gap(&self) -> Duration {
+        Duration::from_millis(50)
+    }
 
-impl Display for NewConError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let string = match self {
-            NewConError::EstablishCon(e) => format!("no connection could be established: ({e})"),
-            NewConError::Reply => {
-                "there was an error with the reply from the display server. this should not happen"
-                    .to_string()
-            }
-            NewConError::NoEmptyKeycodes => {
-                "there were no empty keycodes that could be used".to_string()
+    /// Get the location of the mouse when the last button was pressed. It is
+    /// updated whenever a button is pressed or clicked, except for the scroll
+    /// buttons. It is `None` if no button was pressed yet or the location
+    /// could not be determined when it was pressed (e.g. with libei). On
+    /// Wayland the location is only an estimate, see [`Mouse::location`].
+    fn last_click_location(&self) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// Click the button `count` times (e.g. twice for a double click). The
+    /// time between the clicks is the `double_click_gap_ms` field of the
+    /// [`Settings`]. It has to be shorter than the double click interval of
+    /// the system for the clicks to be recognized as one multi click
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[doc(alias = "double_click")]
+    fn multi_click(&mut self, button: Button, count: u32) -> InputResult<()> {
+        for n in 0..count {
+            if n > 0 {
+                std::thread::sleep(self.double_click_gap());
             }
-        };
-        write!(f, "{string}")
+            self.button(button, Direction::Click)?;
+        }
+        Ok(())
     }
-}
 
-impl Error for NewConError {}
+    /// Press the mouse button, hold it for the given duration and release it
+    /// again. This can be used to simulate a long-press. The button is also
+    /// released if the thread panics while the button is held.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[doc(alias = "tap_and_hold")]
+    fn long_press(&mut self, button: Button, duration: Duration) -> InputResult<()> {
+        self.button(button, Direction::Press)?;
+        let guard = ButtonGuard {
+            mouse: self,
+            button,
+            held: true,
+        };
+        std::thread::sleep(duration);
+        guard.release()
+    }
 
-/// Settings for creating the Enigo struct and it's behavior
+    /// Poll the location of the mouse every `poll` until it did not change for
+    /// the duration of `idle` and return the location. This can be used to
+    /// wait for the user or an animation to stop moving the mouse.
+    ///
+    /// # Errors
+    /// An error is returned if the location of the mouse cannot be
+    /// retrieved (e.g. on Wayland). Have a look at the documentation of
+    /// [`InputError`] to see under which conditions an error will be returned.
+    fn wait_for_mouse_idle(&self, idle: Duration, poll: Duration) -> InputResult<(i32, i32)> {
+        let mut location = self.location()?;
+        let mut unchanged_since = Instant::now();
+        while unchanged_since.elapsed() < idle {
+            std::thread::sleep(poll);
+            let new_location = self.location()?;
+            if new_location != location {
+                location = new_location;
+                unchanged_since = Instant::now();
+            }
+        }
+        Ok(location)
+    }
+}
+
+/// Check if the button event has to be sent with [`click_with_gap`]. Scroll
+/// buttons have no state, so they are always clicked at once
+pub(crate) fn needs_click_gap(button: Button, direction: Direction, gap: Duration) -> bool {
+    direction == Direction::Click && !gap.is_zero() && !button.is_scroll()
+}
+
+/// Click the button by pressing it, waiting for the gap and releasing it
+/// again
+pub(crate) fn click_with_gap<M: Mouse + ?Sized>(
+    mouse: &mut M,
+    button: Button,
+    gap: Duration,
+) -> InputResult<()> {
+    mouse.button(button, Direction::Press)?;
+    std::thread::sleep(gap);
+    mouse.button(button, Direction::Release)
+}
+
+/// Enter a key that is simulated with a shortcut on the platform
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn enter_shortcut<K: Keyboard + ?Sized>(
+    keyboard: &mut K,
+    modifiers: &[Key],
+    key: Key,
+    direction: Direction,
+) -> InputResult<()> {
+    match direction {
+        Direction::Click => keyboard.key_combo(modifiers, key),
+        Direction::Press => {
+            let mut keys = modifiers.to_vec();
+            keys.push(key);
+            keyboard.press_keys(&keys)
+        }
+        Direction::Release => {
+            let mut keys = vec![key];
+            keys.extend(modifiers.iter().rev());
+            keyboard.release_keys(&keys)
+        }
+    }
+}
+
+/// Split off the newline, tab or carriage return at the end of the part of a
+/// text. They have to be entered as key events
+fn split_control_char(part: &str) -> (&str, Option<char>) {
+    match part.char_indices().last() {
+        Some((idx, c @ ('\n' | '\r' | '\t'))) => (&part[..idx], Some(c)),
+        _ => (part, None),
+    }
+}
+
+/// Get the text up to the last complete UTF-8 sequence. The bytes of an
+/// incomplete sequence at the end are left out, because the rest of them is
+/// not read yet
+fn complete_utf8(bytes: &[u8]) -> InputResult<&str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(text),
+        Err(e) if e.error_len().is_none() => {
+            Ok(std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default())
+        }
+        Err(_) => Err(InputError::InvalidInput("the text is not valid UTF-8")),
+    }
+}
+
+/// Enter the text and report the number of entered characters to
+/// `on_progress`
+fn enter_text_with_progress<K: Keyboard + ?Sized>(
+    keyboard: &mut K,
+    text: &str,
+    on_progress: &mut dyn FnMut(usize, usize),
+) -> InputResult<()> {
+    let text = normalize(keyboard, text);
+    let (text, cut_off) = limit_length(keyboard, &text);
+    let total = text.chars().count();
+    let mut done = 0;
+    for part in text.split_inclusive(['\n', '\r', '\t']) {
+        let (part, control_char) = split_control_char(part);
+        enter_text(keyboard, part, &mut |entered| {
+            done += entered;
+            on_progress(done, total);
+        })?;
+        if let Some(c) = control_char {
+            keyboard.key(Key::Unicode(c), Direction::Click)?;
+            done += 1;
+            on_progress(done, total);
+        }
+    }
+    if !cut_off.is_empty() {
+        return Err(TEXT_TOO_LONG);
+    }
+    Ok(())
+}
+
+/// Error that is returned if the text was cut off after the maximum length
+pub(crate) const TEXT_TOO_LONG: InputError =
+    InputError::InvalidInput("the text is longer than Settings::max_text_length");
+
+/// Split the text after the maximum number of characters of the keyboard.
+/// Returns the text that can be entered and the text that was cut off
+pub(crate) fn limit_length<'a, K: Keyboard + ?Sized>(
+    keyboard: &K,
+    text: &'a str,
+) -> (&'a str, &'a str) {
+    if let Some((end, _)) = keyboard
+        .max_text_length()
+        .and_then(|max| text.char_indices().nth(max))
+    {
+        warn!(
+            "the text is cut off after {} characters",
+            text[..end].chars().count()
+        );
+        return text.split_at(end);
+    }
+    (text, "")
+}
+
+/// Convert the text to the normalization form of the keyboard, if one was set
+#[cfg_attr(not(feature = "unicode"), allow(unused_variables))]
+pub(crate) fn normalize<'a, K: Keyboard + ?Sized>(keyboard: &K, text: &'a str) -> Cow<'a, str> {
+    #[cfg(feature = "unicode")]
+    if let Some(form) = keyboard.normalization_form() {
+        return Cow::Owned(form.normalize(text));
+    }
+    Cow::Borrowed(text)
+}
+
+/// Enter text that does not contain any newlines, tabs or carriage returns and
+/// report the number of newly entered characters to `on_entered`
+fn enter_text<K: Keyboard + ?Sized>(
+    keyboard: &mut K,
+    text: &str,
+    on_entered: &mut dyn FnMut(usize),
+) -> InputResult<()> {
+    if text.is_empty() {
+        return Ok(());
+    }
+
+    // Fall back to entering single keys if no fast text entry is available
+    let fast_text_res = keyboard.fast_text(text);
+    match fast_text_res {
+        Ok(Some(())) => {
+            debug!("fast text entry was successful");
+            on_entered(text.chars().count());
+            Ok(())
+        }
+        Ok(None) => {
+            debug!("fast text entry not available. Trying to enter individual letters now");
+            for c in text.chars() {
+                keyboard.key(Key::Unicode(c), Direction::Click)?;
+                on_entered(1);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("{e}");
+            Err(e)
+        }
+    }
+}
+
+/// Releases the held mouse button when it gets dropped
+struct ButtonGuard<'a, M: Mouse + ?Sized> {
+    mouse: &'a mut M,
+    button: Button,
+    held: bool,
+}
+
+impl<M: Mouse + ?Sized> ButtonGuard<'_, M> {
+    fn release(mut self) -> InputResult<()> {
+        self.held = false;
+        self.mouse.button(self.button, Direction::Release)
+    }
+}
+
+impl<M: Mouse + ?Sized> Drop for ButtonGuard<'_, M> {
+    fn drop(&mut self) {
+        if self.held {
+            if let Err(e) = self.mouse.button(self.button, Direction::Release) {
+                error!("unable to release the held button {:?}: {e}", self.button);
+            }
+        }
+    }
+}
+
+pub type InputResult<T> = Result<T, InputError>;
+
+/// Information about a window
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WindowInfo {
+    /// Title of the window
+    pub title: String,
+    /// Class of the window. On X11 this is the class of the `WM_CLASS`
+    /// property, on Windows it is the name of the window class
+    pub class: Option<String>,
+    /// Id of the process the window belongs to
+    pub pid: Option<u32>,
+    /// Id of the window. On X11 this is the id of the X window, on Windows it
+    /// is the handle of the window
+    pub id: u64,
+}
+
+impl WindowInfo {
+    /// Check if both describe the same window. The title is not compared,
+    /// because it can change while the window keeps the focus (e.g. when
+    /// switching the tab of a browser)
+    pub(crate) fn is_same_window(&self, other: &Self) -> bool {
+        self.id == other.id && self.pid == other.pid && self.class == other.class
+    }
+}
+
+/// Describes how the input is simulated with the connections that are used
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Capabilities {
+    /// How the scroll events are simulated. It is `None` if it is not
+    /// possible to scroll
+    pub scroll_method: Option<ScrollMethod>,
+    /// Version of the `zwlr_virtual_pointer_manager_v1` protocol that was
+    /// bound. It is `None` if the protocol is not used
+    pub wayland_virtual_pointer_version: Option<u32>,
+    /// The location of the mouse can be retrieved with [`Mouse::location`]
+    pub location: bool,
+    /// The size of the main display can be retrieved with
+    /// [`Mouse::main_display`]
+    pub main_display: bool,
+    /// The focused window can be retrieved with `Enigo::focused_window`
+    pub focused_window: bool,
+    /// Number of lines one unit of the length of [`Mouse::scroll`] scrolls.
+    /// It is `None` if the application that receives the scroll events decides
+    /// it (X11 and Wayland)
+    pub scroll_lines_per_notch: Option<u32>,
+    /// Buttons that can be simulated with [`Mouse::button`]. For all other
+    /// buttons, [`InputError::Unsupported`] is returned instead of sending a
+    /// different button (e.g. on X11 if the pointer has less than nine
+    /// buttons, `Button::Back` and `Button::Forward` are not supported)
+    pub buttons: Vec<Button>,
+}
+
+impl Capabilities {
+    /// Check which of the queries do not return [`InputError::Unsupported`]
+    pub(crate) fn with_queries(mut self, enigo: &Enigo) -> Self {
+        let is_supported =
+            |result: InputResult<()>| !matches!(result, Err(InputError::Unsupported(_)));
+        self.location = is_supported(enigo.location().map(|_| ()));
+        self.main_display = is_supported(enigo.main_display().map(|_| ()));
+        self.focused_window = is_supported(enigo.focused_window().map(|_| ()));
+        self.scroll_lines_per_notch = enigo.scroll_lines_per_notch().ok();
+        self
+    }
+}
+
+impl Enigo {
+    /// Check that the focused window is the expected one before the input is
+    /// simulated
+    fn check_focus(&self, expected_window: &WindowInfo) -> InputResult<()> {
+        let focused_window = self.focused_window()?;
+        if focused_window.is_same_window(expected_window) {
+            Ok(())
+        } else {
+            warn!("the focus changed to {focused_window:?}");
+            Err(InputError::Simulate("focus changed"))
+        }
+    }
+
+    /// Enter the key like [`Keyboard::key`], but only if `expected_window` is
+    /// still the focused window right before the key is entered. This
+    /// prevents sending input to the wrong window if the focus was stolen
+    /// (e.g. by a notification). The windows are compared by their id,
+    /// process id and class, so a change of the title is ignored.
+    ///
+    /// # Errors
+    /// `InputError::Simulate("focus changed")` is returned if another window
+    /// is focused. Getting the focused window is not supported on all
+    /// platforms (see [`Enigo::focused_window`]). Have a look at the
+    /// documentation of [`InputError`] to see under which other conditions an
+    /// error will be returned.
+    pub fn key_guarded(
+        &mut self,
+        key: Key,
+        direction: Direction,
+        expected_window: &WindowInfo,
+    ) -> InputResult<()> {
+        self.check_focus(expected_window)?;
+        self.key(key, direction)
+    }
+
+    /// Press, release or click the button like [`Mouse::button`], but only if
+    /// `expected_window` is still the focused window right before the button
+    /// event is sent.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`Enigo::key_guarded`] to see
+    /// under which conditions an error will be returned.
+    pub fn button_guarded(
+        &mut self,
+        button: Button,
+        direction: Direction,
+        expected_window: &WindowInfo,
+    ) -> InputResult<()> {
+        self.check_focus(expected_window)?;
+        self.button(button, direction)
+    }
+
+    /// Move the mouse like [`Mouse::move_mouse`], but only if
+    /// `expected_window` is still the focused window right before the mouse
+    /// is moved.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`Enigo::key_guarded`] to see
+    /// under which conditions an error will be returned.
+    pub fn move_mouse_guarded(
+        &mut self,
+        x: i32,
+        y: i32,
+        coordinate: Coordinate,
+        expected_window: &WindowInfo,
+    ) -> InputResult<()> {
+        self.check_focus(expected_window)?;
+        self.move_mouse(x, y, coordinate)
+    }
+}
+
+/// Specifies how scroll events are simulated
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrollMethod {
+    /// Clicks of the buttons 4 to 7 (X11)
+    Buttons,
+    /// Scroll wheel events (Windows and macOS)
+    Wheel,
+    /// Continuous `axis` events of the virtual pointer (Wayland)
+    Axis,
+    /// `axis_discrete` events of the virtual pointer (Wayland). They contain
+    /// the continuous value and the number of wheel notches, so compositors
+    /// that only honor one of them still scroll
+    AxisDiscrete,
+}
+
+/// Error when simulating input
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputError {
+    /// Mapping a keycode to a keysym failed
+    Mapping(String),
+    /// Unmapping a keycode failed
+    Unmapping(String),
+    /// There was no space to map any keycodes
+    NoEmptyKeycodes,
+    /// There was an error with the protocol. On Windows this is also returned
+    /// if the OS did not insert the events (e.g. because of UIPI)
+    Simulate(&'static str),
+    /// The connection that is used is not able to do this (e.g. getting the
+    /// location of the mouse on Wayland). You might be able to use a fallback
+    Unsupported(&'static str),
+    /// The input you want to simulate is invalid
+    /// This happens for example if you want to enter text that contains NULL
+    /// bytes (`\0`)
+    InvalidInput(&'static str),
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let string = match self {
+            InputError::Mapping(e) => format!("error when mapping keycode to keysym: ({e})"),
+            InputError::Unmapping(e) => format!("error when unmapping keysym: ({e})"),
+            InputError::NoEmptyKeycodes => {
+                "there were no empty keycodes that could be used".to_string()
+            }
+            InputError::Simulate(e) => format!("simulating input failed: ({e})"),
+            InputError::Unsupported(e) => format!("this is not supported: ({e})"),
+            InputError::InvalidInput(e) => format!("you tried to simulate invalid input: ({e})"),
+        };
+        write!(f, "{string}")
+    }
+}
+
+impl Error for InputError {}
+
+/// Error when establishing a new connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NewConError {
+    /// Error while creating the connection
+    EstablishCon(&'static str),
+    /// Error when receiving a reply
+    Reply,
+    /// The keymap is full, so there was no space to map any keycodes to keysyms
+    NoEmptyKeycodes,
+}
+
+impl Display for NewConError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let string = match self {
+            NewConError::EstablishCon(e) => format!("no connection could be established: ({e})"),
+            NewConError::Reply => {
+                "there was an error with the reply from the display server. this should not happen"
+                    .to_string()
+            }
+            NewConError::NoEmptyKeycodes => {
+                "there were no empty keycodes that could be used".to_string()
+            }
+        };
+        write!(f, "{string}")
+    }
+}
+
+impl Error for NewConError {}
+
+/// Settings for creating the Enigo struct and it's behavior
 #[allow(dead_code)] // It is not dead code on other platforms
+#[allow(clippy::struct_excessive_bools)] // Each of the bools is an independent setting
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Settings {
@@ -429,6 +1525,15 @@ pub struct Settings {
     pub x11_display: Option<String>,
     /// Display name to connect to when using Linux Wayland
     pub wayland_display: Option<String>,
+    /// Name of the seat to simulate the input on when using Linux Wayland. If
+    /// it is not set, the first seat is used. Setting it is only needed on
+    /// systems with multiple seats (e.g. kiosk setups)
+    pub wayland_seat: Option<String>,
+    /// Do not bind the `zwp_input_method_manager_v2` protocol when using Linux
+    /// Wayland, so all text is entered with the virtual keyboard. Some
+    /// compositors crash or misbehave when a client uses the input method
+    /// protocol, so this can be used as a workaround
+    pub wayland_disable_input_method: bool,
     /// Arbitrary value to be able to distinguish events created by enigo
     /// All events will be marked with this value in the dwExtraInfo field
     pub windows_dw_extra_info: Option<usize>,
@@ -439,6 +1544,419 @@ pub struct Settings {
     /// Set this to true if you want all held keys to get released when Enigo
     /// gets dropped
     pub release_keys_when_dropped: bool,
+    /// Set this to true if held keys should not get repeated by the X11 server.
+    /// The auto-repeat is turned off for the keycode while it is pressed and
+    /// restored once it gets released. This is only supported with x11rb and
+    /// has no effect on the other platforms because their simulated key
+    /// presses are not repeated
+    pub disable_autorepeat_on_press: bool,
+    /// Set this to true if the events should be sent directly to the focused
+    /// window with `XSendEvent` instead of simulating them with the XTEST
+    /// extension. This is only supported with x11rb.
+    ///
+    /// Most applications accept the events of the XTEST extension, which are
+    /// indistinguishable from real input. Events sent with `XSendEvent` are
+    /// marked as synthetic and some applications ignore them (e.g. xterm
+    /// unless `allowSendEvents` is enabled). They can still be useful for
+    /// applications that ignore input from the XTEST devices. Moving the mouse
+    /// this way only sends motion events to the window and does not move the
+    /// actual pointer
+    pub x11_use_send_event: bool,
+    /// Set this to false if the keymap of the X server should never be
+    /// changed. By default, characters that are not part of the keyboard
+    /// layout are temporarily mapped to unused keycodes, which affects all
+    /// other applications of the X session. If it is false, only the keys of
+    /// the layout can be entered and [`InputError::Mapping`] is returned for
+    /// all other keys. This is only supported with x11rb
+    pub x11_allow_remap: bool,
+    /// Id of the XTEST device that sends the key events. It has to be a
+    /// keyboard. If it is not set, the first keyboard of the X server is used.
+    /// Pinning the device is useful if there are multiple seats or multiple
+    /// XTEST devices. Use `Enigo::list_devices` to get the ids. This is only
+    /// supported with x11rb
+    pub x11_keyboard_device: Option<u8>,
+    /// Id of the XTEST device that sends the button and motion events. It has
+    /// to be a pointer. If it is not set, the first pointer of the X server is
+    /// used. This is only supported with x11rb
+    pub x11_pointer_device: Option<u8>,
+    /// Backend that is used to simulate the input on Linux. If it is set, only
+    /// the connection with this backend is established. It is the same as a
+    /// [`Settings::backend_priority`] with only this backend and it is
+    /// ignored if a priority is set
+    pub prefer_backend: Option<Backend>,
+    /// Backends that are tried in this order on Linux. The first one that
+    /// connects is used and the remaining ones are skipped. An error is
+    /// returned if none of them connects. If it is empty (default), all
+    /// backends are tried (Wayland, X11 and then libei) and the input is sent
+    /// with all established connections. Use a single backend to only use
+    /// that one.
+    ///
+    /// On a Wayland session (e.g. GNOME or KDE), applications that only
+    /// support X11 run under Xwayland. The X11 backend only reaches these
+    /// applications, while the input of the Wayland backend is sent to the
+    /// focused window by the compositor. If only the X11 connection could be
+    /// established on a Wayland session, the input to native Wayland
+    /// applications does nothing. A warning is logged in this case
+    pub backend_priority: Vec<Backend>,
+    /// Name of this instance of Enigo. If it is set, the log messages of the
+    /// functions of the Enigo struct are prefixed with it. This makes it
+    /// easier to tell apart the output of multiple instances
+    pub instance_name: Option<String>,
+    /// Set this to true if carriage returns (`\r`) should be ignored when
+    /// entering text or a [`Key::Unicode`]. Otherwise they are entered as
+    /// [`Key::Return`]. Ignoring them is useful if the text uses `\r\n` as
+    /// line endings and only a single [`Key::Return`] should be entered per
+    /// line
+    pub ignore_carriage_return: bool,
+    /// Maximum number of characters that are entered per call of
+    /// [`Keyboard::text`] and the other functions to enter text. Longer texts
+    /// are cut off after this many characters and
+    /// [`InputError::InvalidInput`] is returned once the beginning was
+    /// entered ([`Keyboard::text_checked`] returns the characters that were
+    /// cut off instead). This is a safety measure if the text comes from an
+    /// untrusted source (e.g. the output of a chatbot). If it is not set, the
+    /// length is not limited
+    pub max_text_length: Option<usize>,
+    /// Set this to true to paste the text with [`Keyboard::text_robust`] if
+    /// entering it with key events failed. This is only supported on X11 with
+    /// x11rb and the `clipboard` feature. Pasting replaces the PRIMARY
+    /// selection
+    pub fallback_to_paste: bool,
+    /// Set this to true to simulate sticky keys. A modifier that is clicked
+    /// with [`Keyboard::key`] is not entered right away, but held while the
+    /// next key that is not a modifier is entered (e.g. clicking
+    /// [`Key::Control`] and then `a` enters Ctrl+A). It is also held for the
+    /// next call of [`Keyboard::text`]. Afterwards it is cleared. Clicking a
+    /// latched modifier again clears it as well. This does not depend on the
+    /// accessibility settings of the operating system
+    pub sticky_modifiers: bool,
+    /// Set this to true to allow entering the system keys [`Key::Power`],
+    /// [`Key::Sleep`] and [`Key::WakeUp`] (depending on the platform).
+    /// Entering them can actually shut down the computer, put it to sleep or
+    /// wake it up, so [`InputError::InvalidInput`] is returned for them by
+    /// default. Only enable this if you are sure the system action is
+    /// intended (e.g. when testing the power management UI in a virtual
+    /// machine)
+    pub allow_system_keys: bool,
+    /// Set this to true if releasing a key or keycode that was not pressed
+    /// with this instance of Enigo should be a noop. By default the release
+    /// event is sent anyways. Some compositors log a warning for such
+    /// unmatched releases, so this is useful if keys are released
+    /// defensively (e.g. at the end of a script)
+    pub ignore_unmatched_release: bool,
+    /// Time in milliseconds between the press and the release of a mouse
+    /// button that is clicked with [`Direction::Click`]. If it is zero, the
+    /// backend decides when the release is sent. Some applications only
+    /// recognize clicks if the button was held for a moment
+    pub click_press_release_gap_ms: u32,
+    /// Time in milliseconds between the clicks of [`Mouse::multi_click`]. It
+    /// has to be shorter than the double click interval of the system
+    pub double_click_gap_ms: u32,
+    /// Location at which the keyboard and mouse events are posted on macOS.
+    /// Have a look at the documentation of [`EventTap`] to see which
+    /// applications receive the events
+    pub macos_event_tap: EventTap,
+    /// Maximum number of keysyms that are mapped to unused keycodes at the
+    /// same time on Linux. Characters that are not part of the keyboard
+    /// layout get mapped to an unused keycode when they are entered. By
+    /// default, all of them get unmapped once no unused keycodes are left.
+    /// If this is set, the least recently used keysym that is not held gets
+    /// unmapped as soon as the limit is reached. This avoids holding on to
+    /// the keycodes in long running processes and the stall of unmapping all
+    /// of them at once. This is only supported with x11rb and Wayland
+    pub linux_keysym_cache_size: Option<usize>,
+    /// Set this to true if the coordinates of [`Mouse::move_mouse`],
+    /// [`Mouse::location`] and [`Mouse::main_display`] should be in physical
+    /// pixels instead of logical pixels on displays with a scale factor (e.g.
+    /// high-DPI displays). On Wayland the coordinates are converted with the
+    /// scale of the output and on macOS with the ratio of pixels to points of
+    /// the main display. On X11 there is no scale factor and on Windows the
+    /// coordinates depend on the DPI awareness of the process, so this setting
+    /// has no effect there
+    pub use_physical_pixels: bool,
+    /// Set this to true to invert the direction of horizontal scrolling with
+    /// [`Mouse::scroll`]. Positive lengths then scroll to the left. The
+    /// horizontal scroll buttons are swapped as well
+    pub invert_scroll_x: bool,
+    /// Set this to true to invert the direction of vertical scrolling with
+    /// [`Mouse::scroll`]. Positive lengths then scroll up and the vertical
+    /// scroll buttons are swapped as well. This is
+    /// independent of [`Settings::invert_scroll_x`], because some systems
+    /// only use natural scrolling for one of the axes
+    pub invert_scroll_y: bool,
+    /// Id of the process that all keyboard and mouse events are posted to on
+    /// macOS. If it is set, the events are delivered to the process with
+    /// `CGEventPostToPid` instead of being posted at the
+    /// [`Settings::macos_event_tap`]. This allows automating an application
+    /// in the background. The application needs to be running and enigo needs
+    /// the accessibility permission. Some applications ignore events that
+    /// are posted to them directly
+    #[cfg(feature = "platform_specific")]
+    pub macos_target_pid: Option<i32>,
+    /// Unicode normalization form the text is converted to before it is
+    /// entered with [`Keyboard::text`] and the other functions to enter text.
+    /// This ensures consistent results regardless of how the text was
+    /// normalized. If it is not set, the text is entered as it is
+    #[cfg(feature = "unicode")]
+    pub normalize_text: Option<NormalizationForm>,
+}
+
+/// Unicode normalization form that text can be converted to before it is
+/// entered
+#[cfg(feature = "unicode")]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NormalizationForm {
+    /// Canonical composition (e.g. 'e' followed by a combining acute accent
+    /// becomes 'é')
+    Nfc,
+    /// Canonical decomposition (e.g. 'é' becomes 'e' followed by a combining
+    /// acute accent)
+    Nfd,
+}
+
+#[cfg(feature = "unicode")]
+impl NormalizationForm {
+    /// Convert the text to the normalization form
+    #[must_use]
+    pub fn normalize(self, text: &str) -> String {
+        use unicode_normalization::UnicodeNormalization;
+
+        match self {
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfd => text.nfd().collect(),
+        }
+    }
+}
+
+/// Location at which events are posted on macOS. Applications and accessibility
+/// tools that monitor the events with an event tap only see the events that
+/// were posted at or before the location of their tap.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EventTap {
+    /// The events are posted where the HID system events enter the window
+    /// server. They are seen by all event taps, just like real input
+    Hid,
+    /// The events are posted where the HID system and remote control events
+    /// enter a login session. Event taps at the HID location (e.g. some
+    /// keyboard remapping tools) do not see them
+    #[default]
+    Session,
+    /// The events are posted where the session events have been annotated to
+    /// flow to an application. Only event taps of the targeted application
+    /// see them and global hotkeys and accessibility tools are skipped
+    AnnotatedSession,
+}
+
+/// Backend to simulate the input with on Linux
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// The Wayland protocols `virtual_keyboard`, `input_method` and
+    /// `virtual_pointer`. This needs the `wayland` feature
+    Wayland,
+    /// The X11 server (or Xwayland). This needs the `x11rb` or `xdo` feature
+    X11,
+    /// The `libei` library. This needs the `libei` feature
+    Libei,
+}
+
+/// Check if the direction releases a key or keycode that is not held
+pub(crate) fn is_unmatched_release<T: PartialEq>(
+    held: &[T],
+    item: &T,
+    direction: Direction,
+) -> bool {
+    direction == Direction::Release && !held.contains(item)
+}
+
+/// Release the held keys and keycodes in the reverse order of how they were
+/// pressed (e.g. Shift before Ctrl if Ctrl was pressed first). Applications
+/// that track the state of the modifiers expect them to be released this way
+pub(crate) fn release_held<K: Keyboard + ?Sized>(keyboard: &mut K, keys: &[Key], keycodes: &[u16]) {
+    for &key in keys.iter().rev() {
+        if keyboard.key(key, Direction::Release).is_err() {
+            error!("unable to release {key:?}");
+        }
+    }
+    for &keycode in keycodes.iter().rev() {
+        if keyboard.raw(keycode, Direction::Release).is_err() {
+            error!("unable to release {keycode:?}");
+        }
+    }
+}
+
+/// Modifiers that were clicked while the `sticky_modifiers` of the [`Settings`]
+/// are enabled. They are held for the next key that is not a modifier and are
+/// cleared afterwards
+#[derive(Debug, Default)]
+pub(crate) struct StickyModifiers {
+    enabled: bool,
+    latched: Vec<Key>,
+}
+
+impl StickyModifiers {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            latched: Vec::new(),
+        }
+    }
+
+    /// Latch the key if it is a clicked modifier. Clicking a latched modifier
+    /// again clears it. Returns true if the key was handled and must not be
+    /// entered
+    pub(crate) fn latch(&mut self, key: Key, direction: Direction) -> bool {
+        if !self.enabled || direction != Direction::Click || !key.is_modifier() {
+            return false;
+        }
+        if let Some(idx) = self.latched.iter().position(|&latched| latched == key) {
+            self.latched.remove(idx);
+            debug!("cleared the latched modifier {key:?}");
+        } else {
+            self.latched.push(key);
+            debug!("latched the modifier {key:?}");
+        }
+        true
+    }
+
+    /// Take the latched modifiers if they have to be held for the key. This is
+    /// the case if the key is pressed or clicked and is not a modifier
+    pub(crate) fn take(&mut self, key: Key, direction: Direction) -> Vec<Key> {
+        if direction == Direction::Release || key.is_modifier() {
+            return Vec::new();
+        }
+        std::mem::take(&mut self.latched)
+    }
+
+    /// Take all latched modifiers
+    pub(crate) fn take_all(&mut self) -> Vec<Key> {
+        std::mem::take(&mut self.latched)
+    }
+
+    /// Check if any modifiers are latched
+    #[cfg(target_os = "windows")]
+    pub(crate) fn is_latched(&self) -> bool {
+        !self.latched.is_empty()
+    }
+}
+
+/// Apply the sticky modifiers to the key. A clicked modifier is latched and a
+/// key that is not a modifier is entered while the latched modifiers are held.
+/// Returns `None` if the key has to be entered as usual
+pub(crate) fn enter_sticky<K: Keyboard + ?Sized>(
+    keyboard: &mut K,
+    sticky_modifiers: fn(&mut K) -> &mut StickyModifiers,
+    key: Key,
+    direction: Direction,
+) -> Option<InputResult<()>> {
+    let sticky_modifiers = sticky_modifiers(keyboard);
+    if sticky_modifiers.latch(key, direction) {
+        return Some(Ok(()));
+    }
+    let latched = sticky_modifiers.take(key, direction);
+    if latched.is_empty() {
+        return None;
+    }
+    Some(hold_modifiers(keyboard, latched, |keyboard| {
+        keyboard.key(key, direction)
+    }))
+}
+
+/// Press the keys one after the other. If one of them cannot be pressed, the
+/// keys that were already pressed are released again
+pub(crate) fn press_keys<K: Keyboard + ?Sized>(keyboard: &mut K, keys: &[Key]) -> InputResult<()> {
+    for (pressed, key) in keys.iter().enumerate() {
+        if let Err(e) = keyboard.key(*key, Direction::Press) {
+            for key in keys[..pressed].iter().rev() {
+                if let Err(e) = keyboard.key(*key, Direction::Release) {
+                    error!("unable to release the key {key:?}: {e}");
+                }
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Release all of the keys, even if releasing one of them fails. The first
+/// error is returned
+pub(crate) fn release_keys<K: Keyboard + ?Sized>(
+    keyboard: &mut K,
+    keys: &[Key],
+) -> InputResult<()> {
+    let mut result = Ok(());
+    for key in keys {
+        if let Err(e) = keyboard.key(*key, Direction::Release) {
+            error!("unable to release the key {key:?}: {e}");
+            if result.is_ok() {
+                result = Err(e);
+            }
+        }
+    }
+    result
+}
+
+/// Hold the modifiers while `enter` is called. They are released in the
+/// reverse order, even if `enter` failed
+pub(crate) fn hold_modifiers<K: Keyboard + ?Sized>(
+    keyboard: &mut K,
+    mut modifiers: Vec<Key>,
+    enter: impl FnOnce(&mut K) -> InputResult<()>,
+) -> InputResult<()> {
+    keyboard.press_keys(&modifiers)?;
+    let result = enter(keyboard);
+    modifiers.reverse();
+    result.and(keyboard.release_keys(&modifiers))
+}
+
+/// Parse a shortcut in human notation (e.g. "Ctrl+Shift+K") into the
+/// modifiers and the key
+pub(crate) fn parse_shortcut(shortcut: &str) -> InputResult<(Vec<Key>, Key)> {
+    let mut keys = shortcut
+        .split('+')
+        .map(|name| {
+            Key::from_shortcut_name(name.trim()).ok_or_else(|| {
+                debug!("unknown key {name:?} in the shortcut {shortcut:?}");
+                InputError::InvalidInput("the shortcut contains an unknown key")
+            })
+        })
+        .collect::<InputResult<Vec<_>>>()?;
+    // It is okay to unwrap here, because splitting always returns at least one item
+    let key = keys.pop().unwrap();
+    Ok((keys, key))
+}
+
+/// Invert the length of a scroll if it was requested for the axis
+pub(crate) fn scroll_length(length: i32, axis: Axis, invert_x: bool, invert_y: bool) -> i32 {
+    let invert = match axis {
+        Axis::Horizontal => invert_x,
+        Axis::Vertical => invert_y,
+    };
+    if invert {
+        debug!("the scroll direction is inverted");
+        length.saturating_neg()
+    } else {
+        length
+    }
+}
+
+/// Swap the scroll button for the one of the opposite direction if scrolling
+/// on its axis is inverted. Other buttons are returned as they are
+pub(crate) fn scroll_button(button: Button, invert_x: bool, invert_y: bool) -> Button {
+    match button {
+        Button::ScrollUp if invert_y => Button::ScrollDown,
+        Button::ScrollDown if invert_y => Button::ScrollUp,
+        Button::ScrollLeft if invert_x => Button::ScrollRight,
+        Button::ScrollRight if invert_x => Button::ScrollLeft,
+        button => button,
+    }
+}
+
+/// Create the prefix for the log messages of an instance of Enigo
+pub(crate) fn log_prefix(instance_name: Option<&str>) -> String {
+    instance_name.map_or_else(String::new, |name| format!("[{name}] "))
 }
 
 impl Default for Settings {
@@ -449,9 +1967,776 @@ impl Default for Settings {
             linux_delay: 12,
             x11_display: None,
             wayland_display: None,
+            wayland_seat: None,
+            wayland_disable_input_method: false,
             windows_dw_extra_info: None,
             event_source_user_data: None,
             release_keys_when_dropped: true,
+            disable_autorepeat_on_press: false,
+            x11_use_send_event: false,
+            x11_allow_remap: true,
+            x11_keyboard_device: None,
+            x11_pointer_device: None,
+            prefer_backend: None,
+            backend_priority: Vec::new(),
+            instance_name: None,
+            ignore_carriage_return: false,
+            max_text_length: None,
+            fallback_to_paste: false,
+            sticky_modifiers: false,
+            allow_system_keys: false,
+            ignore_unmatched_release: false,
+            click_press_release_gap_ms: 0,
+            double_click_gap_ms: 50,
+            macos_event_tap: EventTap::default(),
+            linux_keysym_cache_size: None,
+            use_physical_pixels: false,
+            invert_scroll_x: false,
+            invert_scroll_y: false,
+            #[cfg(feature = "platform_specific")]
+            macos_target_pid: None,
+            #[cfg(feature = "unicode")]
+            normalize_text: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the entered keys, optionally supports fast text entry and fails
+    /// to enter a key
+    #[derive(Default)]
+    struct Recorder {
+        fast: bool,
+        fail_on: Option<Key>,
+        no_repeat: bool,
+        keys: Vec<(Key, Direction)>,
+        keycodes: Vec<(u16, Direction)>,
+        max_len: Option<usize>,
+        sticky: StickyModifiers,
+        paste: bool,
+        pasted: Vec<String>,
+        #[cfg(feature = "unicode")]
+        normalize: Option<NormalizationForm>,
+    }
+
+    impl Keyboard for Recorder {
+        fn fast_text(&mut self, _text: &str) -> InputResult<Option<()>> {
+            Ok(self.fast.then_some(()))
+        }
+
+        #[cfg(feature = "unicode")]
+        fn normalization_form(&self) -> Option<NormalizationForm> {
+            self.normalize
+        }
+
+        fn max_text_length(&self) -> Option<usize> {
+            self.max_len
+        }
+
+        fn native_key_repeat(&self) -> bool {
+            !self.no_repeat
+        }
+
+        fn take_sticky_modifiers(&mut self) -> Vec<Key> {
+            self.sticky.take_all()
+        }
+
+        fn paste_text(&mut self, text: &str) -> InputResult<Option<()>> {
+            if !self.paste {
+                return Ok(None);
+            }
+            self.pasted.push(text.to_string());
+            Ok(Some(()))
+        }
+
+        fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+            if self.fail_on == Some(key) {
+                return Err(InputError::Mapping("failed to map the key".to_string()));
+            }
+            self.keys.push((key, direction));
+            Ok(())
+        }
+
+        fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
+            self.keycodes.push((keycode, direction));
+            Ok(())
+        }
+    }
+
+    /// Records the button events
+    #[derive(Default)]
+    struct Clicker {
+        buttons: Vec<(Button, Direction)>,
+        moves: Vec<(i32, i32, Coordinate)>,
+        location: Option<(i32, i32)>,
+        display: Option<(i32, i32)>, // size of the main display instead of 1920x1080
+        fail_buttons: bool,
+        click_gap: Duration,
+        held: Vec<Key>,
+        held_during_buttons: Vec<Vec<Key>>,
+    }
+
+    impl Keyboard for Clicker {
+        fn fast_text(&mut self, _text: &str) -> InputResult<Option<()>> {
+            Ok(None)
+        }
+
+        fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
+            match direction {
+                Direction::Press => self.held.push(key),
+                Direction::Release => self.held.retain(|&k| k != key),
+                Direction::Click => {}
+            }
+            Ok(())
+        }
+
+        fn raw(&mut self, _keycode: u16, _direction: Direction) -> InputResult<()> {
+            Ok(())
+        }
+    }
+
+    impl Mouse for Clicker {
+        fn double_click_gap(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+            if self.fail_buttons {
+                return Err(InputError::Simulate("failed to send the button event"));
+            }
+            if needs_click_gap(button, direction, self.click_gap) {
+                return click_with_gap(self, button, self.click_gap);
+            }
+            self.buttons.push((button, direction));
+            self.held_during_buttons.push(self.held.clone());
+            Ok(())
+        }
+
+        fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+            self.moves.push((x, y, coordinate));
+            Ok(())
+        }
+
+        fn scroll(&mut self, _length: i32, _axis: Axis) -> InputResult<u32> {
+            Ok(1)
+        }
+
+        fn main_display(&self) -> InputResult<(i32, i32)> {
+            Ok(self.display.unwrap_or((1920, 1080)))
+        }
+
+        fn location(&self) -> InputResult<(i32, i32)> {
+            self.location
+                .ok_or(InputError::Unsupported("the location is unknown"))
+        }
+    }
+
+    #[test]
+    fn button_with_mods_holds_the_modifiers() {
+        let mut clicker = Clicker::default();
+        clicker
+            .button_with_mods(Button::Left, Direction::Click, &[Key::Control])
+            .unwrap();
+        assert_eq!(clicker.buttons, vec![(Button::Left, Direction::Click)]);
+        assert_eq!(clicker.held_during_buttons, vec![vec![Key::Control]]);
+        assert!(clicker.held.is_empty());
+
+        // The modifiers are released if the button event failed
+        let mut clicker = Clicker {
+            fail_buttons: true,
+            ..Default::default()
+        };
+        assert!(clicker
+            .button_with_mods(Button::Right, Direction::Click, &[Key::Shift, Key::Control])
+            .is_err());
+        assert!(clicker.held.is_empty());
+    }
+
+    #[test]
+    fn click_at_relative_coordinates() {
+        let mut clicker = Clicker {
+            location: Some((100, 1000)),
+            ..Default::default()
+        };
+        clicker
+            .click_at(-20, 30, Coordinate::Rel, Button::Left)
+            .unwrap();
+        clicker
+            .click_at(-200, 200, Coordinate::Rel, Button::Left)
+            .unwrap();
+        clicker
+            .click_at(-200, 200, Coordinate::Abs, Button::Left)
+            .unwrap();
+        // The offset is added to the location and clamped to the display
+        assert_eq!(
+            clicker.moves,
+            vec![
+                (80, 1030, Coordinate::Abs),
+                (0, 1079, Coordinate::Abs),
+                (-200, 200, Coordinate::Abs),
+            ]
+        );
+        assert_eq!(clicker.buttons, vec![(Button::Left, Direction::Click); 3]);
+
+        // Without a known location, the mouse is moved relatively
+        let mut clicker = Clicker::default();
+        clicker
+            .click_at(5, 5, Coordinate::Rel, Button::Right)
+            .unwrap();
+        assert_eq!(clicker.moves, vec![(5, 5, Coordinate::Rel)]);
+
+        // The location cannot be clamped to an empty display or if the mouse
+        // is on another display
+        let mut clicker = Clicker {
+            location: Some((0, 0)),
+            display: Some((0, 0)),
+            ..Default::default()
+        };
+        assert!(clicker
+            .click_at(5, 5, Coordinate::Rel, Button::Left)
+            .is_err());
+        let mut clicker = Clicker {
+            location: Some((-100, 50)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            clicker.click_at(5, 5, Coordinate::Rel, Button::Left),
+            Err(InputError::InvalidInput(_))
+        ));
+        assert!(clicker.moves.is_empty() && clicker.buttons.is_empty());
+    }
+
+    #[test]
+    fn windows_are_compared_without_the_title() {
+        let window = WindowInfo {
+            title: "Inbox".to_string(),
+            class: Some("firefox".to_string()),
+            pid: Some(42),
+            id: 7,
+        };
+        let renamed = WindowInfo {
+            title: "Inbox (1)".to_string(),
+            ..window.clone()
+        };
+        assert!(window.is_same_window(&renamed));
+        let other = WindowInfo {
+            id: 8,
+            ..window.clone()
+        };
+        assert!(!window.is_same_window(&other));
+        let other = WindowInfo {
+            pid: Some(43),
+            ..window.clone()
+        };
+        assert!(!window.is_same_window(&other));
+    }
+
+    #[test]
+    fn scroll_axes_are_inverted_independently() {
+        for (invert_x, invert_y) in [(false, false), (true, false), (false, true), (true, true)] {
+            let sign_x = if invert_x { -1 } else { 1 };
+            let sign_y = if invert_y { -1 } else { 1 };
+            for length in [-3, 1, 5] {
+                assert_eq!(
+                    scroll_length(length, Axis::Horizontal, invert_x, invert_y),
+                    sign_x * length
+                );
+                assert_eq!(
+                    scroll_length(length, Axis::Vertical, invert_x, invert_y),
+                    sign_y * length
+                );
+            }
+        }
+        assert_eq!(
+            scroll_length(i32::MIN, Axis::Vertical, false, true),
+            i32::MAX
+        );
+
+        assert_eq!(
+            scroll_button(Button::ScrollUp, true, false),
+            Button::ScrollUp
+        );
+        assert_eq!(
+            scroll_button(Button::ScrollUp, false, true),
+            Button::ScrollDown
+        );
+        assert_eq!(
+            scroll_button(Button::ScrollLeft, true, false),
+            Button::ScrollRight
+        );
+        assert_eq!(scroll_button(Button::Left, true, true), Button::Left);
+    }
+
+    #[test]
+    fn multi_click_and_click_with_gap() {
+        let mut clicker = Clicker::default();
+        clicker.multi_click(Button::Left, 0).unwrap();
+        assert!(clicker.buttons.is_empty());
+        clicker.multi_click(Button::Left, 3).unwrap();
+        assert_eq!(clicker.buttons, vec![(Button::Left, Direction::Click); 3]);
+
+        // The clicks are redirected like in the button functions of the
+        // platforms. Scroll buttons must not be redirected again
+        let mut clicker = Clicker {
+            click_gap: Duration::from_millis(1),
+            ..Default::default()
+        };
+        clicker.button(Button::Right, Direction::Click).unwrap();
+        clicker.button(Button::ScrollUp, Direction::Click).unwrap();
+        clicker.button(Button::Left, Direction::Press).unwrap();
+        clicker.click_gap = Duration::ZERO;
+        clicker.button(Button::Left, Direction::Click).unwrap();
+        assert_eq!(
+            clicker.buttons,
+            vec![
+                (Button::Right, Direction::Press),
+                (Button::Right, Direction::Release),
+                (Button::ScrollUp, Direction::Click),
+                (Button::Left, Direction::Press),
+                (Button::Left, Direction::Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_robust_pastes_the_rest() {
+        let mut recorder = Recorder {
+            fail_on: Some(Key::Unicode('b')),
+            paste: true,
+            ..Default::default()
+        };
+        recorder.text_robust("abc").unwrap();
+        assert_eq!(recorder.keys, vec![(Key::Unicode('a'), Direction::Click)]);
+        assert_eq!(recorder.pasted, vec!["bc".to_string()]);
+
+        // The error is returned if the text cannot be pasted
+        let mut recorder = Recorder {
+            fail_on: Some(Key::Unicode('b')),
+            ..Default::default()
+        };
+        assert!(matches!(
+            recorder.text_robust("abc"),
+            Err(InputError::Mapping(_))
+        ));
+
+        // Invalid text is never pasted
+        let mut recorder = Recorder {
+            paste: true,
+            max_len: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(recorder.text_robust("abc"), Err(TEXT_TOO_LONG));
+        assert!(recorder.pasted.is_empty());
+    }
+
+    #[test]
+    fn text_progress_per_char() {
+        let mut recorder = Recorder::default();
+        let mut progress = vec![];
+        recorder
+            .text_with_progress("ab\nc", |done, total| progress.push((done, total)))
+            .unwrap();
+        assert_eq!(progress, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+        assert_eq!(recorder.keys.len(), 4);
+    }
+
+    #[test]
+    fn text_progress_fast_text() {
+        let mut recorder = Recorder {
+            fast: true,
+            ..Default::default()
+        };
+        let mut progress = vec![];
+        recorder
+            .text_with_progress("abc", |done, total| progress.push((done, total)))
+            .unwrap();
+        assert_eq!(progress, vec![(3, 3)]);
+        assert!(recorder.keys.is_empty());
+    }
+
+    #[test]
+    fn web_button_round_trip() {
+        for &button in BUTTONS {
+            if let Some(web_button) = button.to_web_button() {
+                assert_eq!(Button::from_web_button(web_button), Some(button));
+            }
+        }
+        assert_eq!(Button::Right.to_web_button(), Some(2));
+        #[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+        assert_eq!(Button::Forward.to_web_button(), Some(4));
+        assert_eq!(Button::ScrollUp.to_web_button(), None);
+        assert_eq!(Button::from_web_button(5), None);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn text_is_normalized() {
+        let mut recorder = Recorder {
+            normalize: Some(NormalizationForm::Nfc),
+            ..Default::default()
+        };
+        recorder.text("e\u{301}").unwrap();
+        assert_eq!(recorder.keys, vec![(Key::Unicode('é'), Direction::Click)]);
+    }
+
+    #[test]
+    fn text_keeps_logical_order_of_bidi_text() {
+        let mut recorder = Recorder::default();
+        recorder.text("ab سلام").unwrap();
+        assert_eq!(
+            recorder.keys,
+            "ab سلام"
+                .chars()
+                .map(|c| (Key::Unicode(c), Direction::Click))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn hold_key_repeats_without_native_repeat() {
+        let mut recorder = Recorder {
+            no_repeat: true,
+            ..Default::default()
+        };
+        recorder
+            .hold_key_with_repeat(Key::DownArrow, Duration::from_millis(25), 100)
+            .unwrap();
+        let mut expected = vec![(Key::DownArrow, Direction::Press); 3];
+        expected.push((Key::DownArrow, Direction::Release));
+        assert_eq!(recorder.keys, expected);
+
+        let mut recorder = Recorder::default();
+        recorder
+            .hold_key_with_repeat(Key::DownArrow, Duration::from_millis(25), 100)
+            .unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::DownArrow, Direction::Press),
+                (Key::DownArrow, Direction::Release)
+            ]
+        );
+    }
+
+    #[test]
+    fn text_from_reader_with_split_utf8_sequences() {
+        /// Returns a single byte per read
+        struct ByteReader<'a>(&'a [u8]);
+
+        impl std::io::Read for ByteReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let Some((&byte, rest)) = self.0.split_first() else {
+                    return Ok(0);
+                };
+                buf[0] = byte;
+                self.0 = rest;
+                Ok(1)
+            }
+        }
+
+        let mut recorder = Recorder::default();
+        let entered = recorder
+            .text_from_reader(ByteReader("aé❤\n".as_bytes()))
+            .unwrap();
+        assert_eq!(entered, 4);
+        assert_eq!(
+            recorder.keys,
+            "aé❤\n"
+                .chars()
+                .map(|c| (Key::Unicode(c), Direction::Click))
+                .collect::<Vec<_>>()
+        );
+
+        let mut recorder = Recorder::default();
+        assert!(matches!(
+            recorder.text_from_reader(&b"a\xff"[..]),
+            Err(InputError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            recorder.text_from_reader(&"é".as_bytes()[..1]),
+            Err(InputError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn text_via_keys_skips_fast_text() {
+        let mut recorder = Recorder {
+            fast: true,
+            ..Default::default()
+        };
+        recorder.text_via_keys("a\tb").unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Unicode('a'), Direction::Click),
+                (Key::Unicode('\t'), Direction::Click),
+                (Key::Unicode('b'), Direction::Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_batch_is_entered_right_away_by_default() {
+        let mut recorder = Recorder::default();
+        recorder.begin_text().unwrap();
+        recorder.append_text("ab").unwrap();
+        assert_eq!(recorder.keys.len(), 2);
+        recorder.append_text("c").unwrap();
+        recorder.end_text().unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Unicode('a'), Direction::Click),
+                (Key::Unicode('b'), Direction::Click),
+                (Key::Unicode('c'), Direction::Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn held_keys_are_released_in_reverse_order() {
+        let mut recorder = Recorder::default();
+        release_held(
+            &mut recorder,
+            &[Key::Control, Key::Shift, Key::Unicode('a')],
+            &[37, 50],
+        );
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Unicode('a'), Direction::Release),
+                (Key::Shift, Direction::Release),
+                (Key::Control, Direction::Release),
+            ]
+        );
+        assert_eq!(
+            recorder.keycodes,
+            vec![(50, Direction::Release), (37, Direction::Release)]
+        );
+    }
+
+    #[test]
+    fn sticky_modifiers_are_held_for_the_next_key() {
+        // Enter the key like the key functions of the platforms
+        fn key(recorder: &mut Recorder, key: Key, direction: Direction) -> InputResult<()> {
+            enter_sticky(recorder, |recorder| &mut recorder.sticky, key, direction)
+                .unwrap_or_else(|| recorder.key(key, direction))
+        }
+
+        let mut recorder = Recorder {
+            sticky: StickyModifiers::new(true),
+            ..Default::default()
+        };
+        key(&mut recorder, Key::Control, Direction::Click).unwrap();
+        assert!(recorder.keys.is_empty());
+        key(&mut recorder, Key::Unicode('a'), Direction::Click).unwrap();
+        key(&mut recorder, Key::Unicode('b'), Direction::Click).unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Control, Direction::Press),
+                (Key::Unicode('a'), Direction::Click),
+                (Key::Control, Direction::Release),
+                (Key::Unicode('b'), Direction::Click),
+            ]
+        );
+
+        // Clicking a latched modifier again clears it
+        recorder.keys.clear();
+        key(&mut recorder, Key::Shift, Direction::Click).unwrap();
+        key(&mut recorder, Key::Alt, Direction::Click).unwrap();
+        key(&mut recorder, Key::Shift, Direction::Click).unwrap();
+        recorder.text("c").unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Alt, Direction::Press),
+                (Key::Unicode('c'), Direction::Click),
+                (Key::Alt, Direction::Release),
+            ]
+        );
+
+        // Without sticky modifiers, clicking a modifier enters it right away
+        let mut recorder = Recorder::default();
+        key(&mut recorder, Key::Control, Direction::Click).unwrap();
+        assert_eq!(recorder.keys, vec![(Key::Control, Direction::Click)]);
+    }
+
+    #[test]
+    fn press_keys_releases_pressed_keys_on_error() {
+        let mut recorder = Recorder {
+            fail_on: Some(Key::Unicode('s')),
+            ..Default::default()
+        };
+        let keys = [Key::Unicode('w'), Key::Unicode('a'), Key::Unicode('s')];
+        assert!(recorder.press_keys(&keys).is_err());
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Unicode('w'), Direction::Press),
+                (Key::Unicode('a'), Direction::Press),
+                (Key::Unicode('a'), Direction::Release),
+                (Key::Unicode('w'), Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_is_cut_off_after_the_maximum_length() {
+        let mut recorder = Recorder {
+            max_len: Some(3),
+            ..Default::default()
+        };
+        assert!(matches!(
+            recorder.text("äb\ncd"),
+            Err(InputError::InvalidInput(_))
+        ));
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Unicode('ä'), Direction::Click),
+                (Key::Unicode('b'), Direction::Click),
+                (Key::Unicode('\n'), Direction::Click),
+            ]
+        );
+
+        // Texts up to the maximum length are entered completely
+        let mut recorder = Recorder {
+            max_len: Some(3),
+            ..Default::default()
+        };
+        recorder.text("abc").unwrap();
+        assert_eq!(recorder.keys.len(), 3);
+        assert_eq!(recorder.text_checked("abcde").unwrap(), vec!['d', 'e']);
+        assert_eq!(recorder.keys.len(), 6);
+    }
+
+    #[test]
+    fn text_checked_returns_dropped_chars() {
+        let mut recorder = Recorder {
+            fail_on: Some(Key::Unicode('b')),
+            ..Default::default()
+        };
+        let dropped = recorder.text_checked("abc\0\nb").unwrap();
+        assert_eq!(dropped, vec!['b', '\0', 'b']);
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Unicode('a'), Direction::Click),
+                (Key::Unicode('c'), Direction::Click),
+                (Key::Unicode('\n'), Direction::Click),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_releases() {
+        let held = [Key::Shift, Key::Unicode('a')];
+        assert!(is_unmatched_release(
+            &held,
+            &Key::Control,
+            Direction::Release
+        ));
+        assert!(!is_unmatched_release(
+            &held,
+            &Key::Shift,
+            Direction::Release
+        ));
+        assert!(!is_unmatched_release(
+            &held,
+            &Key::Control,
+            Direction::Press
+        ));
+        assert!(!is_unmatched_release(
+            &held,
+            &Key::Control,
+            Direction::Click
+        ));
+        assert!(is_unmatched_release(&[], &42u16, Direction::Release));
+    }
+
+    #[test]
+    fn key_combo_releases_modifiers_on_error() {
+        let mut recorder = Recorder {
+            fail_on: Some(Key::Unicode('a')),
+            ..Default::default()
+        };
+        assert!(recorder
+            .key_combo(&[Key::Control, Key::Shift], Key::Unicode('a'))
+            .is_err());
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Control, Direction::Press),
+                (Key::Shift, Direction::Press),
+                (Key::Shift, Direction::Release),
+                (Key::Control, Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_shortcuts() {
+        assert_eq!(
+            parse_shortcut("Ctrl+Shift+K").unwrap(),
+            (vec![Key::Control, Key::Shift], Key::Unicode('k'))
+        );
+        assert_eq!(
+            parse_shortcut("Cmd+Option+Esc").unwrap(),
+            (vec![Key::Meta, Key::Alt], Key::Escape)
+        );
+        assert_eq!(
+            parse_shortcut(" win + opt + f5 ").unwrap(),
+            (vec![Key::Meta, Key::Alt], Key::F5)
+        );
+        assert_eq!(
+            parse_shortcut("ctrl+plus").unwrap(),
+            (vec![Key::Control], Key::Unicode('+'))
+        );
+        assert_eq!(parse_shortcut("Tab").unwrap(), (vec![], Key::Tab));
+        assert_eq!(
+            parse_shortcut("Shift+Menu").unwrap(),
+            (vec![Key::Shift], Key::Menu)
+        );
+        for shortcut in ["", "Ctrl+", "Ctrl++", "Ctrl+Foo", "Hyper+A"] {
+            assert!(parse_shortcut(shortcut).is_err(), "{shortcut:?}");
+        }
+    }
+
+    #[test]
+    fn web_codes_of_keys() {
+        assert_eq!(Key::Control.web_code(), Some("ControlLeft"));
+        assert_eq!(Key::RShift.web_code(), Some("ShiftRight"));
+        assert_eq!(Key::Meta.web_code(), Some("MetaLeft"));
+        assert_eq!(Key::Return.web_code(), Some("Enter"));
+        assert_eq!(Key::NumpadEnter.web_code(), Some("NumpadEnter"));
+        assert_eq!(Key::F1.web_code(), Some("F1"));
+        assert_eq!(Key::UpArrow.web_code(), Some("ArrowUp"));
+        assert_eq!(Key::MediaPlayPause.web_code(), Some("MediaPlayPause"));
+        assert_eq!(Key::Menu.web_code(), Some("ContextMenu"));
+        // The physical key of these keys is not known
+        assert_eq!(Key::Unicode('a').web_code(), None);
+        assert_eq!(Key::Other(0x61).web_code(), None);
+    }
+
+    #[test]
+    fn shortcut_is_entered_as_key_combo() {
+        let mut recorder = Recorder::default();
+        recorder.shortcut("Alt+Tab").unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                (Key::Alt, Direction::Press),
+                (Key::Tab, Direction::Click),
+                (Key::Alt, Direction::Release),
+            ]
+        );
+        // Nothing is entered if the shortcut is invalid
+        let mut recorder = Recorder::default();
+        assert!(recorder.shortcut("Alt+Foo").is_err());
+        assert!(recorder.keys.is_empty());
+    }
+}
@@ -2,11 +2,14 @@ use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
 use std::fmt::Display;
 
+#[cfg(feature = "wayland")]
+use log::error;
 use log::{debug, trace};
 pub(super) use xkeysym::{KeyCode, Keysym};
 
+use super::Stats;
 #[cfg(feature = "wayland")]
-use crate::keycodes::ModifierBitflag;
+use crate::keycodes::{Modifier, ModifierBitflag};
 use crate::{Direction, InputError, InputResult, Key};
 
 /// The "empty" keyboard symbol.
@@ -19,18 +22,28 @@ const DEFAULT_DELAY: u32 = 12;
 #[derive(Debug)]
 pub struct KeyMap<Keycode> {
     pub(super) additionally_mapped: HashMap<Keysym, Keycode>,
+    recently_used: VecDeque<Keysym>, // additionally mapped keysyms, least recently used first
+    cache_size: Option<usize>,       // maximum number of additionally mapped keysyms
+    remaps: u64,                     // number of times a keysym was mapped or unmapped
+    layout_keys: u64,                // number of keys entered with a keycode of the layout
+    mapped_keys: u64,                // number of keys entered with an additionally mapped keycode
     keycode_min: Keycode,
     keycode_max: Keycode,
     keysyms_per_keycode: u8,
     keysyms: Vec<u32>,
 
     unused_keycodes: VecDeque<Keycode>,
-    held_keycodes: Vec<Keycode>, // cannot get unmapped
+    pub(super) held_keycodes: Vec<Keycode>, // cannot get unmapped
     needs_regeneration: bool,
     #[cfg(feature = "wayland")]
-    pub(super) file: Option<std::fs::File>, // temporary file that contains the keymap
+    pub(super) file: Option<std::fs::File>, // sealed memfd that contains the keymap
+    #[cfg(feature = "wayland")]
+    file_size: usize, // size of the keymap in the file in bytes
+    #[cfg(feature = "wayland")]
+    layout: Option<String>, // keymap of a named layout in the xkb text format
     #[cfg(feature = "wayland")]
-    modifiers: ModifierBitflag, // state of the modifiers
+    held_modifiers: Vec<Modifier>, /* a modifier can be held by multiple keys (e.g. LShift and
+                                    * RShift) */
     #[cfg(feature = "x11rb")]
     last_keys: Vec<Keycode>, // last pressed keycodes
     #[cfg(feature = "x11rb")]
@@ -39,6 +52,7 @@ pub struct KeyMap<Keycode> {
     last_event_before_delays: std::time::Instant, // time of the last event
     #[cfg(feature = "x11rb")]
     pending_delays: u32,
+    log_prefix: String, // prefix of the log messages of the instance of Enigo
 }
 
 // TODO: Check if the bounds can be simplified
@@ -62,16 +76,23 @@ where
         unused_keycodes: VecDeque<Keycode>,
         keysyms_per_keycode: u8,
         keysyms: Vec<u32>,
+        cache_size: Option<usize>,
+        log_prefix: &str,
     ) -> Self {
         let capacity: usize = keycode_max.try_into().unwrap() - keycode_min.try_into().unwrap();
         let capacity = capacity + 1;
         let keymap = HashMap::with_capacity(capacity);
+        let recently_used = VecDeque::with_capacity(capacity);
         let held_keycodes = vec![];
         let needs_regeneration = true;
         #[cfg(feature = "wayland")]
         let file = None;
         #[cfg(feature = "wayland")]
-        let modifiers = 0;
+        let file_size = 0;
+        #[cfg(feature = "wayland")]
+        let layout = None;
+        #[cfg(feature = "wayland")]
+        let held_modifiers = vec![];
         #[cfg(feature = "x11rb")]
         let last_keys = vec![];
         #[cfg(feature = "x11rb")]
@@ -82,6 +103,11 @@ where
         let pending_delays = 0;
         Self {
             additionally_mapped: keymap,
+            recently_used,
+            cache_size,
+            remaps: 0,
+            layout_keys: 0,
+            mapped_keys: 0,
             keycode_min,
             keycode_max,
             keysyms_per_keycode,
@@ -92,7 +118,11 @@ where
             #[cfg(feature = "wayland")]
             file,
             #[cfg(feature = "wayland")]
-            modifiers,
+            file_size,
+            #[cfg(feature = "wayland")]
+            layout,
+            #[cfg(feature = "wayland")]
+            held_modifiers,
             #[cfg(feature = "x11rb")]
             last_keys,
             #[cfg(feature = "x11rb")]
@@ -101,6 +131,7 @@ where
             last_event_before_delays,
             #[cfg(feature = "x11rb")]
             pending_delays,
+            log_prefix: log_prefix.to_string(),
         }
     }
 
@@ -126,7 +157,7 @@ where
                     if ks == keysym {
                         let i: usize = i.try_into().unwrap();
                         let i: Keycode = i.try_into().unwrap();
-                        trace!("found keysym in row {i}, col {j}");
+                        trace!("{}found keysym in row {i}, col {j}", self.log_prefix);
                         return Some(i);
                     }
                 }
@@ -140,15 +171,21 @@ where
     pub fn key_to_keycode<C: Bind<Keycode>>(&mut self, c: &C, key: Key) -> InputResult<Keycode> {
         let sym = Keysym::from(key);
 
-        if let Some(keycode) = self.keysym_to_keycode(sym) {
-            return Ok(keycode);
-        }
-
         let keycode = {
-            if let Some(&keycode) = self.additionally_mapped.get(&sym) {
+            if let Some(keycode) = self.keysym_to_keycode(sym) {
+                // The keysym is part of the keyboard layout
+                self.layout_keys += 1;
+                keycode
+            } else if let Some(&keycode) = self.additionally_mapped.get(&sym) {
                 // The keysym is already mapped and cached in the keymap
+                self.recently_used.retain(|&s| s != sym);
+                self.recently_used.push_back(sym);
+                self.mapped_keys += 1;
                 keycode
             } else {
+                self.mapped_keys += 1;
+                // Unmap the least recently used keysyms if the cache is full
+                self.evict(c)?;
                 // Unmap keysyms if there are no unused keycodes
                 self.make_room(c)?;
                 // The keysym needs to get mapped to an unused keycode.
@@ -162,6 +199,105 @@ where
         Ok(keycode)
     }
 
+    /// Get the keycode of the key without changing the keymap. An error is
+    /// returned if the key is not part of the keyboard layout
+    #[cfg(feature = "x11rb")]
+    pub fn key_to_layout_keycode(&mut self, key: Key) -> InputResult<Keycode> {
+        let sym = Keysym::from(key);
+        let Some(keycode) = self.keysym_to_keycode(sym) else {
+            debug!(
+                "{}the keysym {sym:?} is not part of the keyboard layout",
+                self.log_prefix
+            );
+            return Err(InputError::Mapping(format!("{sym:?}")));
+        };
+        self.layout_keys += 1;
+        self.update_delays(keycode);
+        Ok(keycode)
+    }
+
+    /// Get the keycode of the key in the keyboard layout and the keycodes of
+    /// the modifiers that select the level. Level 0 is the key without
+    /// modifiers, level 1 is with Shift, level 2 is with `ISO_Level3_Shift`
+    /// (`AltGr`) and level 3 is with both. The key has to be the keysym of
+    /// level 0
+    #[cfg(feature = "x11rb")]
+    pub fn level_keycodes(&self, key: Key, level: u8) -> InputResult<(Keycode, Vec<Keycode>)> {
+        let (column, modifiers): (usize, &[Keysym]) = match level {
+            0 => (0, &[]),
+            1 => (1, &[Keysym::Shift_L]),
+            2 => (4, &[Keysym::ISO_Level3_Shift]),
+            3 => (5, &[Keysym::Shift_L, Keysym::ISO_Level3_Shift]),
+            _ => {
+                return Err(InputError::InvalidInput(
+                    "the level has to be between 0 and 3",
+                ))
+            }
+        };
+        let sym = Keysym::from(key).raw();
+        let keysyms_per_keycode = usize::from(self.keysyms_per_keycode);
+        let Some(row) = self
+            .keysyms
+            .chunks(keysyms_per_keycode.max(1))
+            .position(|syms| syms.first() == Some(&sym))
+        else {
+            debug!(
+                "{}the keysym {sym:?} is not part of the keyboard layout",
+                self.log_prefix
+            );
+            return Err(InputError::Mapping(format!("{sym:?}")));
+        };
+        let start = row * keysyms_per_keycode;
+        if column >= keysyms_per_keycode || self.keysyms[start + column] == NO_SYMBOL.raw() {
+            return Err(InputError::InvalidInput("the key does not have the level"));
+        }
+        let keycode_min: usize = self.keycode_min.try_into().unwrap();
+        let keycode = (keycode_min + row).try_into().unwrap();
+        let modifiers = modifiers
+            .iter()
+            .map(|&modifier| {
+                self.keysym_to_keycode(modifier).ok_or_else(|| {
+                    debug!(
+                        "{}the modifier {modifier:?} is not part of the keyboard layout",
+                        self.log_prefix
+                    );
+                    InputError::Mapping(format!("{modifier:?}"))
+                })
+            })
+            .collect::<InputResult<_>>()?;
+        Ok((keycode, modifiers))
+    }
+
+    /// Check if the key is part of the keyboard layout, so it can be entered
+    /// without changing the keymap
+    #[cfg(feature = "x11rb")]
+    pub fn is_in_layout(&self, key: Key) -> bool {
+        self.keysym_to_keycode(Keysym::from(key)).is_some()
+    }
+
+    /// Get the number of changes of the keymap and how many keys were entered
+    /// with a keycode of the layout or an additionally mapped one
+    pub fn stats(&self) -> Stats {
+        Stats {
+            remaps: self.remaps,
+            layout_keys: self.layout_keys,
+            mapped_keys: self.mapped_keys,
+            ..Default::default()
+        }
+    }
+
+    /// Reset the stats to zero
+    pub fn reset_stats(&mut self) {
+        self.remaps = 0;
+        self.layout_keys = 0;
+        self.mapped_keys = 0;
+    }
+
+    /// Get the number of keycodes that can still be used to map keysyms
+    pub fn free_keycodes(&self) -> usize {
+        self.unused_keycodes.len()
+    }
+
     /// Get the pending delay
     #[cfg(feature = "x11rb")]
     pub fn pending_delays(&self) -> u32 {
@@ -176,7 +312,8 @@ where
             // A keycode is unused so a mapping is possible
             Some(unused_keycode) => {
                 trace!(
-                    "trying to map keycode {} to keysym {:?}",
+                    "{}trying to map keycode {} to keysym {:?}",
+                    self.log_prefix,
                     unused_keycode,
                     keysym
                 );
@@ -184,8 +321,13 @@ where
                     return Err(InputError::Mapping(format!("{keysym:?}")));
                 };
                 self.needs_regeneration = true;
+                self.remaps += 1;
                 self.additionally_mapped.insert(keysym, unused_keycode);
-                debug!("mapped keycode {} to keysym {:?}", unused_keycode, keysym);
+                self.recently_used.push_back(keysym);
+                debug!(
+                    "{}mapped keycode {} to keysym {:?}",
+                    self.log_prefix, unused_keycode, keysym
+                );
                 Ok(unused_keycode)
             }
             // All keycodes are being used. A mapping is not possible
@@ -202,14 +344,16 @@ where
         keysym: Keysym,
         keycode: Keycode,
     ) -> InputResult<()> {
-        trace!("trying to unmap keysym {:?}", keysym);
+        trace!("{}trying to unmap keysym {:?}", self.log_prefix, keysym);
         if c.bind_key(keycode, NO_SYMBOL).is_err() {
             return Err(InputError::Unmapping(format!("{keysym:?}")));
         };
         self.needs_regeneration = true;
+        self.remaps += 1;
         self.unused_keycodes.push_back(keycode);
         self.additionally_mapped.remove(&keysym);
-        debug!("unmapped keysym {:?}", keysym);
+        self.recently_used.retain(|&s| s != keysym);
+        debug!("{}unmapped keysym {:?}", self.log_prefix, keysym);
         Ok(())
     }
 
@@ -235,15 +379,44 @@ where
                 .try_into()
                 .unwrap_or(u32::MAX);
             self.pending_delays = self.delay.saturating_sub(elapsed_ms);
-            trace!("delay needed");
+            trace!("{}delay needed", self.log_prefix);
             self.last_keys.clear();
         } else {
-            trace!("no delay needed");
+            trace!("{}no delay needed", self.log_prefix);
             self.pending_delays = 1;
         }
         self.last_keys.push(keycode);
     }
 
+    /// Unmap the least recently used keysyms that are not held until there is
+    /// room for one more keysym in the cache. Nothing is done if the size of
+    /// the cache is not limited
+    fn evict<C: Bind<Keycode>>(&mut self, c: &C) -> InputResult<()> {
+        let Some(cache_size) = self.cache_size else {
+            return Ok(());
+        };
+        while self.additionally_mapped.len() >= cache_size {
+            let Some(&keysym) = self.recently_used.iter().find(|keysym| {
+                self.additionally_mapped
+                    .get(keysym)
+                    .is_some_and(|keycode| !self.held_keycodes.contains(keycode))
+            }) else {
+                debug!(
+                    "{}all keysyms in the cache are held. none of them can be evicted",
+                    self.log_prefix
+                );
+                break;
+            };
+            let keycode = self.additionally_mapped[&keysym];
+            trace!(
+                "{}evicting the least recently used keysym {keysym:?}",
+                self.log_prefix
+            );
+            self.unmap(c, keysym, keycode)?;
+        }
+        Ok(())
+    }
+
     /// Check if there are still unused keycodes available. If there aren't,
     /// make some room by freeing the already mapped keycodes.
     /// Returns true, if keys were unmapped and the keymap needs to be
@@ -271,93 +444,217 @@ where
     }
 
     /// Regenerate the keymap if there were any changes
-    /// and write the new keymap to a temporary file
+    /// and write the new keymap to a new sealed file
     ///
     /// If there was the need to regenerate the keymap, the size of the keymap
-    /// is returned
+    /// is returned. If there is no file yet, a keymap is always generated even
+    /// if no keys were mapped so far. If writing the keymap failed, it will
+    /// get regenerated the next time this function is called
     #[cfg(feature = "wayland")]
-    pub fn regenerate(&mut self) -> Result<Option<u32>, std::io::Error> {
-        use super::{KEYMAP_BEGINNING, KEYMAP_END};
-        use std::io::{Seek, SeekFrom, Write};
-        use xkbcommon::xkb::keysym_get_name;
-
+    pub fn regenerate(&mut self) -> InputResult<Option<usize>> {
         // Don't do anything if there were no changes
-        if !self.needs_regeneration {
-            debug!("keymap did not change and does not require regeneration");
+        if !self.needs_regeneration && self.file.is_some() {
+            debug!(
+                "{}keymap did not change and does not require regeneration",
+                self.log_prefix
+            );
             return Ok(None);
         }
 
-        // Create a file to store the layout
-        if self.file.is_none() {
-            let mut temp_file = tempfile::tempfile()?;
-            temp_file.write_all(KEYMAP_BEGINNING)?;
-            self.file = Some(temp_file);
+        match self.write_keymap() {
+            Ok((file, file_size)) => {
+                self.file = Some(file);
+                self.file_size = file_size;
+                self.needs_regeneration = false;
+                debug!("{}regenerated the keymap", self.log_prefix);
+                Ok(Some(self.file_size))
+            }
+            Err(e) => {
+                error!("{}unable to regenerate the keymap: {e}", self.log_prefix);
+                Err(InputError::Mapping(format!(
+                    "unable to regenerate the keymap: {e}"
+                )))
+            }
         }
+    }
+
+    /// Use the named keyboard layout (e.g. "de") as the base of the keymap so
+    /// the keycodes correspond to the keys of that layout. Keysyms that are
+    /// not part of the layout are mapped to the keycodes the layout does not
+    /// use. All additionally mapped keysyms are removed
+    ///
+    /// This does not apply the changes
+    #[cfg(feature = "wayland")]
+    pub fn set_layout(&mut self, layout: &str) -> InputResult<()> {
+        use xkbcommon::xkb;
+
+        if !self.held_keycodes.is_empty() {
+            return Err(InputError::InvalidInput(
+                "the layout cannot be changed while keys are held",
+            ));
+        }
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let Some(keymap) = xkb::Keymap::new_from_names(
+            &context,
+            "evdev",
+            "pc105",
+            layout,
+            "",
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        ) else {
+            error!(
+                "{}unable to compile the keyboard layout {layout:?}",
+                self.log_prefix
+            );
+            return Err(InputError::InvalidInput(
+                "unable to compile the keyboard layout",
+            ));
+        };
+
+        // The keysyms of the first two levels are stored like the X server does.
+        // Otherwise the case of a letter would be converted when looking it up
+        let keycode_min: usize = self.keycode_min.try_into().unwrap();
+        let keycode_max: usize = self.keycode_max.try_into().unwrap();
+        self.keysyms_per_keycode = 2;
+        self.keysyms.clear();
+        self.unused_keycodes.clear();
+        for i in keycode_min..=keycode_max {
+            let raw_keycode: u32 = i.try_into().unwrap();
+            let syms: Vec<u32> = (0..2)
+                .map(|level| {
+                    keymap
+                        .key_get_syms_by_level(xkb::Keycode::new(raw_keycode), 0, level)
+                        .first()
+                        .map_or(NO_SYMBOL.raw(), |sym| sym.raw())
+                })
+                .collect();
+            if syms[0] == NO_SYMBOL.raw() {
+                self.unused_keycodes.push_back(i.try_into().unwrap());
+            }
+            self.keysyms.extend(syms);
+        }
+        self.additionally_mapped.clear();
+        self.recently_used.clear();
+        self.layout = Some(keymap.get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1));
+        self.needs_regeneration = true;
+        debug!(
+            "{}using the keyboard layout {layout:?} with {} unused keycodes",
+            self.log_prefix,
+            self.unused_keycodes.len()
+        );
+        Ok(())
+    }
+
+    /// Write the keymap to a new file and return it together with its length
+    ///
+    /// The contents of the file are sealed so it can safely get shared with the
+    /// compositor. That is why a new file has to be created each time
+    #[cfg(feature = "wayland")]
+    fn write_keymap(&self) -> Result<(std::fs::File, usize), std::io::Error> {
+        use super::{KEYMAP_BEGINNING, KEYMAP_END};
+        use std::io::{Seek, Write};
 
-        let keymap_file = self
-            .file
-            .as_mut()
-            .expect("There was no file to write to. This should not be possible!");
-        // Move the virtual cursor of the file to the end of the part of the keymap that
-        // is always the same so we only overwrite the parts that can change.
-        keymap_file.seek(SeekFrom::Start(KEYMAP_BEGINNING.len() as u64))?;
-        for (&keysym, &keycode) in &self.additionally_mapped {
-            write!(
-                keymap_file,
-                "
+        let mut keymap_file = create_keymap_file()?;
+        if let Some(layout) = &self.layout {
+            // The sections are always written in the same order by xkbcommon, so the
+            // keycodes section is the first one and the symbols section the last one
+            let keycodes_end = layout.find("\n};");
+            let keymap_end = layout.rfind("\n};");
+            let symbols_end = keymap_end.and_then(|end| layout[..end].rfind("\n};"));
+            let (Some(keycodes_end), Some(symbols_end)) = (keycodes_end, symbols_end) else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "the keymap of the layout has an unexpected format",
+                ));
+            };
+            keymap_file.write_all(&layout.as_bytes()[..keycodes_end])?;
+            for &keycode in self.additionally_mapped.values() {
+                write!(keymap_file, "\n\t<I{keycode}> = {keycode};")?;
+            }
+            keymap_file.write_all(&layout.as_bytes()[keycodes_end..symbols_end])?;
+            for (&keysym, &keycode) in &self.additionally_mapped {
+                write!(
+                    keymap_file,
+                    "\n\tkey <I{}> {{ [ {} ] }};",
+                    keycode,
+                    keysym_name(keysym)
+                )?;
+            }
+            keymap_file.write_all(&layout.as_bytes()[symbols_end..])?;
+        } else {
+            keymap_file.write_all(KEYMAP_BEGINNING)?;
+            for (&keysym, &keycode) in &self.additionally_mapped {
+                write!(
+                    keymap_file,
+                    "
             key <I{}> {{ [ {} ] }}; // \\n",
-                keycode,
-                keysym_get_name(keysym)
-            )?;
+                    keycode,
+                    keysym_name(keysym)
+                )?;
+            }
+            keymap_file.write_all(KEYMAP_END)?;
         }
-        keymap_file.write_all(KEYMAP_END)?;
-        // Truncate the file at the current cursor position in order to cut off any old
-        // data in case the keymap was smaller than the old one
         let keymap_len = keymap_file.stream_position()?;
-        keymap_file.set_len(keymap_len)?;
-        self.needs_regeneration = false;
-        match keymap_len.try_into() {
-            Ok(v) => {
-                debug!("regenerated the keymap");
-                Ok(Some(v))
-            }
-            Err(_) => Err(std::io::Error::new(
+        seal_keymap_file(&keymap_file)?;
+        let keymap_len = keymap_len.try_into().map_err(|_| {
+            std::io::Error::new(
                 std::io::ErrorKind::Other,
-                "the length of the new keymap exceeds the u32::MAX",
-            )),
-        }
+                "the length of the new keymap exceeds usize::MAX",
+            )
+        })?;
+        Ok((keymap_file, keymap_len))
     }
 
     /// Tells the keymap that a modifier was pressed
     /// Updates the internal state of the modifiers and returns the new bitflag
     /// representing the state of the modifiers
+    ///
+    /// A modifier stays active as long as at least one key is holding it, so
+    /// releasing one of them does not clear a modifier that is still held by
+    /// another key
     #[cfg(feature = "wayland")]
     pub fn enter_modifier(
         &mut self,
-        modifier: ModifierBitflag,
+        modifier: Modifier,
         direction: crate::Direction,
     ) -> ModifierBitflag {
         match direction {
             crate::Direction::Press => {
-                self.modifiers |= modifier;
-                self.modifiers
+                self.held_modifiers.push(modifier);
             }
             crate::Direction::Release => {
-                self.modifiers &= !modifier;
-                self.modifiers
+                if let Some(idx) = self.held_modifiers.iter().position(|&m| m == modifier) {
+                    self.held_modifiers.swap_remove(idx);
+                }
             }
-            crate::Direction::Click => self.modifiers,
+            crate::Direction::Click => {}
         }
+        self.held_modifiers
+            .iter()
+            .fold(0, |modifiers, m| modifiers | m.bitflag())
+    }
+
+    /// Modifiers that are currently held
+    #[cfg(feature = "wayland")]
+    pub fn held_modifiers(&self) -> &[Modifier] {
+        &self.held_modifiers
     }
 
     pub fn key(&mut self, keycode: Keycode, direction: Direction) {
         match direction {
             Direction::Press => {
-                debug!("added the key {keycode} to the held keycodes");
+                debug!(
+                    "{}added the key {keycode} to the held keycodes",
+                    self.log_prefix
+                );
                 self.held_keycodes.push(keycode);
             }
             Direction::Release => {
-                debug!("removed the key {keycode} from the held keycodes");
+                debug!(
+                    "{}removed the key {keycode} from the held keycodes",
+                    self.log_prefix
+                );
                 self.held_keycodes.retain(|&k| k != keycode);
             }
             Direction::Click => (),
@@ -370,6 +667,63 @@ where
     }
 }
 
+/// Get the name of the keysym that is written to the keymap. Keysyms of
+/// Unicode chars are always written in the U notation, because their names
+/// depend on the version of xkbcommon (e.g. `combining_acute` for U+0301)
+/// and the xkbcommon of the compositor might not know them
+#[cfg(feature = "wayland")]
+fn keysym_name(keysym: Keysym) -> String {
+    match keysym.raw() {
+        raw @ 0x0100_0000..=0x0110_FFFF => format!("U{:04X}", raw & 0x00FF_FFFF),
+        _ => xkbcommon::xkb::keysym_get_name(keysym),
+    }
+}
+
+/// Create an anonymous file in memory that allows adding seals
+#[cfg(all(feature = "wayland", target_os = "linux"))]
+fn create_keymap_file() -> Result<std::fs::File, std::io::Error> {
+    use std::os::fd::FromRawFd;
+
+    let name = b"enigo-keymap\0";
+    let fd = unsafe {
+        libc::memfd_create(
+            name.as_ptr().cast(),
+            libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // The file descriptor was just created and is not owned by anything else
+    Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+}
+
+/// memfds are not available on all platforms so a regular temporary file is
+/// used instead
+#[cfg(all(feature = "wayland", not(target_os = "linux")))]
+fn create_keymap_file() -> Result<std::fs::File, std::io::Error> {
+    tempfile::tempfile()
+}
+
+/// Prevent any further modifications of the file so the compositor can safely
+/// mmap it
+#[cfg(all(feature = "wayland", target_os = "linux"))]
+fn seal_keymap_file(file: &std::fs::File) -> Result<(), std::io::Error> {
+    use std::os::fd::AsRawFd;
+
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_ADD_SEALS, seals) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "wayland", not(target_os = "linux")))]
+#[allow(clippy::unnecessary_wraps)]
+fn seal_keymap_file(_file: &std::fs::File) -> Result<(), std::io::Error> {
+    Ok(())
+}
+
 pub trait Bind<Keycode> {
     // Map the keysym to the given keycode
     // Only use keycodes that are not used, otherwise the existing mapping is
@@ -382,3 +736,416 @@ pub trait Bind<Keycode> {
 }
 
 impl<Keycode> Bind<Keycode> for () {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "wayland")]
+    fn wayland_keymap() -> KeyMap<u32> {
+        let unused_keycodes = (8..=255).collect();
+        KeyMap::new(8, 255, unused_keycodes, 0, vec![], None, "")
+    }
+
+    #[cfg(feature = "wayland")]
+    fn keymap_content(keymap: &mut KeyMap<u32>) -> String {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let file = keymap.file.as_mut().unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut content = String::new();
+        file.read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    // Chars outside of the BMP need two UTF-16 code units but are a single
+    // Unicode scalar value, so they have to result in a single keysym
+    fn non_bmp_char_is_mapped_to_one_keysym() {
+        let mut keymap = wayland_keymap();
+        let keycode = keymap.key_to_keycode(&(), Key::Unicode('𝕊')).unwrap();
+        assert_eq!(keymap.additionally_mapped.len(), 1);
+        assert_eq!(
+            keymap
+                .additionally_mapped
+                .get(&Keysym::new(0x0100_0000 | 0x1D54A)),
+            Some(&keycode)
+        );
+
+        assert!(keymap.regenerate().unwrap().is_some());
+        let content = keymap_content(&mut keymap);
+        assert_eq!(content.matches("U1D54A").count(), 1);
+        assert!(content.contains(&format!("key <I{keycode}> {{ [ U1D54A ] }};")));
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn navigation_keys_are_mapped_to_their_keysyms() {
+        let navigation_keys = [
+            (Key::Insert, "Insert"),
+            (Key::Delete, "Delete"),
+            (Key::Home, "Home"),
+            (Key::End, "End"),
+            (Key::PageUp, "Prior"),
+            (Key::PageDown, "Next"),
+            (Key::LeftArrow, "Left"),
+            (Key::UpArrow, "Up"),
+            (Key::RightArrow, "Right"),
+            (Key::DownArrow, "Down"),
+        ];
+        let mut keymap = wayland_keymap();
+        let keycodes: Vec<_> = navigation_keys
+            .iter()
+            .map(|&(key, _)| {
+                let keycode = keymap.key_to_keycode(&(), key).unwrap();
+                keymap.key(keycode, Direction::Click);
+                keycode
+            })
+            .collect();
+
+        assert!(keymap.regenerate().unwrap().is_some());
+        let content = keymap_content(&mut keymap);
+        for ((_, name), keycode) in navigation_keys.iter().zip(keycodes) {
+            assert!(content.contains(&format!("key <I{keycode}> {{ [ {name} ] }};")));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "x11rb")]
+    // Repeated keys of the keyboard layout (e.g. double spaces) get dropped by
+    // the X server if there is no delay between them
+    fn delay_repeated_key_of_layout() {
+        let space = Keysym::from(Key::Space).raw();
+        let a = Keysym::from(Key::Unicode('a')).raw();
+        let mut keymap: KeyMap<u8> =
+            KeyMap::new(8, 10, VecDeque::from([10]), 1, vec![space, a, 0], None, "");
+        let keycode = keymap.key_to_keycode(&(), Key::Space).unwrap();
+        assert_eq!(keycode, 8);
+        assert_eq!(keymap.pending_delays(), 1);
+        keymap.key(keycode, Direction::Click);
+        keymap.key_to_keycode(&(), Key::Space).unwrap();
+        assert!(keymap.pending_delays() > 1);
+    }
+
+    #[test]
+    #[cfg(feature = "x11rb")]
+    fn layout_keycode_does_not_map() {
+        let space = Keysym::from(Key::Space).raw();
+        let mut keymap: KeyMap<u8> =
+            KeyMap::new(8, 9, VecDeque::from([9]), 1, vec![space, 0], None, "");
+        assert_eq!(keymap.key_to_layout_keycode(Key::Space).unwrap(), 8);
+        assert!(matches!(
+            keymap.key_to_layout_keycode(Key::Unicode('a')),
+            Err(InputError::Mapping(_))
+        ));
+        assert!(keymap.additionally_mapped.is_empty());
+        assert_eq!(keymap.free_keycodes(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "x11rb")]
+    fn keycodes_of_levels() {
+        let shift = Keysym::Shift_L.raw();
+        let level3 = Keysym::ISO_Level3_Shift.raw();
+        let [one, exclam, onesuperior, exclamdown] =
+            ['1', '!', '¹', '¡'].map(|c| Keysym::from(Key::Unicode(c)).raw());
+        let a = Keysym::from(Key::Unicode('a')).raw();
+        #[rustfmt::skip]
+        let keysyms = vec![
+            shift, 0, 0, 0, 0, 0,
+            level3, 0, 0, 0, 0, 0,
+            one, exclam, 0, 0, onesuperior, exclamdown,
+            a, 0, 0, 0, 0, 0,
+        ];
+        let keymap: KeyMap<u8> = KeyMap::new(8, 11, VecDeque::new(), 6, keysyms, None, "");
+        let one = Key::Unicode('1');
+        assert_eq!(keymap.level_keycodes(one, 0).unwrap(), (10, vec![]));
+        assert_eq!(keymap.level_keycodes(one, 1).unwrap(), (10, vec![8]));
+        assert_eq!(keymap.level_keycodes(one, 2).unwrap(), (10, vec![9]));
+        assert_eq!(keymap.level_keycodes(one, 3).unwrap(), (10, vec![8, 9]));
+        for (key, level) in [(one, 4), (Key::Unicode('a'), 2)] {
+            assert!(matches!(
+                keymap.level_keycodes(key, level),
+                Err(InputError::InvalidInput(_))
+            ));
+        }
+        assert!(matches!(
+            keymap.level_keycodes(Key::Unicode('!'), 0),
+            Err(InputError::Mapping(_))
+        ));
+    }
+
+    #[test]
+    fn count_layout_and_mapped_keys() {
+        let space = Keysym::from(Key::Space).raw();
+        let mut keymap: KeyMap<u8> = KeyMap::new(
+            8,
+            10,
+            VecDeque::from([9, 10]),
+            1,
+            vec![space, 0, 0],
+            None,
+            "",
+        );
+        for key in [Key::Space, Key::Unicode('ß'), Key::Space, Key::Unicode('ß')] {
+            keymap.key_to_keycode(&(), key).unwrap();
+        }
+        let stats = keymap.stats();
+        assert_eq!(stats.layout_keys, 2);
+        assert_eq!(stats.mapped_keys, 2);
+        // Only the first 'ß' changed the keymap
+        assert_eq!(stats.remaps, 1);
+        keymap.reset_stats();
+        assert_eq!(keymap.stats(), Stats::default());
+    }
+
+    #[test]
+    fn evict_least_recently_used_keysym() {
+        let unused_keycodes = (8..=255).collect();
+        let mut keymap: KeyMap<u32> = KeyMap::new(8, 255, unused_keycodes, 0, vec![], Some(2), "");
+        let a = keymap.key_to_keycode(&(), Key::Unicode('a')).unwrap();
+        keymap.key_to_keycode(&(), Key::Unicode('b')).unwrap();
+        // Using 'a' again makes 'b' the least recently used keysym
+        keymap.key_to_keycode(&(), Key::Unicode('a')).unwrap();
+        keymap.key_to_keycode(&(), Key::Unicode('c')).unwrap();
+        assert_eq!(keymap.additionally_mapped.len(), 2);
+        assert!(!keymap
+            .additionally_mapped
+            .contains_key(&Keysym::from(Key::Unicode('b'))));
+        // 'a', 'b' and 'c' were mapped and 'b' was unmapped
+        assert_eq!(keymap.stats().remaps, 4);
+
+        // Held keysyms are not evicted
+        keymap.key(a, Direction::Press);
+        keymap.key_to_keycode(&(), Key::Unicode('d')).unwrap();
+        keymap.key_to_keycode(&(), Key::Unicode('e')).unwrap();
+        assert_eq!(keymap.additionally_mapped.len(), 2);
+        assert_eq!(
+            keymap
+                .additionally_mapped
+                .get(&Keysym::from(Key::Unicode('a'))),
+            Some(&a)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn regenerate_only_after_changes() {
+        let mut keymap = wayland_keymap();
+        keymap.key_to_keycode(&(), Key::Unicode('a')).unwrap();
+        let size = keymap.regenerate().unwrap().unwrap();
+        assert_eq!(keymap_content(&mut keymap).len(), size);
+        assert_eq!(keymap.regenerate().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn regenerate_empty_keymap() {
+        use super::super::{KEYMAP_BEGINNING, KEYMAP_END};
+
+        let mut keymap = wayland_keymap();
+        let size = keymap.regenerate().unwrap().unwrap();
+        assert_eq!(size, KEYMAP_BEGINNING.len() + KEYMAP_END.len());
+        // The file is sealed, so writing to it has to fail
+        #[cfg(target_os = "linux")]
+        {
+            use std::io::Write;
+            assert!(keymap.file.as_ref().unwrap().write_all(b" ").is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    // Combining marks do not have a named keysym, but they still have to be
+    // mapped so they combine with the previous char in the target
+    fn combining_mark_is_mapped() {
+        let mut keymap = wayland_keymap();
+        let keycode = keymap.key_to_keycode(&(), Key::Unicode('\u{301}')).unwrap();
+        assert_eq!(
+            keymap.additionally_mapped.get(&Keysym::new(0x0100_0301)),
+            Some(&keycode)
+        );
+        keymap.regenerate().unwrap();
+        let content = keymap_content(&mut keymap);
+        assert!(content.contains(&format!("key <I{keycode}> {{ [ U0301 ] }};")));
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn keycodes_of_named_layout() {
+        let mut keymap = wayland_keymap();
+        keymap.set_layout("de").unwrap();
+        // Z and Y are swapped on a German layout
+        assert_eq!(keymap.key_to_keycode(&(), Key::Unicode('z')).unwrap(), 29);
+        assert_eq!(keymap.key_to_keycode(&(), Key::Unicode('y')).unwrap(), 52);
+        assert_eq!(keymap.stats().layout_keys, 2);
+        // Keysyms that are not part of the layout get mapped to unused keycodes
+        let keycode = keymap.key_to_keycode(&(), Key::Unicode('α')).unwrap();
+        keymap.regenerate().unwrap();
+        let content = keymap_content(&mut keymap);
+        assert!(content.contains(&format!("<I{keycode}> = {keycode};")));
+        assert!(content.contains(&format!("key <I{keycode}> {{ [ Greek_alpha ] }};")));
+        assert!(keymap.set_layout("no_such_layout").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn click_modifier_while_other_is_held() {
+        let mut keymap = wayland_keymap();
+        let control = Modifier::Control.bitflag();
+        let shift = Modifier::Shift.bitflag();
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Press),
+            control
+        );
+        // Clicking a modifier is a press followed by a release
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Press),
+            control | shift
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Release),
+            control
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Release),
+            0
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    // LShift and RShift both map to the Shift modifier
+    fn click_modifier_while_same_is_held() {
+        let mut keymap = wayland_keymap();
+        let shift = Modifier::Shift.bitflag();
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Press),
+            shift
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Press),
+            shift
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Release),
+            shift
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Release),
+            0
+        );
+        // Releasing a modifier that is not held does not change anything
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Release),
+            0
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    // Releasing the modifier that was pressed first must not clear the bits of
+    // the modifiers that are still held
+    fn release_modifiers_in_different_order() {
+        let mut keymap = wayland_keymap();
+        let shift = Modifier::Shift.bitflag();
+        let control = Modifier::Control.bitflag();
+        let alt = Modifier::Mod1.bitflag();
+        keymap.enter_modifier(Modifier::Shift, Direction::Press);
+        keymap.enter_modifier(Modifier::Control, Direction::Press);
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Mod1, Direction::Press),
+            shift | control | alt
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Shift, Direction::Release),
+            control | alt
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Mod1, Direction::Release),
+            control
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Release),
+            0
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn release_modifier_without_press() {
+        let mut keymap = wayland_keymap();
+        let shift = Modifier::Shift.bitflag();
+        keymap.enter_modifier(Modifier::Shift, Direction::Press);
+        // Releasing a modifier that is not held must not affect the held ones
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Release),
+            shift
+        );
+        // A later press of the modifier must not be cancelled by the earlier
+        // release
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Press),
+            shift | Modifier::Control.bitflag()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn click_does_not_change_held_modifiers() {
+        let mut keymap = wayland_keymap();
+        let control = Modifier::Control.bitflag();
+        assert_eq!(keymap.enter_modifier(Modifier::Shift, Direction::Click), 0);
+        keymap.enter_modifier(Modifier::Control, Direction::Press);
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Click),
+            control
+        );
+        assert_eq!(
+            keymap.enter_modifier(Modifier::Control, Direction::Release),
+            0
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    fn every_modifier_has_its_own_bit() {
+        let modifiers = [
+            Modifier::Shift,
+            Modifier::Lock,
+            Modifier::Control,
+            Modifier::Mod1,
+            Modifier::Mod2,
+            Modifier::Mod3,
+            Modifier::Mod4,
+            Modifier::Mod5,
+        ];
+        let mut keymap = wayland_keymap();
+        let mut expected = 0;
+        for modifier in modifiers {
+            assert_eq!(expected & modifier.bitflag(), 0);
+            expected |= modifier.bitflag();
+            assert_eq!(keymap.enter_modifier(modifier, Direction::Press), expected);
+        }
+        for modifier in modifiers {
+            expected &= !modifier.bitflag();
+            assert_eq!(
+                keymap.enter_modifier(modifier, Direction::Release),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "wayland")]
+    // Key::Other allows entering any keysym, even if it has no name
+    fn other_key_is_mapped_to_raw_keysym() {
+        let mut keymap = wayland_keymap();
+        let keycode = keymap.key_to_keycode(&(), Key::Other(0x0012_3456)).unwrap();
+        assert_eq!(
+            keymap.additionally_mapped.get(&Keysym::new(0x0012_3456)),
+            Some(&keycode)
+        );
+    }
+}
@@ -93,41 +93,49 @@ pub struct Con {
 unsafe impl Send for Con {}
 
 impl Con {
-    async fn open_connection() -> ei::Context {
+    async fn open_connection() -> Result<ei::Context, NewConError> {
         use ashpd::desktop::remote_desktop::DeviceType;
 
         trace!("open_connection");
-        if let Some(context) = ei::Context::connect_to_env().unwrap() {
+        if let Some(context) = ei::Context::connect_to_env()
+            .map_err(|_| NewConError::EstablishCon("unable to connect to the ei socket"))?
+        {
             trace!("done open_connection after connect_to_env");
-            context
+            Ok(context)
         } else {
             debug!("Unable to find ei socket. Trying xdg desktop portal.");
-            let remote_desktop = RemoteDesktop::new().await.unwrap();
+            let portal_err = |_| NewConError::EstablishCon("the remote desktop portal failed");
+            let remote_desktop = RemoteDesktop::new().await.map_err(portal_err)?;
             trace!("New desktop");
 
             // device_bitmask |= DeviceType::Touchscreen;
-            let session = remote_desktop.create_session().await.unwrap();
+            let session = remote_desktop.create_session().await.map_err(portal_err)?;
             remote_desktop
                 .select_devices(&session, DeviceType::Keyboard | DeviceType::Pointer) // TODO: Add DeviceType::Touchscreen once we support it in enigo
                 .await
-                .unwrap();
+                .map_err(portal_err)?;
             trace!("new session");
             remote_desktop
                 .start(&session, &ashpd::WindowIdentifier::default())
                 .await
-                .unwrap();
+                .map_err(portal_err)?;
             trace!("start session");
             // This is needed so there is no zbus error
             std::thread::sleep(std::time::Duration::from_millis(10));
-            let fd = remote_desktop.connect_to_eis(&session).await.unwrap();
+            let fd = remote_desktop
+                .connect_to_eis(&session)
+                .await
+                .map_err(portal_err)?;
             let stream = UnixStream::from(fd);
-            stream.set_nonblocking(true).unwrap(); // TODO: Check if this is a good idea
+            stream
+                .set_nonblocking(true)
+                .map_err(|_| NewConError::EstablishCon("unable to configure the ei socket"))?; // TODO: Check if this is a good idea
             trace!("done open_connection");
-            ei::Context::new(stream).unwrap()
+            ei::Context::new(stream)
+                .map_err(|_| NewConError::EstablishCon("unable to create the libei context"))
         }
     }
 
-    #[allow(clippy::unnecessary_wraps)]
     /// Create a new Enigo instance
     pub fn new() -> Result<Self, NewConError> {
         debug!("using libei");
@@ -141,7 +149,7 @@ impl Con {
         let sequence = 0;
         let time_created = Instant::now();
 
-        let context = Self::open_connection().block_on();
+        let context = Self::open_connection().block_on()?;
         let HandshakeResp {
             connection,
             serial,
@@ -152,7 +160,7 @@ impl Con {
             ei::handshake::ContextType::Sender,
             &INTERFACES,
         )
-        .unwrap();
+        .map_err(|_| NewConError::EstablishCon("the libei handshake failed"))?;
 
         trace!("main: handshake");
 
@@ -456,6 +464,15 @@ impl Con {
         }
         Ok(())
     }
+
+    /// Check if the key is part of the keymap of the keyboard, so it can be
+    /// entered
+    pub fn supports_key(&self, key: Key) -> bool {
+        self.keyboards
+            .values()
+            .next()
+            .is_some_and(|keymap| key_to_keycode(keymap, key).is_ok())
+    }
 }
 
 impl Keyboard for Con {
@@ -552,10 +569,10 @@ impl Mouse for Con {
                 Button::Back => 0x116,
                 Button::Forward => 0x115,
                 Button::Middle => 0x112,
-                Button::ScrollDown => return self.scroll(1, Axis::Vertical),
-                Button::ScrollUp => return self.scroll(-1, Axis::Vertical),
-                Button::ScrollRight => return self.scroll(1, Axis::Horizontal),
-                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal),
+                Button::ScrollDown => return self.scroll(1, Axis::Vertical).map(|_| ()),
+                Button::ScrollUp => return self.scroll(-1, Axis::Vertical).map(|_| ()),
+                Button::ScrollRight => return self.scroll(1, Axis::Horizontal).map(|_| ()),
+                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal).map(|_| ()),
             };
 
             let vp = device_data.interface::<ei::Button>().unwrap();
@@ -639,12 +656,12 @@ impl Mouse for Con {
             }
         };
         // TODO: Improve the error
-        Err(InputError::Simulate(
+        Err(InputError::Unsupported(
             "None of the devices implements the move mouse interface so there is no way to move it",
         ))
     }
 
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
         #[allow(clippy::cast_precision_loss)]
         let length = length as f32;
         if let Some((device, device_data)) = self
@@ -667,23 +684,23 @@ impl Mouse for Con {
             self.update("enigo").map_err(|_| {
                 InputError::Simulate("unable to update the libei connection to scroll")
             })?;
-            return Ok(());
+            return Ok(1);
         }
-        Err(InputError::Simulate(
+        Err(InputError::Unsupported(
             "None of the devices implements the Scroll interface so there is no way to scroll",
         ))
     }
 
     fn main_display(&self) -> InputResult<(i32, i32)> {
         // TODO Implement this
-        error!("You tried to get the dimensions of the main display. I don't know how this is possible under Wayland. Let me know if there is a new protocol");
-        Err(InputError::Simulate("Not implemented yet"))
+        debug!("You tried to get the dimensions of the main display. I don't know how this is possible under Wayland. Let me know if there is a new protocol");
+        Err(InputError::Unsupported("Not implemented yet"))
     }
 
     fn location(&self) -> InputResult<(i32, i32)> {
         // TODO Implement this
-        error!("You tried to get the mouse location. I don't know how this is possible under Wayland. Let me know if there is a new protocol");
-        Err(InputError::Simulate("Not implemented yet"))
+        debug!("You tried to get the mouse location. I don't know how this is possible under Wayland. Let me know if there is a new protocol");
+        Err(InputError::Unsupported("Not implemented yet"))
     }
 }
 
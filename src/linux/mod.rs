@@ -1,8 +1,10 @@
+use std::{fmt, time::Duration};
+
 use log::{debug, error, trace, warn};
 
 use crate::{
-    Axis, Button, Coordinate, Direction, InputError, InputResult, Key, Keyboard, Mouse,
-    NewConError, Settings,
+    Axis, Backend, Button, Capabilities, Coordinate, Direction, InputError, InputResult, Key,
+    Keyboard, Mouse, NewConError, Settings, WindowInfo,
 };
 
 // If none of these features is enabled, there is no way to simulate input
@@ -34,9 +36,234 @@ use constants::{KEYMAP_BEGINNING, KEYMAP_END};
 #[cfg(any(feature = "wayland", feature = "x11rb"))]
 mod keymap;
 
+/// Number of requests that were needed to simulate the input. This can be
+/// used to measure the performance and to notice regressions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Stats {
+    /// Number of times enigo waited until the X server or the compositor
+    /// processed the requests (`sync` on X11 and `roundtrip` on Wayland)
+    pub roundtrips: u64,
+    /// Number of simulated events (e.g. key presses and releases, mouse
+    /// movements or committed text)
+    pub events_sent: u64,
+    /// Number of times a keysym was mapped to or unmapped from a keycode
+    pub remaps: u64,
+    /// Number of keys that were entered with a keycode of the keyboard layout
+    /// without changing the keymap
+    pub layout_keys: u64,
+    /// Number of keys that were entered by mapping their keysym to an unused
+    /// keycode or with a keycode it was already mapped to
+    pub mapped_keys: u64,
+}
+
+impl std::ops::AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.roundtrips += other.roundtrips;
+        self.events_sent += other.events_sent;
+        self.remaps += other.remaps;
+        self.layout_keys += other.layout_keys;
+        self.mapped_keys += other.mapped_keys;
+    }
+}
+
+/// Acceleration of the pointer on X11. If the pointer moves more than
+/// `threshold` pixels at once, the movement beyond the threshold is multiplied
+/// by `numerator / denominator`. Set the acceleration to 1/1 to make relative
+/// mouse movements predictable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PointerAcceleration {
+    /// Numerator of the factor the movement is multiplied with
+    pub numerator: u16,
+    /// Denominator of the factor the movement is multiplied with
+    pub denominator: u16,
+    /// Number of pixels the pointer has to move at once before it is
+    /// accelerated
+    pub threshold: u16,
+}
+
+/// Input device of the X server. The id can be used for
+/// [`Settings::x11_keyboard_device`] and [`Settings::x11_pointer_device`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct X11Device {
+    /// Id of the device
+    pub id: u8,
+    /// Name of the device (e.g. "Virtual core XTEST keyboard")
+    pub name: String,
+    /// True if the device is the core keyboard or an extension keyboard
+    pub is_keyboard: bool,
+    /// True if the device is the core pointer or an extension pointer
+    pub is_pointer: bool,
+}
+
+/// Number of units a line of scrolling is divided into when accumulating
+/// fractional scroll lengths
+const SCROLL_UNITS_PER_LINE: f64 = 120.0;
+
+/// Collects fractional scroll lengths until they add up to whole lines
+#[derive(Debug, Default)]
+struct ScrollAccumulator {
+    axis: Axis,
+    units: i32, // scrolled units that did not add up to a whole line yet
+}
+
+impl ScrollAccumulator {
+    /// Add the length and return the number of whole lines that need to be
+    /// scrolled. The remaining fraction is kept until the next call. It gets
+    /// discarded if the axis changes
+    #[allow(clippy::cast_possible_truncation)]
+    fn add(&mut self, length: f64, axis: Axis) -> i32 {
+        if axis != self.axis {
+            self.axis = axis;
+            self.units = 0;
+        }
+        // The length is stored in whole units to avoid rounding errors
+        self.units = self
+            .units
+            .saturating_add((length * SCROLL_UNITS_PER_LINE).round() as i32);
+        let lines = self.units / SCROLL_UNITS_PER_LINE as i32;
+        self.units %= SCROLL_UNITS_PER_LINE as i32;
+        lines
+    }
+}
+
+/// Maximum number of clicks of a scroll button that are sent for one call of
+/// [`Mouse::scroll`] on X11
+#[cfg(any(feature = "x11rb", feature = "xdo"))]
+const MAX_SCROLL_CLICKS: u32 = 10_000;
+
+/// Get the number of clicks of a scroll button that are needed to scroll the
+/// length. It is capped at [`MAX_SCROLL_CLICKS`], so extreme lengths do not
+/// block for ages
+#[cfg(any(feature = "x11rb", feature = "xdo"))]
+fn scroll_clicks(length: i32, log_prefix: &str) -> u32 {
+    let clicks = length.unsigned_abs();
+    if clicks > MAX_SCROLL_CLICKS {
+        warn!("{log_prefix}scrolling {clicks} clicks is capped at {MAX_SCROLL_CLICKS} clicks");
+        return MAX_SCROLL_CLICKS;
+    }
+    clicks
+}
+
+/// Number of motion events that are sent while dragging
+const DRAG_STEPS: i32 = 10;
+
+/// Evenly spaced locations from the start to the target. The start is not
+/// included, but the target is always the last location
+fn drag_path(start: (i32, i32), target: (i32, i32), steps: i32) -> Vec<(i32, i32)> {
+    (1..=steps)
+        .map(|step| {
+            (
+                start.0 + (target.0 - start.0) * step / steps,
+                start.1 + (target.1 - start.1) * step / steps,
+            )
+        })
+        .collect()
+}
+
+/// Check if the session is a Wayland session. Applications that only support
+/// X11 run under Xwayland in such a session
+#[cfg(any(feature = "x11rb", feature = "xdo"))]
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|session_type| session_type == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+#[cfg(feature = "wayland")]
+fn connect_wayland(settings: &Settings, log_prefix: &str) -> Option<wayland::Con> {
+    match wayland::Con::new(
+        &settings.wayland_display,
+        &settings.wayland_seat,
+        settings.wayland_disable_input_method,
+        settings.linux_keysym_cache_size,
+        settings.use_physical_pixels,
+        log_prefix,
+    ) {
+        Ok(con) => {
+            debug!("{log_prefix}wayland connection established");
+            Some(con)
+        }
+        Err(e) => {
+            warn!("{log_prefix}{e}");
+            None
+        }
+    }
+}
+
+#[cfg(any(feature = "x11rb", feature = "xdo"))]
+fn connect_x11(settings: &Settings, log_prefix: &str) -> Option<x11::Con> {
+    match &settings.x11_display {
+        Some(name) => {
+            debug!(
+                "\x1b[93m{log_prefix}trying to establish a x11 connection to: {}\x1b[0m",
+                name
+            );
+        }
+        None => {
+            debug!("\x1b[93m{log_prefix}trying to establish a x11 connection to $DISPLAY\x1b[0m");
+        }
+    }
+    match x11::Con::new(
+        &settings.x11_display,
+        settings.linux_delay,
+        settings.disable_autorepeat_on_press,
+        settings.x11_use_send_event,
+        settings.linux_keysym_cache_size,
+        settings.x11_allow_remap,
+        log_prefix,
+    ) {
+        Ok(con) => {
+            #[cfg(feature = "x11rb")]
+            let con = con.with_devices(settings.x11_keyboard_device, settings.x11_pointer_device);
+            debug!("{log_prefix}x11 connection established");
+            Some(con)
+        }
+        Err(e) => {
+            warn!("{log_prefix}failed to establish x11 connection: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(feature = "libei")]
+fn connect_libei(log_prefix: &str) -> Option<libei::Con> {
+    match libei::Con::new() {
+        Ok(con) => {
+            debug!("{log_prefix}libei connection established");
+            Some(con)
+        }
+        Err(e) => {
+            warn!("{log_prefix}failed to establish libei connection: {e}");
+            None
+        }
+    }
+}
+
+/// Keys that need to be clicked to enter the compose sequence
+fn compose_keys(sequence: &[char]) -> Vec<Key> {
+    std::iter::once(Key::Compose)
+        .chain(sequence.iter().map(|&c| Key::Unicode(c)))
+        .collect()
+}
+
+#[allow(clippy::struct_excessive_bools)] // The bools are copied from the settings
 pub struct Enigo {
     held: (Vec<Key>, Vec<u16>), // Currently held keys and held keycodes
+    scroll_accumulator: ScrollAccumulator,
+    last_click_location: Option<(i32, i32)>,
     release_keys_when_dropped: bool,
+    ignore_carriage_return: bool,
+    max_text_length: Option<usize>,
+    fallback_to_paste: bool,
+    allow_system_keys: bool,
+    ignore_unmatched_release: bool,
+    invert_scroll_x: bool,
+    invert_scroll_y: bool,
+    sticky_modifiers: crate::StickyModifiers,
+    click_gap: Duration,        // time between the press and release of a click
+    double_click_gap: Duration, // time between the clicks of a multi click
+    #[cfg(feature = "unicode")]
+    normalize_text: Option<crate::NormalizationForm>,
+    log_prefix: String,
     #[cfg(feature = "wayland")]
     wayland: Option<wayland::Con>,
     #[cfg(any(feature = "x11rb", feature = "xdo"))]
@@ -45,6 +272,33 @@ pub struct Enigo {
     libei: Option<libei::Con>,
 }
 
+impl fmt::Debug for Enigo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Enigo");
+        debug
+            .field("held", &self.held)
+            .field("last_click_location", &self.last_click_location)
+            .field("release_keys_when_dropped", &self.release_keys_when_dropped)
+            .field("ignore_carriage_return", &self.ignore_carriage_return)
+            .field("max_text_length", &self.max_text_length)
+            .field("fallback_to_paste", &self.fallback_to_paste)
+            .field("allow_system_keys", &self.allow_system_keys)
+            .field("ignore_unmatched_release", &self.ignore_unmatched_release)
+            .field("invert_scroll_x", &self.invert_scroll_x)
+            .field("invert_scroll_y", &self.invert_scroll_y)
+            .field("sticky_modifiers", &self.sticky_modifiers)
+            .field("click_gap", &self.click_gap)
+            .field("double_click_gap", &self.double_click_gap);
+        #[cfg(feature = "wayland")]
+        debug.field("wayland", &self.wayland);
+        #[cfg(any(feature = "x11rb", feature = "xdo"))]
+        debug.field("x11", &self.x11);
+        #[cfg(feature = "libei")]
+        debug.field("libei", &self.libei.is_some());
+        debug.finish_non_exhaustive()
+    }
+}
+
 impl Enigo {
     /// Create a new Enigo struct to establish the connection to simulate input
     /// with the specified settings
@@ -52,74 +306,111 @@ impl Enigo {
     /// # Errors
     /// Have a look at the documentation of `NewConError` to see under which
     /// conditions an error will be returned.
+    #[allow(clippy::too_many_lines)]
     pub fn new(settings: &Settings) -> Result<Self, NewConError> {
         let mut connection_established = false;
         #[allow(unused_variables)]
         let Settings {
-            linux_delay,
-            x11_display,
-            wayland_display,
             release_keys_when_dropped,
+            instance_name,
+            ignore_carriage_return,
+            max_text_length,
+            fallback_to_paste,
+            allow_system_keys,
+            ignore_unmatched_release,
+            click_press_release_gap_ms,
+            double_click_gap_ms,
+            prefer_backend,
+            backend_priority,
+            invert_scroll_x,
+            invert_scroll_y,
+            sticky_modifiers,
             ..
         } = settings;
 
+        let log_prefix = crate::log_prefix(instance_name.as_deref());
         let held = (Vec::new(), Vec::new());
         #[cfg(feature = "wayland")]
-        let wayland = match wayland::Con::new(wayland_display) {
-            Ok(con) => {
-                connection_established = true;
-                debug!("wayland connection established");
-                Some(con)
-            }
-            Err(e) => {
-                warn!("{e}");
-                None
-            }
-        };
+        let mut wayland = None;
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
-        match x11_display {
-            Some(name) => {
-                debug!(
-                    "\x1b[93mtrying to establish a x11 connection to: {}\x1b[0m",
-                    name
-                );
-            }
-            None => {
-                debug!("\x1b[93mtrying to establish a x11 connection to $DISPLAY\x1b[0m");
-            }
-        }
-        #[cfg(any(feature = "x11rb", feature = "xdo"))]
-        let x11 = match x11::Con::new(x11_display, *linux_delay) {
-            Ok(con) => {
-                connection_established = true;
-                debug!("x11 connection established");
-                Some(con)
-            }
-            Err(e) => {
-                warn!("failed to establish x11 connection: {e}");
-                None
-            }
-        };
+        let mut x11 = None;
         #[cfg(feature = "libei")]
-        let libei = match libei::Con::new() {
-            Ok(con) => {
-                connection_established = true;
-                debug!("libei connection established");
-                Some(con)
-            }
-            Err(e) => {
-                warn!("failed to establish libei connection: {e}");
-                None
-            }
+        let mut libei = None;
+        // A preferred backend is a priority with only that backend
+        let backend_priority = match (backend_priority.is_empty(), prefer_backend) {
+            (true, Some(backend)) => vec![*backend],
+            _ => backend_priority.clone(),
         };
+        // Without a priority, all backends are tried and all of the established
+        // connections are used
+        let use_all = backend_priority.is_empty();
+        let backends: Vec<Backend> = if use_all {
+            vec![Backend::Wayland, Backend::X11, Backend::Libei]
+        } else {
+            backend_priority
+        };
+        for backend in backends {
+            let connected = match backend {
+                #[cfg(feature = "wayland")]
+                Backend::Wayland => {
+                    wayland = connect_wayland(settings, &log_prefix);
+                    wayland.is_some()
+                }
+                #[cfg(any(feature = "x11rb", feature = "xdo"))]
+                Backend::X11 => {
+                    x11 = connect_x11(settings, &log_prefix);
+                    x11.is_some()
+                }
+                #[cfg(feature = "libei")]
+                Backend::Libei => {
+                    libei = connect_libei(&log_prefix);
+                    libei.is_some()
+                }
+                #[allow(unreachable_patterns)]
+                _ => {
+                    debug!("{log_prefix}skipped the {backend:?} backend, because its feature is not enabled");
+                    false
+                }
+            };
+            connection_established |= connected;
+            if connected && !use_all {
+                debug!("{log_prefix}using the {backend:?} backend");
+                break;
+            }
+        }
         if !connection_established {
-            error!("no successful connection");
+            error!("{log_prefix}no successful connection");
             return Err(NewConError::EstablishCon("no successful connection"));
         }
+        #[cfg(any(feature = "x11rb", feature = "xdo"))]
+        if is_wayland_session() && x11.is_some() {
+            #[cfg(feature = "wayland")]
+            let wayland_established = wayland.is_some();
+            #[cfg(not(feature = "wayland"))]
+            let wayland_established = false;
+            if !wayland_established {
+                warn!("{log_prefix}this is a Wayland session, but only the x11 connection was established. The input only reaches applications running under Xwayland");
+            }
+        }
 
         Ok(Self {
             held,
+            scroll_accumulator: ScrollAccumulator::default(),
+            last_click_location: None,
             release_keys_when_dropped: *release_keys_when_dropped,
+            ignore_carriage_return: *ignore_carriage_return,
+            max_text_length: *max_text_length,
+            fallback_to_paste: *fallback_to_paste,
+            allow_system_keys: *allow_system_keys,
+            ignore_unmatched_release: *ignore_unmatched_release,
+            invert_scroll_x: *invert_scroll_x,
+            invert_scroll_y: *invert_scroll_y,
+            sticky_modifiers: crate::StickyModifiers::new(*sticky_modifiers),
+            click_gap: Duration::from_millis((*click_press_release_gap_ms).into()),
+            double_click_gap: Duration::from_millis((*double_click_gap_ms).into()),
+            #[cfg(feature = "unicode")]
+            normalize_text: settings.normalize_text,
+            log_prefix,
             #[cfg(feature = "wayland")]
             wayland,
             #[cfg(any(feature = "x11rb", feature = "xdo"))]
@@ -156,140 +447,668 @@ impl Enigo {
     pub fn held(&mut self) -> (Vec<Key>, Vec<u16>) {
         self.held.clone()
     }
+
+    /// Scroll by a fractional number of lines. Fractions that do not add up to
+    /// a whole line are remembered and added to the next call, so many small
+    /// scrolls (e.g. from a smooth scrolling source) are not lost. The
+    /// remembered fraction is discarded if the axis changes. Have a look at
+    /// [`Mouse::scroll`] for the direction of the scrolling
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    pub fn scroll_by(&mut self, length: f64, axis: Axis) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}scroll_by(length: {length:?}, axis: {axis:?})\x1b[0m",
+            self.log_prefix
+        );
+        let lines = self.scroll_accumulator.add(length, axis);
+        if lines == 0 {
+            debug!(
+                "{}not enough scrolling accumulated for a whole line",
+                self.log_prefix
+            );
+            return Ok(());
+        }
+        self.scroll(lines, axis).map(|_| ())
+    }
+
+    /// Get the capabilities of the connections that are used
+    #[must_use]
+    pub fn capabilities(&self) -> Capabilities {
+        debug!("\x1b[93m{}capabilities()\x1b[0m", self.log_prefix);
+        #[allow(unused_mut)]
+        let mut capabilities = Capabilities::default();
+        #[cfg(any(feature = "x11rb", feature = "xdo"))]
+        if self.x11.is_some() {
+            capabilities.scroll_method = Some(crate::ScrollMethod::Buttons);
+        }
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_ref() {
+            let wayland_capabilities = con.capabilities();
+            if wayland_capabilities.scroll_method.is_some() {
+                capabilities = wayland_capabilities;
+            }
+        }
+        capabilities.buttons = crate::BUTTONS
+            .iter()
+            .copied()
+            .filter(|&button| self.supports_button(button))
+            .collect();
+        capabilities.with_queries(self)
+    }
+
+    /// Get all keys without any data (e.g. no [`Key::Unicode`]) that can be
+    /// entered with the connections that are used. Wayland, xdo and x11rb map
+    /// any keysym that is not part of the keyboard layout, so all keys are
+    /// supported. If [`Settings::x11_allow_remap`] is `false` or libei is
+    /// used, only the modifiers and keys of the keyboard layout are supported
+    #[must_use]
+    pub fn supported_keys(&self) -> Vec<Key> {
+        debug!("\x1b[93m{}supported_keys()\x1b[0m", self.log_prefix);
+        crate::keycodes::NAMED_KEYS
+            .iter()
+            .copied()
+            .filter(|&key| {
+                (self.allow_system_keys || !key.is_system_key()) && self.supports_key(key)
+            })
+            .collect()
+    }
+
+    /// Check if all used connections can enter the key
+    #[allow(unused_variables, clippy::unused_self)]
+    fn supports_key(&self, key: Key) -> bool {
+        #[cfg(feature = "libei")]
+        if let Some(con) = self.libei.as_ref() {
+            if !con.supports_key(key) {
+                return false;
+            }
+        }
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            if !con.supports_key(key) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check if all used connections can simulate the button
+    #[allow(unused_variables, clippy::unused_self)]
+    fn supports_button(&self, button: Button) -> bool {
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            if !con.supports_button(button) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Get the number of lines one unit of the length of [`Mouse::scroll`]
+    /// scrolls
+    ///
+    /// # Errors
+    /// On X11 one unit is one click of the scroll wheel buttons and on Wayland
+    /// it is one notch of a scroll wheel. The application that receives the
+    /// events decides how many lines it scrolls, so
+    /// [`InputError::Unsupported`] is always returned
+    pub fn scroll_lines_per_notch(&self) -> InputResult<u32> {
+        debug!("\x1b[93m{}scroll_lines_per_notch()\x1b[0m", self.log_prefix);
+        Err(InputError::Unsupported(
+            "the application decides how many lines are scrolled per notch",
+        ))
+    }
+
+    /// Get the number of roundtrips, sent events and changes of the keymap of
+    /// the Wayland and x11rb connections since Enigo was created or the stats
+    /// were reset. The connections of xdo and libei are not counted
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        debug!("\x1b[93m{}stats()\x1b[0m", self.log_prefix);
+        #[allow(unused_mut)]
+        let mut stats = Stats::default();
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_ref() {
+            stats += con.stats();
+        }
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            stats += con.stats();
+        }
+        stats
+    }
+
+    /// Reset the number of roundtrips, sent events and changes of the keymap
+    /// to zero
+    pub fn reset_stats(&mut self) {
+        debug!("\x1b[93m{}reset_stats()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_mut() {
+            con.reset_stats();
+        }
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            con.reset_stats();
+        }
+    }
+
+    /// Enter a compose sequence by clicking the compose key (`Multi_key`)
+    /// followed by the chars of the sequence (e.g. `['a', 'e']` for `æ`). This
+    /// does not change the keymap for the resulting char. The application
+    /// translates the sequence with the compose table of the user, so it
+    /// only works if the application supports compose sequences (e.g. via
+    /// XIM)
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    pub fn compose_x11(&mut self, sequence: &[char]) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}compose_x11(sequence: {sequence:?})\x1b[0m",
+            self.log_prefix
+        );
+        for key in compose_keys(sequence) {
+            self.key(key, Direction::Click)?;
+        }
+        Ok(())
+    }
+
+    /// Press the button at the current location of the mouse, move the mouse
+    /// to the center of the X11 window in several steps and release the
+    /// button there. The intermediate motion events are needed for drag and
+    /// drop (e.g. XDND) to recognize the drag. The button is released even if
+    /// moving the mouse failed.
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if the window id is
+    /// invalid. This is only possible with x11rb, so an error is returned if
+    /// there is no x11rb connection. Have a look at the documentation of
+    /// [`InputError`] to see under which other conditions an error will be
+    /// returned.
+    pub fn drag_to_window(&mut self, button: Button, window: u32) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}drag_to_window(button: {button:?}, window: {window})\x1b[0m",
+            self.log_prefix
+        );
+        let target = self.window_center(window)?;
+        let start = self.location()?;
+        self.button(button, Direction::Press)?;
+        let result = drag_path(start, target, DRAG_STEPS)
+            .into_iter()
+            .try_for_each(|(x, y)| self.move_mouse(x, y, Coordinate::Abs));
+        result.and(self.button(button, Direction::Release))
+    }
+
+    /// Move the mouse to the coordinates relative to the X11 window (e.g. a
+    /// reparented or embedded child window). The coordinates are translated
+    /// to root window coordinates, so a widget can be addressed by its local
+    /// coordinates without computing the offset of the window
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if the window id is
+    /// invalid or the coordinates do not fit in an `i16`. This is only
+    /// possible with x11rb, so an error is returned if there is no x11rb
+    /// connection. Have a look at the documentation of [`InputError`] to see
+    /// under which other conditions an error will be returned.
+    #[allow(unused_variables)]
+    pub fn move_mouse_in_window(&mut self, x: i32, y: i32, window: u32) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}move_mouse_in_window(x: {x:?}, y: {y:?}, window: {window})\x1b[0m",
+            self.log_prefix
+        );
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            let (Ok(x), Ok(y)) = (i16::try_from(x), i16::try_from(y)) else {
+                return Err(InputError::InvalidInput(
+                    "the coordinates have to fit in an i16 on X11",
+                ));
+            };
+            let (x, y) = con.window_to_root(window, x, y)?;
+            return self.move_mouse(x, y, Coordinate::Abs);
+        }
+        Err(InputError::Unsupported(
+            "moving the mouse relative to a window is only possible with x11rb",
+        ))
+    }
+
+    /// Enter the key on the given level of the keyboard layout. The key has
+    /// to be the symbol on level 0 (e.g. `Key::Unicode('1')`). Level 1 is
+    /// entered by holding Shift, level 2 by holding `ISO_Level3_Shift`
+    /// (`AltGr`) and level 3 by holding both. This allows entering a
+    /// specific symbol of a key (e.g. '!' on a US layout) without depending
+    /// on how the symbol would be mapped
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if the key does not have
+    /// the level and an [`InputError::Mapping`] if the key or one of the
+    /// modifiers is not part of the keyboard layout. This is only possible
+    /// with x11rb, so an error is returned if there is no x11rb connection
+    #[allow(unused_variables)]
+    pub fn key_at_level(&mut self, key: Key, level: u8, direction: Direction) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}key_at_level(key: {key:?}, level: {level}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            let (keycode, modifiers) = con.level_keycodes(key, level)?;
+            if direction != Direction::Release {
+                for &modifier in &modifiers {
+                    self.enter_raw(modifier.into(), Direction::Press, None)?;
+                }
+            }
+            self.enter_raw(keycode.into(), direction, None)?;
+            if direction != Direction::Press {
+                for &modifier in modifiers.iter().rev() {
+                    self.enter_raw(modifier.into(), Direction::Release, None)?;
+                }
+            }
+            return Ok(());
+        }
+        Err(InputError::Unsupported(
+            "entering a key on a level is only possible with x11rb",
+        ))
+    }
+
+    /// Generate the keymap of the virtual keyboard from the named keyboard
+    /// layout (e.g. "de" or "fr"). Afterwards the raw keycodes correspond to
+    /// the keys of that layout, regardless of the layout of the system.
+    /// Characters that are not part of the layout are still mapped to unused
+    /// keycodes when entering text
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if the layout could not be
+    /// compiled or if keys are currently held. This is only possible with
+    /// Wayland, so an error is returned if there is no Wayland connection
+    #[allow(unused_variables)]
+    pub fn set_wayland_layout(&mut self, layout: &str) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}set_wayland_layout(layout: {layout:?})\x1b[0m",
+            self.log_prefix
+        );
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_mut() {
+            return con.set_layout(layout);
+        }
+        Err(InputError::Unsupported(
+            "setting the layout is only possible with Wayland",
+        ))
+    }
+
+    /// Get the acceleration of the pointer
+    ///
+    /// # Errors
+    /// This is only possible with x11rb, so an error is returned if there is
+    /// no x11rb connection
+    pub fn pointer_acceleration(&self) -> InputResult<PointerAcceleration> {
+        debug!("\x1b[93m{}pointer_acceleration()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            return con.pointer_acceleration();
+        }
+        Err(InputError::Unsupported(
+            "the pointer acceleration can only be queried with x11rb",
+        ))
+    }
+
+    /// Get the modifiers that are currently held down (e.g. by the user).
+    /// Unlike [`Enigo::held`], this is not limited to the keys entered with
+    /// this instance. The X server merges the state of all keyboards, so the
+    /// modifiers held by simulated input are included as well
+    ///
+    /// # Errors
+    /// This is only possible with x11rb, so an error is returned if there is
+    /// no x11rb connection
+    pub fn physical_modifiers(&self) -> InputResult<Vec<Key>> {
+        debug!("\x1b[93m{}physical_modifiers()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            return con.physical_modifiers();
+        }
+        Err(InputError::Unsupported(
+            "the modifiers can only be queried with x11rb",
+        ))
+    }
+
+    /// List the input devices of the X server. Their ids can be used to pin the
+    /// devices that send the input with [`Settings::x11_keyboard_device`] and
+    /// [`Settings::x11_pointer_device`]
+    ///
+    /// # Errors
+    /// This is only possible with x11rb, so an error is returned if there is
+    /// no x11rb connection
+    pub fn list_devices(&mut self) -> InputResult<Vec<X11Device>> {
+        debug!("\x1b[93m{}list_devices()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            return con.devices();
+        }
+        Err(InputError::Unsupported(
+            "the input devices can only be listed with x11rb",
+        ))
+    }
+
+    /// Set the acceleration of the pointer. It is changed for the whole X
+    /// session and not only for the simulated input
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if one of the values is
+    /// larger than `i16::MAX` or the denominator is zero. This is only
+    /// possible with x11rb, so an error is returned if there is no x11rb
+    /// connection
+    #[allow(unused_variables)]
+    pub fn set_pointer_acceleration(
+        &mut self,
+        acceleration: PointerAcceleration,
+    ) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}set_pointer_acceleration(acceleration: {acceleration:?})\x1b[0m",
+            self.log_prefix
+        );
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            return con.set_pointer_acceleration(acceleration);
+        }
+        Err(InputError::Unsupported(
+            "the pointer acceleration can only be changed with x11rb",
+        ))
+    }
+
+    /// Get the auto-repeat of the keyboard. The first value is the delay in
+    /// milliseconds before a held key starts to repeat and the second one is
+    /// the number of repetitions per second
+    ///
+    /// # Errors
+    /// This is only possible with x11rb, so an error is returned if there is
+    /// no x11rb connection
+    pub fn autorepeat(&mut self) -> InputResult<(u32, u32)> {
+        debug!("\x1b[93m{}autorepeat()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            return con.autorepeat_rate();
+        }
+        Err(InputError::Unsupported(
+            "the auto-repeat can only be queried with x11rb",
+        ))
+    }
+
+    /// Set the delay in milliseconds before a held key starts to repeat and
+    /// the number of repetitions per second. It is changed for the whole X
+    /// session and not only for the simulated input. This makes tests that
+    /// hold keys independent of the settings of the user
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if the delay is larger than
+    /// `u16::MAX` or the rate is not between 1 and 1000 Hz. This is only
+    /// possible with x11rb, so an error is returned if there is no x11rb
+    /// connection
+    #[allow(unused_variables)]
+    pub fn set_autorepeat(&mut self, delay_ms: u32, rate_hz: u32) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}set_autorepeat(delay_ms: {delay_ms}, rate_hz: {rate_hz})\x1b[0m",
+            self.log_prefix
+        );
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            return con.set_autorepeat_rate(delay_ms, rate_hz);
+        }
+        Err(InputError::Unsupported(
+            "the auto-repeat can only be changed with x11rb",
+        ))
+    }
+
+    /// Set the text of the X11 PRIMARY selection. It is distinct from the
+    /// clipboard and pasted with a click of the middle mouse button (e.g. with
+    /// [`Enigo::middle_click_paste`]). The selection is served by a background
+    /// thread until another application takes it over
+    ///
+    /// # Errors
+    /// This is only possible with x11rb, so an [`InputError::Unsupported`] is
+    /// returned if there is no x11rb connection (e.g. on Wayland)
+    #[cfg(feature = "clipboard")]
+    #[allow(unused_variables)]
+    pub fn set_primary_selection(&mut self, text: &str) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}set_primary_selection(text: {text:?})\x1b[0m",
+            self.log_prefix
+        );
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            return con.set_primary_selection(text);
+        }
+        Err(InputError::Unsupported(
+            "the primary selection can only be set with x11rb",
+        ))
+    }
+
+    /// Paste the PRIMARY selection with a click of the middle mouse button at
+    /// the current location of the mouse
+    ///
+    /// # Errors
+    /// Pasting the primary selection is specific to X11, so an
+    /// [`InputError::Unsupported`] is returned if there is no X11 connection
+    /// (e.g. on Wayland)
+    #[cfg(feature = "clipboard")]
+    pub fn middle_click_paste(&mut self) -> InputResult<()> {
+        debug!("\x1b[93m{}middle_click_paste()\x1b[0m", self.log_prefix);
+        #[cfg(any(feature = "x11rb", feature = "xdo"))]
+        if let Some(con) = self.x11.as_mut() {
+            return con.button(Button::Middle, Direction::Click);
+        }
+        Err(InputError::Unsupported(
+            "the primary selection can only be pasted on X11",
+        ))
+    }
+
+    /// Get the center of the X11 window in root window coordinates
+    #[allow(unused_variables, clippy::unused_self)]
+    fn window_center(&self, window: u32) -> InputResult<(i32, i32)> {
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_ref() {
+            return con.window_center(window);
+        }
+        Err(InputError::Unsupported(
+            "getting the geometry of a window is only possible with x11rb",
+        ))
+    }
+
+    /// Get the title, class and process id of the window that currently has
+    /// the focus
+    ///
+    /// # Errors
+    /// This is only possible on X11. There is no protocol to get the focused
+    /// window on Wayland, so an error is returned if there is no X11
+    /// connection.
+    pub fn focused_window(&self) -> InputResult<WindowInfo> {
+        debug!("\x1b[93m{}focused_window()\x1b[0m", self.log_prefix);
+        #[cfg(any(feature = "x11rb", feature = "xdo"))]
+        if let Some(con) = self.x11.as_ref() {
+            trace!("{}try getting the focused window via x11", self.log_prefix);
+            return con.focused_window();
+        }
+        Err(InputError::Unsupported(
+            "there is no protocol to get the focused window",
+        ))
+    }
 }
 
 impl Mouse for Enigo {
+    fn double_click_gap(&self) -> Duration {
+        self.double_click_gap
+    }
+
+    fn last_click_location(&self) -> Option<(i32, i32)> {
+        debug!("\x1b[93m{}last_click_location()\x1b[0m", self.log_prefix);
+        self.last_click_location
+    }
+
     fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mbutton(button: {button:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}button(button: {button:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        if crate::needs_click_gap(button, direction, self.click_gap) {
+            return crate::click_with_gap(self, button, self.click_gap);
+        }
+        // The scroll buttons are sent to the backends directly, so they have to
+        // be inverted here. On the other platforms they are scrolled with
+        // Mouse::scroll, which inverts them
+        let button = crate::scroll_button(button, self.invert_scroll_x, self.invert_scroll_y);
         let mut success = false;
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_mut() {
-            trace!("try sending button event via libei");
+            trace!("{}try sending button event via libei", self.log_prefix);
             con.button(button, direction)?;
-            debug!("sent button event via libei");
+            debug!("{}sent button event via libei", self.log_prefix);
             success = true;
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_mut() {
-            trace!("try sending button event via wayland");
+            trace!("{}try sending button event via wayland", self.log_prefix);
             con.button(button, direction)?;
-            debug!("sent button event via wayland");
+            debug!("{}sent button event via wayland", self.log_prefix);
             success = true;
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_mut() {
-            trace!("try sending button event via x11");
+            trace!("{}try sending button event via x11", self.log_prefix);
             con.button(button, direction)?;
-            debug!("sent button event via x11");
+            debug!("{}sent button event via x11", self.log_prefix);
             success = true;
         }
-        if success {
-            debug!("sent button event");
-            Ok(())
-        } else {
-            Err(InputError::Simulate("No protocol to enter the result"))
+        if !success {
+            return Err(InputError::Simulate("No protocol to enter the result"));
         }
+        debug!("{}sent button event", self.log_prefix);
+        if direction != Direction::Release && !button.is_scroll() {
+            self.last_click_location = self.location().ok();
+        }
+        Ok(())
     }
 
     fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
-        debug!("\x1b[93mmove_mouse(x: {x:?}, y: {y:?}, coordinate:{coordinate:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}move_mouse(x: {x:?}, y: {y:?}, coordinate:{coordinate:?})\x1b[0m",
+            self.log_prefix
+        );
         let mut success = false;
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_mut() {
-            trace!("try moving the mouse via libei");
+            trace!("{}try moving the mouse via libei", self.log_prefix);
             con.move_mouse(x, y, coordinate)?;
-            debug!("moved the mouse via libei");
+            debug!("{}moved the mouse via libei", self.log_prefix);
             success = true;
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_mut() {
-            trace!("try moving the mouse via wayland");
+            trace!("{}try moving the mouse via wayland", self.log_prefix);
             con.move_mouse(x, y, coordinate)?;
-            debug!("moved the mouse via wayland");
+            debug!("{}moved the mouse via wayland", self.log_prefix);
             success = true;
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_mut() {
-            trace!("try moving the mouse via x11");
+            trace!("{}try moving the mouse via x11", self.log_prefix);
             con.move_mouse(x, y, coordinate)?;
-            debug!("moved the mouse via x11");
+            debug!("{}moved the mouse via x11", self.log_prefix);
             success = true;
         }
         if success {
-            debug!("moved the mouse");
+            debug!("{}moved the mouse", self.log_prefix);
             Ok(())
         } else {
             Err(InputError::Simulate("No protocol to enter the result"))
         }
     }
 
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
-        debug!("\x1b[93mscroll(length: {length:?}, axis: {axis:?})\x1b[0m");
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
+        debug!(
+            "\x1b[93m{}scroll(length: {length:?}, axis: {axis:?})\x1b[0m",
+            self.log_prefix
+        );
+        let length = crate::scroll_length(length, axis, self.invert_scroll_x, self.invert_scroll_y);
         let mut success = false;
+        let mut events = 0;
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_mut() {
-            trace!("try scrolling via libei");
-            con.scroll(length, axis)?;
-            debug!("scrolled via libei");
+            trace!("{}try scrolling via libei", self.log_prefix);
+            events += con.scroll(length, axis)?;
+            debug!("{}scrolled via libei", self.log_prefix);
             success = true;
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_mut() {
-            trace!("try scrolling via wayland");
-            con.scroll(length, axis)?;
-            debug!("scrolled via wayland");
+            trace!("{}try scrolling via wayland", self.log_prefix);
+            events += con.scroll(length, axis)?;
+            debug!("{}scrolled via wayland", self.log_prefix);
             success = true;
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_mut() {
-            trace!("try scrolling via x11");
-            con.scroll(length, axis)?;
-            debug!("scrolled via x11");
+            trace!("{}try scrolling via x11", self.log_prefix);
+            events += con.scroll(length, axis)?;
+            debug!("{}scrolled via x11", self.log_prefix);
             success = true;
         }
         if success {
-            debug!("scrolled");
-            Ok(())
+            debug!("{}scrolled with {events} events", self.log_prefix);
+            Ok(events)
         } else {
             Err(InputError::Simulate("No protocol to enter the result"))
         }
     }
 
     fn main_display(&self) -> InputResult<(i32, i32)> {
-        debug!("\x1b[93mmain_display()\x1b[0m");
+        debug!("\x1b[93m{}main_display()\x1b[0m", self.log_prefix);
         #[cfg(feature = "libeii")]
         if let Some(con) = self.libei.as_ref() {
-            trace!("try getting the dimensions of the display via libei");
+            trace!(
+                "{}try getting the dimensions of the display via libei",
+                self.log_prefix
+            );
             return con.main_display();
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_ref() {
-            trace!("try getting the dimensions of the display via wayland");
+            trace!(
+                "{}try getting the dimensions of the display via wayland",
+                self.log_prefix
+            );
             return con.main_display();
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_ref() {
-            trace!("try getting the dimensions of the display via x11");
+            trace!(
+                "{}try getting the dimensions of the display via x11",
+                self.log_prefix
+            );
             return con.main_display();
         }
         Err(InputError::Simulate("No protocol to enter the result"))
     }
 
     fn location(&self) -> InputResult<(i32, i32)> {
-        debug!("\x1b[93mlocation()\x1b[0m");
+        debug!("\x1b[93m{}location()\x1b[0m", self.log_prefix);
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_ref() {
-            trace!("try getting the mouse location via libei");
+            trace!(
+                "{}try getting the mouse location via libei",
+                self.log_prefix
+            );
             return con.location();
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_ref() {
-            trace!("try getting the mouse location via wayland");
+            trace!(
+                "{}try getting the mouse location via wayland",
+                self.log_prefix
+            );
             return con.location();
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_ref() {
-            trace!("try getting the mouse location via x11");
+            trace!("{}try getting the mouse location via x11", self.log_prefix);
             return con.location();
         }
         Err(InputError::Simulate("No protocol to enter the result"))
@@ -297,107 +1116,304 @@ impl Mouse for Enigo {
 }
 
 impl Keyboard for Enigo {
+    #[cfg(feature = "unicode")]
+    fn normalization_form(&self) -> Option<crate::NormalizationForm> {
+        self.normalize_text
+    }
+
+    fn max_text_length(&self) -> Option<usize> {
+        self.max_text_length
+    }
+
+    fn take_sticky_modifiers(&mut self) -> Vec<Key> {
+        self.sticky_modifiers.take_all()
+    }
+
+    #[allow(unused_variables)]
+    fn paste_text(&mut self, text: &str) -> InputResult<Option<()>> {
+        debug!(
+            "\x1b[93m{}paste_text(text: {text:?})\x1b[0m",
+            self.log_prefix
+        );
+        if !self.fallback_to_paste {
+            return Ok(None);
+        }
+        #[cfg(all(feature = "clipboard", feature = "x11rb"))]
+        if self.x11.is_some() {
+            trace!(
+                "{}try pasting the text via the primary selection of x11",
+                self.log_prefix
+            );
+            self.set_primary_selection(text)?;
+            self.middle_click_paste()?;
+            return Ok(Some(()));
+        }
+        Ok(None)
+    }
+
+    fn native_key_repeat(&self) -> bool {
+        #[cfg(feature = "libei")]
+        if self.libei.is_some() {
+            return false;
+        }
+        #[cfg(feature = "wayland")]
+        if self.wayland.is_some() {
+            return false;
+        }
+        true
+    }
+
     fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
-        debug!("\x1b[93mfast_text(text: {text})\x1b[0m");
+        debug!("\x1b[93m{}fast_text(text: {text})\x1b[0m", self.log_prefix);
 
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_mut() {
-            trace!("try entering text fast via libei");
+            trace!("{}try entering text fast via libei", self.log_prefix);
             con.text(text)?;
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_mut() {
-            trace!("try entering text fast via wayland");
+            trace!("{}try entering text fast via wayland", self.log_prefix);
             con.text(text)?;
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_mut() {
-            trace!("try entering text fast via x11");
+            trace!("{}try entering text fast via x11", self.log_prefix);
             con.text(text)?;
         }
-        debug!("entered the text fast");
+        debug!("{}entered the text fast", self.log_prefix);
         Ok(Some(()))
     }
 
+    fn begin_text(&mut self) -> InputResult<()> {
+        debug!("\x1b[93m{}begin_text()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_mut() {
+            if con.begin_text() {
+                debug!("{}started a batch of text", self.log_prefix);
+                return Ok(());
+            }
+        }
+        debug!(
+            "{}batches of text are only supported with the input_method protocol",
+            self.log_prefix
+        );
+        Ok(())
+    }
+
+    fn end_text(&mut self) -> InputResult<()> {
+        debug!("\x1b[93m{}end_text()\x1b[0m", self.log_prefix);
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_mut() {
+            con.end_text()?;
+        }
+        Ok(())
+    }
+
+    /// The events of all keys are queued and sent at once
+    fn press_keys(&mut self, keys: &[Key]) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}press_keys(keys: {keys:?})\x1b[0m",
+            self.log_prefix
+        );
+        self.begin_key_batch();
+        let result = crate::press_keys(self, keys);
+        result.and(self.end_key_batch())
+    }
+
+    /// The events of all keys are queued and sent at once
+    fn release_keys(&mut self, keys: &[Key]) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}release_keys(keys: {keys:?})\x1b[0m",
+            self.log_prefix
+        );
+        self.begin_key_batch();
+        let result = crate::release_keys(self, keys);
+        result.and(self.end_key_batch())
+    }
+
     fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mkey(key: {key:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}key(key: {key:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
         // Nothing to do
         if key == Key::Unicode('\0') {
-            debug!("entering the null byte is a noop");
+            debug!("{}entering the null byte is a noop", self.log_prefix);
+            return Ok(());
+        }
+        key.check_system_key(self.allow_system_keys)?;
+        key.check_enterable()?;
+        if let Some(result) =
+            crate::enter_sticky(self, |enigo| &mut enigo.sticky_modifiers, key, direction)
+        {
+            return result;
+        }
+        // Handle special characters separately
+        let key = match key {
+            Key::Unicode('\r') if self.ignore_carriage_return => {
+                debug!("{}entering a carriage return is a noop", self.log_prefix);
+                return Ok(());
+            }
+            Key::Unicode('\n' | '\r') => Key::Return,
+            Key::Unicode('\t') => Key::Tab,
+            key => key,
+        };
+        if self.ignore_unmatched_release
+            && crate::is_unmatched_release(&self.held.0, &key, direction)
+        {
+            debug!(
+                "{}releasing the key {key:?} that is not held is a noop",
+                self.log_prefix
+            );
             return Ok(());
         }
 
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_mut() {
-            trace!("try entering the key via libei");
+            trace!("{}try entering the key via libei", self.log_prefix);
             con.key(key, direction)?;
-            debug!("entered the key via libei");
+            debug!("{}entered the key via libei", self.log_prefix);
         }
 
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_mut() {
-            trace!("try entering the key via wayland");
+            trace!("{}try entering the key via wayland", self.log_prefix);
             con.key(key, direction)?;
-            debug!("entered the key via wayland");
+            debug!("{}entered the key via wayland", self.log_prefix);
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_mut() {
-            trace!("try entering the key via x11");
+            trace!("{}try entering the key via x11", self.log_prefix);
             con.key(key, direction)?;
-            debug!("entered the key via x11");
+            debug!("{}entered the key via x11", self.log_prefix);
         }
 
         match direction {
             Direction::Press => {
-                debug!("added the key {key:?} to the held keys");
+                debug!("{}added the key {key:?} to the held keys", self.log_prefix);
                 self.held.0.push(key);
             }
             Direction::Release => {
-                debug!("removed the key {key:?} from the held keys");
+                debug!(
+                    "{}removed the key {key:?} from the held keys",
+                    self.log_prefix
+                );
                 self.held.0.retain(|&k| k != key);
             }
             Direction::Click => (),
         }
 
-        debug!("entered the key");
+        debug!("{}entered the key", self.log_prefix);
         Ok(())
     }
 
     fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mraw(keycode: {keycode:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}raw(keycode: {keycode:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        self.enter_raw(keycode, direction, None)
+    }
+
+    fn raw_at(&mut self, keycode: u16, direction: Direction, time: u32) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}raw_at(keycode: {keycode:?}, direction: {direction:?}, time: {time})\x1b[0m",
+            self.log_prefix
+        );
+        self.enter_raw(keycode, direction, Some(time))
+    }
+}
+
+impl Enigo {
+    /// Queue the key events of the connections until `end_key_batch` is called.
+    /// The other connections send the events right away
+    #[allow(clippy::unused_self)] // only the Wayland and x11rb connections batch keys
+    fn begin_key_batch(&mut self) {
+        #[cfg(feature = "wayland")]
+        if let Some(con) = self.wayland.as_mut() {
+            con.begin_key_batch();
+        }
+        #[cfg(feature = "x11rb")]
+        if let Some(con) = self.x11.as_mut() {
+            con.begin_key_batch();
+        }
+    }
+
+    /// Send the queued key events of the connections at once
+    #[allow(clippy::unused_self)] // only the Wayland and x11rb connections batch keys
+    fn end_key_batch(&mut self) -> InputResult<()> {
+        let result = Ok(());
+        #[cfg(feature = "wayland")]
+        let result = result.and(
+            self.wayland
+                .as_mut()
+                .map_or(Ok(()), wayland::Con::end_key_batch),
+        );
+        #[cfg(feature = "x11rb")]
+        let result = result.and(self.x11.as_mut().map_or(Ok(()), x11::Con::end_key_batch));
+        result
+    }
 
+    /// Enter the keycode with the connections. The timestamp is only used on
+    /// Wayland
+    #[allow(unused_variables)]
+    fn enter_raw(
+        &mut self,
+        keycode: u16,
+        direction: Direction,
+        time: Option<u32>,
+    ) -> InputResult<()> {
+        if self.ignore_unmatched_release
+            && crate::is_unmatched_release(&self.held.1, &keycode, direction)
+        {
+            debug!(
+                "{}releasing the keycode {keycode:?} that is not held is a noop",
+                self.log_prefix
+            );
+            return Ok(());
+        }
         #[cfg(feature = "libei")]
         if let Some(con) = self.libei.as_mut() {
-            trace!("try entering the keycode via libei");
+            trace!("{}try entering the keycode via libei", self.log_prefix);
             con.raw(keycode, direction)?;
-            debug!("entered the keycode via libei");
+            debug!("{}entered the keycode via libei", self.log_prefix);
         }
         #[cfg(feature = "wayland")]
         if let Some(con) = self.wayland.as_mut() {
-            trace!("try entering the keycode via wayland");
-            con.raw(keycode, direction)?;
-            debug!("entered the keycode via wayland");
+            trace!("{}try entering the keycode via wayland", self.log_prefix);
+            match time {
+                Some(time) => con.raw_at(keycode.into(), direction, time)?,
+                None => con.raw(keycode, direction)?,
+            }
+            debug!("{}entered the keycode via wayland", self.log_prefix);
         }
         #[cfg(any(feature = "x11rb", feature = "xdo"))]
         if let Some(con) = self.x11.as_mut() {
-            trace!("try entering the keycode via x11");
+            trace!("{}try entering the keycode via x11", self.log_prefix);
             con.raw(keycode, direction)?;
-            debug!("entered the keycode via x11");
+            debug!("{}entered the keycode via x11", self.log_prefix);
         }
 
         match direction {
             Direction::Press => {
-                debug!("added the keycode {keycode:?} to the held keys");
+                debug!(
+                    "{}added the keycode {keycode:?} to the held keys",
+                    self.log_prefix
+                );
                 self.held.1.push(keycode);
             }
             Direction::Release => {
-                debug!("removed the keycode {keycode:?} from the held keys");
+                debug!(
+                    "{}removed the keycode {keycode:?} from the held keys",
+                    self.log_prefix
+                );
                 self.held.1.retain(|&k| k != keycode);
             }
             Direction::Click => (),
         }
 
-        debug!("entered the keycode");
+        debug!("{}entered the keycode", self.log_prefix);
         Ok(())
     }
 }
@@ -409,16 +1425,150 @@ impl Drop for Enigo {
             return;
         }
         let (held_keys, held_keycodes) = self.held();
-        for &key in &held_keys {
-            if self.key(key, Direction::Release).is_err() {
-                error!("unable to release {:?}", key);
-            };
+        crate::release_held(self, &held_keys, &held_keycodes);
+        debug!(
+            "{}released all held keys and held keycodes",
+            self.log_prefix
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_fails_without_any_connection() {
+        let settings = Settings {
+            x11_display: Some(":4242".to_string()),
+            wayland_display: Some("enigo-nonexistent-display".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Enigo::new(&settings),
+            Err(NewConError::EstablishCon(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(not(feature = "libei"))]
+    fn new_only_uses_the_backends_of_the_priority() {
+        // The other backends are skipped even if they are available
+        let settings = Settings {
+            backend_priority: vec![Backend::Libei],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Enigo::new(&settings),
+            Err(NewConError::EstablishCon(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(not(feature = "libei"))]
+    fn new_only_uses_the_preferred_backend() {
+        let settings = Settings {
+            prefer_backend: Some(Backend::Libei),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Enigo::new(&settings),
+            Err(NewConError::EstablishCon(_))
+        ));
+    }
+
+    #[test]
+    fn accumulate_fractional_scrolls() {
+        let mut accumulator = ScrollAccumulator::default();
+        let lines: Vec<i32> = (0..10)
+            .map(|_| accumulator.add(0.3, Axis::Vertical))
+            .collect();
+        assert_eq!(lines.iter().filter(|&&l| l != 0).count(), 3);
+        assert_eq!(lines.iter().sum::<i32>(), 3);
+    }
+
+    #[test]
+    #[cfg(any(feature = "x11rb", feature = "xdo"))]
+    fn scroll_clicks_of_extreme_lengths() {
+        assert_eq!(scroll_clicks(-3, ""), 3);
+        assert_eq!(scroll_clicks(i32::MIN, ""), MAX_SCROLL_CLICKS);
+        assert_eq!(scroll_clicks(i32::MAX, ""), MAX_SCROLL_CLICKS);
+    }
+
+    #[test]
+    fn compose_ae_via_multi_key() {
+        assert_eq!(
+            compose_keys(&['a', 'e']),
+            vec![Key::Compose, Key::Unicode('a'), Key::Unicode('e')]
+        );
+        assert_eq!(
+            xkeysym::Keysym::from(Key::Compose),
+            xkeysym::Keysym::Multi_key
+        );
+    }
+
+    #[test]
+    fn menu_key_is_the_menu_keysym() {
+        assert_eq!(xkeysym::Keysym::from(Key::Menu), xkeysym::Keysym::Menu);
+        // The deprecated name is still the menu key on Linux
+        assert_eq!(xkeysym::Keysym::from(Key::LMenu), xkeysym::Keysym::Menu);
+    }
+
+    #[test]
+    fn system_keys_need_to_be_allowed() {
+        for key in [Key::Power, Key::Sleep, Key::WakeUp] {
+            assert!(matches!(
+                key.check_system_key(false),
+                Err(InputError::InvalidInput(_))
+            ));
+            assert!(key.check_system_key(true).is_ok());
         }
-        for &keycode in &held_keycodes {
-            if self.raw(keycode, Direction::Release).is_err() {
-                error!("unable to release {:?}", keycode);
-            };
+        assert!(Key::Return.check_system_key(false).is_ok());
+    }
+
+    #[test]
+    fn chars_without_keysym_are_rejected() {
+        for c in ['\u{1}', '\u{7}', '\u{1f}', '\u{85}', '\u{fdd0}', '\u{fffe}'] {
+            assert!(
+                matches!(
+                    Key::Unicode(c).check_enterable(),
+                    Err(InputError::InvalidInput(_))
+                ),
+                "{c:?}"
+            );
         }
-        debug!("released all held keys and held keycodes");
+        for c in [
+            '\0', '\u{8}', '\t', '\n', '\r', '\u{1b}', '\u{7f}', 'a', 'é', '\u{301}',
+        ] {
+            assert!(Key::Unicode(c).check_enterable().is_ok(), "{c:?}");
+        }
+        assert!(Key::Other(0x1).check_enterable().is_ok());
+    }
+
+    #[test]
+    fn drag_path_ends_at_target() {
+        let path = drag_path((0, 100), (50, -100), 5);
+        assert_eq!(
+            path,
+            vec![(10, 60), (20, 20), (30, -20), (40, -60), (50, -100)]
+        );
+        assert_eq!(drag_path((7, 7), (7, 7), 2), vec![(7, 7), (7, 7)]);
+    }
+
+    #[test]
+    fn accumulate_negative_scrolls() {
+        let mut accumulator = ScrollAccumulator::default();
+        assert_eq!(accumulator.add(-0.6, Axis::Vertical), 0);
+        assert_eq!(accumulator.add(-0.6, Axis::Vertical), -1);
+        assert_eq!(accumulator.add(0.2, Axis::Vertical), 0);
+        assert_eq!(accumulator.add(0.2, Axis::Vertical), 0);
+    }
+
+    #[test]
+    fn reset_scroll_accumulator_on_axis_change() {
+        let mut accumulator = ScrollAccumulator::default();
+        assert_eq!(accumulator.add(0.9, Axis::Vertical), 0);
+        assert_eq!(accumulator.add(0.9, Axis::Horizontal), 0);
+        assert_eq!(accumulator.add(0.2, Axis::Horizontal), 1);
     }
 }
@@ -1,15 +1,18 @@
+use std::cell::Cell;
 use std::collections::VecDeque;
 use std::convert::TryInto;
 use std::env;
-use std::os::unix::io::AsFd;
+use std::fmt;
+use std::os::unix::io::{AsFd, AsRawFd};
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use log::{debug, error, trace, warn};
 use wayland_client::{
-    protocol::{wl_pointer, wl_registry, wl_seat},
-    Connection, Dispatch, EventQueue, QueueHandle,
+    backend::WaylandError,
+    protocol::{wl_callback, wl_display, wl_output, wl_pointer, wl_registry, wl_seat},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
 };
 use wayland_protocols_misc::{
     zwp_input_method_v2::client::{zwp_input_method_manager_v2, zwp_input_method_v2},
@@ -20,22 +23,58 @@ use wayland_protocols_wlr::virtual_pointer::v1::client::{
     zwlr_virtual_pointer_manager_v1, zwlr_virtual_pointer_v1,
 };
 
-use super::keymap::{Bind, KeyMap};
+use super::{
+    keymap::{Bind, KeyMap},
+    Stats,
+};
 use crate::{
-    keycodes::Modifier, keycodes::ModifierBitflag, Axis, Button, Coordinate, Direction, InputError,
-    InputResult, Key, Keyboard, Mouse, NewConError,
+    keycodes::Modifier, keycodes::ModifierBitflag, Axis, Button, Capabilities, Coordinate,
+    Direction, InputError, InputResult, Key, Keyboard, Mouse, NewConError, ScrollMethod,
 };
 
 pub type Keycode = u32;
 
+/// Maximum time to wait for an answer of the compositor while connecting
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
 pub struct Con {
     keymap: KeyMap<Keycode>,
     event_queue: EventQueue<WaylandState>,
     state: WaylandState,
     virtual_keyboard: Option<zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1>,
     input_method: Option<(zwp_input_method_v2::ZwpInputMethodV2, u32)>,
+    pending_text: Option<String>, // text of the current batch that was not committed yet
+    batch_keys: bool,             // the key events are queued until the batch of keys ends
+    pressed_chars: Vec<(char, char)>, // held chars and the chars that were pressed for them
     virtual_pointer: Option<zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1>,
     base_time: std::time::Instant,
+    last_time: Cell<Option<u32>>, // timestamp of the last event
+    // There is no protocol to get the location of the mouse, so it is tracked
+    // once it was moved to absolute coordinates
+    pointer_location: Option<(i32, i32)>,
+    stats: Stats,
+    use_physical_pixels: bool, // the coordinates are in physical pixels instead of logical ones
+    log_prefix: String,        // prefix of the log messages of the instance of Enigo
+}
+
+impl fmt::Debug for Con {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Con")
+            .field("virtual_keyboard", &self.virtual_keyboard.is_some())
+            .field("input_method", &self.input_method.is_some())
+            .field("pending_text", &self.pending_text)
+            .field("virtual_pointer", &self.virtual_pointer.is_some())
+            .field(
+                "virtual_pointer_version",
+                &self.state.pointer_manager_version,
+            )
+            .field("output_size", &(self.state.width, self.state.height))
+            .field("output_scale", &self.state.scale)
+            .field("use_physical_pixels", &self.use_physical_pixels)
+            .field("free_keycodes", &self.keymap.free_keycodes())
+            .field("pointer_location", &self.pointer_location)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Con {
@@ -43,12 +82,20 @@ impl Con {
     ///
     /// # Errors
     /// TODO
-    pub fn new(dpy_name: &Option<String>) -> Result<Self, NewConError> {
+    #[allow(clippy::too_many_lines)]
+    pub fn new(
+        dpy_name: &Option<String>,
+        seat_name: &Option<String>,
+        disable_input_method: bool,
+        keysym_cache_size: Option<usize>,
+        use_physical_pixels: bool,
+        log_prefix: &str,
+    ) -> Result<Self, NewConError> {
         // Setup Wayland Connection
         let connection = match dpy_name {
             Some(dyp_name) => {
                 debug!(
-                    "\x1b[93mtrying to establish a connection to: {}\x1b[0m",
+                    "\x1b[93m{log_prefix}trying to establish a connection to: {}\x1b[0m",
                     dyp_name
                 );
                 let mut socket_path = env::var_os("XDG_RUNTIME_DIR")
@@ -62,7 +109,9 @@ impl Con {
                 Connection::from_socket(stream)
             }
             None => {
-                debug!("\x1b[93mtrying to establish a connection to $WAYLAND_DISPLAY\x1b[0m");
+                debug!(
+                    "\x1b[93m{log_prefix}trying to establish a connection to $WAYLAND_DISPLAY\x1b[0m"
+                );
                 Connection::connect_to_env()
             }
         };
@@ -70,7 +119,7 @@ impl Con {
         let connection = match connection {
             Ok(connection) => connection,
             Err(e) => {
-                error!("{:?}", e);
+                error!("{log_prefix}{:?}", e);
                 return Err(NewConError::EstablishCon(
                     "failed to connect to wayland. Try setting 'export WAYLAND_DISPLAY=wayland-0': {e}",
                 ));
@@ -80,7 +129,7 @@ impl Con {
         // Check to see if there was an error trying to connect
         if let Some(e) = connection.protocol_error() {
             error!(
-                "unknown wayland initialization failure: {} {} {} {}",
+                "{log_prefix}unknown wayland initialization failure: {} {} {} {}",
                 e.code, e.object_id, e.object_interface, e.message
             );
             return Err(NewConError::EstablishCon(
@@ -99,8 +148,34 @@ impl Con {
 
         // Setup WaylandState and dispatch events
         let mut state = WaylandState::new();
-        if event_queue.roundtrip(&mut state).is_err() {
-            return Err(NewConError::EstablishCon("wayland roundtrip not possible"));
+        state.disable_input_method = disable_input_method;
+        state.log_prefix = log_prefix.to_string();
+        if !roundtrip_with_timeout(&mut event_queue, &mut state, &display, CONNECT_TIMEOUT)? {
+            return Err(NewConError::EstablishCon(
+                "the compositor did not announce its globals in time",
+            ));
+        };
+        // The names and capabilities of the seats are sent after they were
+        // bound. Some compositors never send them, so the connection is still
+        // used without them
+        if !roundtrip_with_timeout(&mut event_queue, &mut state, &display, CONNECT_TIMEOUT)? {
+            warn!(
+                "{log_prefix}the compositor did not send the names and capabilities of the seats in time"
+            );
+        }
+        state.seat = match seat_name {
+            Some(seat_name) => {
+                let Some((seat, _)) = state
+                    .seats
+                    .iter()
+                    .find(|(_, name)| name.as_ref() == Some(seat_name))
+                else {
+                    error!("{log_prefix}there is no seat with the name {seat_name}");
+                    return Err(NewConError::EstablishCon("named seat not found"));
+                };
+                Some(seat.clone())
+            }
+            None => state.seats.first().map(|(seat, _)| seat.clone()),
         };
 
         let (virtual_keyboard, input_method, virtual_pointer) = (None, None, None);
@@ -113,7 +188,15 @@ impl Con {
         }
 
         let (keysyms_per_keycode, keysyms) = (0, vec![]);
-        let keymap = KeyMap::new(8, 255, unused_keycodes, keysyms_per_keycode, keysyms);
+        let keymap = KeyMap::new(
+            8,
+            255,
+            unused_keycodes,
+            keysyms_per_keycode,
+            keysyms,
+            keysym_cache_size,
+            log_prefix,
+        );
 
         let mut connection = Self {
             keymap,
@@ -121,13 +204,23 @@ impl Con {
             state,
             virtual_keyboard,
             input_method,
+            pending_text: None,
+            batch_keys: false,
+            pressed_chars: Vec::new(),
             virtual_pointer,
             base_time,
+            last_time: Cell::new(None),
+            pointer_location: None,
+            stats: Stats::default(),
+            use_physical_pixels,
+            log_prefix: log_prefix.to_string(),
         };
 
         connection.init_protocols()?;
 
-        if connection.apply_keymap().is_err() {
+        // Without a seat there is no virtual keyboard, but the virtual pointer
+        // can still be used
+        if connection.virtual_keyboard.is_some() && connection.apply_keymap().is_err() {
             return Err(NewConError::EstablishCon("unable to apply the keymap"));
         };
         Ok(connection)
@@ -151,6 +244,11 @@ impl Con {
                 .im_manager
                 .as_ref()
                 .map(|im_mgr| (im_mgr.get_input_method(seat, &qh, ()), 0));
+        } else {
+            warn!(
+                "{}there is no seat, so only the virtual pointer can be used",
+                self.log_prefix
+            );
         };
 
         // Setup virtual pointer
@@ -169,7 +267,8 @@ impl Con {
         }
 
         trace!(
-            "protocols available\nvirtual_keyboard: {}\ninput_method: {}\nvirtual_pointer: {}",
+            "{}protocols available\nvirtual_keyboard: {}\ninput_method: {}\nvirtual_pointer: {}",
+            self.log_prefix,
             self.virtual_keyboard.is_some(),
             self.input_method.is_some(),
             self.virtual_pointer.is_some(),
@@ -186,58 +285,125 @@ impl Con {
         Ok(())
     }
 
-    /// Get the duration since the Keymap was created
+    /// Get the number of physical pixels per logical pixel if the coordinates
+    /// are in physical pixels. Otherwise it is always 1, because the
+    /// compositor expects logical pixels
+    fn physical_scale(&self) -> i32 {
+        if self.use_physical_pixels {
+            self.state.scale
+        } else {
+            1
+        }
+    }
+
+    /// Get the size of the output in logical pixels, unless the connection
+    /// uses physical pixels. It is `None` if the compositor did not send the
+    /// size of an output
+    fn output_size(&self) -> Option<(i32, i32)> {
+        if self.state.width <= 0 || self.state.height <= 0 {
+            return None;
+        }
+        let scale = if self.use_physical_pixels {
+            1
+        } else {
+            self.state.scale
+        };
+        Some((self.state.width / scale, self.state.height / scale))
+    }
+
+    /// Get the timestamp for the next event. It is the duration since the
+    /// Keymap was created in milliseconds, but it is always larger than the
+    /// timestamp of the previous event, because some compositors drop events
+    /// that do not have strictly increasing timestamps
     fn get_time(&self) -> u32 {
+        self.use_time(self.elapsed_time())
+    }
+
+    /// Get the duration since the Keymap was created in milliseconds
+    fn elapsed_time(&self) -> u32 {
         let duration = self.base_time.elapsed();
-        let time = duration.as_millis();
-        time.try_into().unwrap_or(u32::MAX)
+        duration.as_millis().try_into().unwrap_or(u32::MAX)
     }
 
-    /// Press/Release a keycode
+    /// Use the given timestamp for the next event. If it is not larger than
+    /// the timestamp of the previous event, the next larger one is used instead
+    fn use_time(&self, time: u32) -> u32 {
+        let time = next_time(self.last_time.get(), time);
+        self.last_time.set(Some(time));
+        time
+    }
+
+    /// Press/Release a keycode. The time is the timestamp of the event in
+    /// milliseconds
     ///
     /// # Errors
     /// TODO
-    fn send_key_event(&mut self, keycode: Keycode, direction: Direction) -> InputResult<()> {
+    fn send_key_event(
+        &mut self,
+        keycode: Keycode,
+        direction: Direction,
+        time: u32,
+    ) -> InputResult<()> {
+        // The text of the batch has to be entered before the key
+        self.commit_pending_text()?;
         if let Some(vk) = &self.virtual_keyboard {
             is_alive(vk)?;
-            let time = self.get_time();
-            let keycode = keycode - 8; // Adjust by 8 due to the xkb/xwayland requirements
+            let keycode = evdev_keycode(keycode)?;
 
             if direction == Direction::Press || direction == Direction::Click {
-                trace!("vk.key({time}, {keycode}, 1)");
+                trace!("{}vk.key({time}, {keycode}, 1)", self.log_prefix);
                 vk.key(time, keycode, 1);
+                self.stats.events_sent += 1;
                 // TODO: Change to flush()
-                if self.event_queue.roundtrip(&mut self.state).is_err() {
-                    return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+                if !self.batch_keys {
+                    self.stats.roundtrips += 1;
+                    if self.event_queue.roundtrip(&mut self.state).is_err() {
+                        return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+                    }
                 }
             }
             if direction == Direction::Release || direction == Direction::Click {
-                trace!("vk.key({time}, {keycode}, 0)");
+                // The release of a click needs a later timestamp than the press
+                let time = if direction == Direction::Click {
+                    self.use_time(time)
+                } else {
+                    time
+                };
+                trace!("{}vk.key({time}, {keycode}, 0)", self.log_prefix);
                 vk.key(time, keycode, 0);
+                self.stats.events_sent += 1;
                 // TODO: Change to flush()
-                if self.event_queue.roundtrip(&mut self.state).is_err() {
-                    return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+                if !self.batch_keys {
+                    self.stats.roundtrips += 1;
+                    if self.event_queue.roundtrip(&mut self.state).is_err() {
+                        return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+                    }
                 }
             }
             return Ok(());
         }
-        Err(InputError::Simulate("no way to enter key"))
+        Err(InputError::Unsupported("no way to enter key"))
     }
 
     /// Sends a modifier event with the updated bitflag of the modifiers to the
     /// compositor
     fn send_modifier_event(&mut self, modifiers: ModifierBitflag) -> InputResult<()> {
+        self.commit_pending_text()?;
         if let Some(vk) = &self.virtual_keyboard {
             is_alive(vk)?;
-            trace!("vk.modifiers({modifiers}, 0, 0, 0)");
+            trace!("{}vk.modifiers({modifiers}, 0, 0, 0)", self.log_prefix);
             vk.modifiers(modifiers, 0, 0, 0);
+            self.stats.events_sent += 1;
             // TODO: Change to flush()
-            if self.event_queue.roundtrip(&mut self.state).is_err() {
-                return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+            if !self.batch_keys {
+                self.stats.roundtrips += 1;
+                if self.event_queue.roundtrip(&mut self.state).is_err() {
+                    return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+                }
             }
             return Ok(());
         }
-        Err(InputError::Simulate("no way to enter modifier"))
+        Err(InputError::Unsupported("no way to enter modifier"))
     }
 
     /// Apply the current keymap
@@ -245,34 +411,122 @@ impl Con {
     /// # Errors
     /// TODO
     fn apply_keymap(&mut self) -> InputResult<()> {
-        trace!("apply_keymap(&mut self)");
+        trace!("{}apply_keymap(&mut self)", self.log_prefix);
         if let Some(vk) = &self.virtual_keyboard {
             is_alive(vk)?;
-            let Ok(keymap_res) = self.keymap.regenerate() else {
-                return Err(InputError::Mapping(
-                    "unable to regenerate keymap".to_string(),
-                ));
-            };
             // Only send an updated keymap if we had to regenerate it
-            // There should always be a file at this point so unwrapping is fine
-            // here
-            if let Some(keymap_size) = keymap_res {
-                trace!("update wayland keymap");
-                vk.keymap(1, self.keymap.file.as_ref().unwrap().as_fd(), keymap_size);
+            if let Some(keymap_size) = self.keymap.regenerate()? {
+                let Ok(keymap_size) = keymap_size.try_into() else {
+                    return Err(InputError::Mapping(
+                        "the size of the keymap does not fit in u32".to_string(),
+                    ));
+                };
+                let Some(keymap_file) = self.keymap.file.as_ref() else {
+                    return Err(InputError::Mapping(
+                        "there is no file containing the keymap".to_string(),
+                    ));
+                };
+                trace!("{}update wayland keymap", self.log_prefix);
+                vk.keymap(1, keymap_file.as_fd(), keymap_size);
                 // TODO: Change to flush()
+                self.stats.roundtrips += 1;
                 if self.event_queue.roundtrip(&mut self.state).is_err() {
                     return Err(InputError::Simulate("The roundtrip on Wayland failed"));
                 }
             }
             return Ok(());
         }
-        Err(InputError::Simulate("no way to apply keymap"))
+        Err(InputError::Unsupported("no way to apply keymap"))
+    }
+
+    /// Commit the text with the `input_method` protocol. Returns false if the
+    /// protocol is not available
+    fn commit_text(&mut self, text: &str) -> InputResult<bool> {
+        let Some((im, serial)) = self.input_method.as_mut() else {
+            return Ok(false);
+        };
+        is_alive(im)?;
+        trace!(
+            "{}fast text input with imput_method protocol",
+            self.log_prefix
+        );
+        im.commit_string(text.to_string());
+        im.commit(*serial);
+        *serial = serial.wrapping_add(1);
+        self.stats.events_sent += 1;
+        // TODO: Change to flush()
+        self.stats.roundtrips += 1;
+        if self.event_queue.roundtrip(&mut self.state).is_err() {
+            return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+        }
+        Ok(true)
+    }
+
+    /// Commit the text of the current batch that was not committed yet
+    fn commit_pending_text(&mut self) -> InputResult<()> {
+        let Some(text) = self.pending_text.as_mut().filter(|text| !text.is_empty()) else {
+            return Ok(());
+        };
+        let text = std::mem::take(text);
+        trace!("{}commit the pending text {text:?}", self.log_prefix);
+        self.commit_text(&text)?;
+        Ok(())
+    }
+
+    /// Start a batch of text that is committed at once with `end_text`.
+    /// Returns false if the `input_method` protocol is not available
+    pub fn begin_text(&mut self) -> bool {
+        if self.input_method.is_none() {
+            return false;
+        }
+        self.pending_text.get_or_insert_with(String::new);
+        true
+    }
+
+    /// Commit the text of the batch and end it
+    pub fn end_text(&mut self) -> InputResult<()> {
+        let res = self.commit_pending_text();
+        self.pending_text = None;
+        res
+    }
+
+    /// Start a batch of keys. Their events are queued and only sent to the
+    /// compositor at once with `end_key_batch`
+    pub fn begin_key_batch(&mut self) {
+        self.batch_keys = true;
+    }
+
+    /// Send the queued events of the batch of keys and end it
+    pub fn end_key_batch(&mut self) -> InputResult<()> {
+        self.batch_keys = false;
+        self.stats.roundtrips += 1;
+        if self.event_queue.roundtrip(&mut self.state).is_err() {
+            return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+        }
+        Ok(())
+    }
+
+    /// Generate the keymap from the named keyboard layout and apply it
+    pub fn set_layout(&mut self, layout: &str) -> InputResult<()> {
+        self.keymap.set_layout(layout)?;
+        self.apply_keymap()
     }
 
     fn raw(&mut self, keycode: Keycode, direction: Direction) -> InputResult<()> {
+        // raw_at makes sure the timestamp is larger than the previous one
+        let time = self.elapsed_time();
+        self.raw_at(keycode, direction, time)
+    }
+
+    /// Press/Release a keycode with the given timestamp in milliseconds since
+    /// the connection was established. The timestamps have to be strictly
+    /// increasing, so if it is not larger than the one of the previous event,
+    /// the next larger one is used instead
+    pub fn raw_at(&mut self, keycode: Keycode, direction: Direction, time: u32) -> InputResult<()> {
+        let time = self.use_time(time);
         // Apply the new keymap if there were any changes
         self.apply_keymap()?;
-        self.send_key_event(keycode, direction)?;
+        self.send_key_event(keycode, direction, time)?;
         // Let the keymap know that the key was held/no longer held
         // This is important to avoid unmapping held keys
         self.keymap.key(keycode, direction);
@@ -280,15 +534,95 @@ impl Con {
         Ok(())
     }
 
+    /// Get the number of roundtrips, sent events and changes of the keymap
+    /// since the connection was established or the stats were reset
+    pub fn stats(&self) -> Stats {
+        let mut stats = self.keymap.stats();
+        stats += self.stats;
+        stats
+    }
+
+    /// Reset the stats to zero
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+        self.keymap.reset_stats();
+    }
+
+    /// Get the capabilities of the connection
+    pub fn capabilities(&self) -> Capabilities {
+        let mut capabilities = Capabilities::default();
+        if self.virtual_pointer.is_some() {
+            capabilities.scroll_method = Some(if self.supports_axis_discrete() {
+                ScrollMethod::AxisDiscrete
+            } else {
+                ScrollMethod::Axis
+            });
+            capabilities.wayland_virtual_pointer_version = Some(self.state.pointer_manager_version);
+        }
+        capabilities
+    }
+
+    /// Check if the bound version of the virtual pointer has the
+    /// `axis_discrete` request
+    fn supports_axis_discrete(&self) -> bool {
+        self.virtual_pointer
+            .as_ref()
+            .is_some_and(|vp| vp.version() >= zwlr_virtual_pointer_v1::REQ_AXIS_DISCRETE_SINCE)
+    }
+
+    /// Send the requests to the virtual pointer and wait until the compositor
+    /// processed them
+    fn send_pointer_requests(&mut self, requests: &[PointerRequest]) -> InputResult<()> {
+        self.queue_pointer_requests(requests)?;
+        // TODO: Change to flush()
+        self.stats.roundtrips += 1;
+        match self.event_queue.roundtrip(&mut self.state) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(InputError::Simulate("The roundtrip on Wayland failed")),
+        }
+    }
+
+    /// Add the requests to the queue of the virtual pointer without sending
+    /// them
+    fn queue_pointer_requests(&mut self, requests: &[PointerRequest]) -> InputResult<()> {
+        // The text of the batch has to be entered before the mouse is used
+        self.commit_pending_text()?;
+        if let Some(vp) = &self.virtual_pointer {
+            is_alive(vp)?;
+            for &request in requests {
+                let time = self.get_time();
+                trace!("{}vp: {request:?} at {time}", self.log_prefix);
+                match request {
+                    PointerRequest::Button(button, state) => vp.button(time, button, state),
+                    PointerRequest::Motion(x, y) => vp.motion(time, x, y),
+                    PointerRequest::MotionAbsolute(x, y, x_extent, y_extent) => {
+                        vp.motion_absolute(time, x, y, x_extent, y_extent);
+                    }
+                    PointerRequest::AxisSource(source) => vp.axis_source(source),
+                    PointerRequest::Axis(axis, value) => vp.axis(time, axis, value),
+                    PointerRequest::AxisStop(axis) => vp.axis_stop(time, axis),
+                    PointerRequest::AxisDiscrete(axis, value, discrete) => {
+                        vp.axis_discrete(time, axis, value, discrete);
+                    }
+                    PointerRequest::Frame => vp.frame(),
+                }
+                if request != PointerRequest::Frame {
+                    self.stats.events_sent += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Flush the Wayland queue
     fn flush(&self) -> InputResult<()> {
         match self.event_queue.flush() {
             Ok(()) => {
-                trace!("flushed event queue");
+                trace!("{}flushed event queue", self.log_prefix);
                 Ok(())
             }
             Err(e) => {
-                error!("{:?}", e);
+                error!("{}{:?}", self.log_prefix, e);
                 Err(InputError::Simulate("could not flush wayland queue"))
             }
         }
@@ -303,6 +637,9 @@ impl Bind<Keycode> for Con {
 impl Drop for Con {
     // Destroy the Wayland objects we created
     fn drop(&mut self) {
+        if self.end_text().is_err() {
+            error!("{}could not commit the pending text", self.log_prefix);
+        }
         if let Some(vk) = &self.virtual_keyboard {
             vk.destroy();
         }
@@ -313,9 +650,9 @@ impl Drop for Con {
             vp.destroy();
         }
         if self.flush().is_err() {
-            error!("could not flush wayland queue");
+            error!("{}could not flush wayland queue", self.log_prefix);
         }
-        trace!("wayland objects were destroyed");
+        trace!("{}wayland objects were destroyed", self.log_prefix);
 
         let _ = self.event_queue.roundtrip(&mut self.state);
     }
@@ -326,11 +663,17 @@ struct WaylandState {
     keyboard_manager: Option<zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1>,
     im_manager: Option<zwp_input_method_manager_v2::ZwpInputMethodManagerV2>,
     pointer_manager: Option<zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1>,
+    pointer_manager_version: u32,
     kde_input: Option<org_kde_kwin_fake_input::OrgKdeKwinFakeInput>,
-    seat: Option<wl_seat::WlSeat>,
-    /*  output: Option<wl_output::WlOutput>,
-    width: i32,
-    height: i32,*/
+    seats: Vec<(wl_seat::WlSeat, Option<String>)>, // all seats and their names
+    seat: Option<wl_seat::WlSeat>,                 // the seat that is used
+    output: Option<wl_output::WlOutput>,
+    width: i32,                 // physical pixels
+    height: i32,                // physical pixels
+    scale: i32,                 // physical pixels per logical pixel
+    synced: bool,               // the compositor answered the last sync request
+    disable_input_method: bool, // do not bind the input method manager
+    log_prefix: String,         // prefix of the log messages of the instance of Enigo
 }
 
 impl WaylandState {
@@ -339,11 +682,17 @@ impl WaylandState {
             keyboard_manager: None,
             im_manager: None,
             pointer_manager: None,
+            pointer_manager_version: 0,
             kde_input: None,
+            seats: vec![],
             seat: None,
-            /*  output: None,
+            output: None,
             width: 0,
-            height: 0,*/
+            height: 0,
+            scale: 1,
+            synced: false,
+            disable_input_method: false,
+            log_prefix: String::new(),
         }
     }
 }
@@ -362,18 +711,25 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
         if let wl_registry::Event::Global {
             name,
             interface,
-            version: _,
+            version,
         } = event
         {
             match &interface[..] {
                 "wl_seat" => {
-                    let seat = registry.bind::<wl_seat::WlSeat, _, _>(name, 1, qh, ());
-                    state.seat = Some(seat);
+                    // The name of the seat is only sent since version 2
+                    let seat = registry.bind::<wl_seat::WlSeat, _, _>(name, version.min(2), qh, ());
+                    state.seats.push((seat, None));
                 }
-                /*"wl_output" => {
-                    let output = registry.bind::<wl_output::WlOutput, _, _>(name, 1, qh, ());
+                // Only the first output is used
+                "wl_output" if state.output.is_none() => {
+                    // The scale of the output is only sent since version 2
+                    let output =
+                        registry.bind::<wl_output::WlOutput, _, _>(name, version.min(2), qh, ());
                     state.output = Some(output);
-                }*/
+                }
+                "zwp_input_method_manager_v2" if state.disable_input_method => {
+                    debug!("{}the input method protocol is disabled", state.log_prefix);
+                }
                 "zwp_input_method_manager_v2" => {
                     let manager = registry
                         .bind::<zwp_input_method_manager_v2::ZwpInputMethodManagerV2, _, _>(
@@ -395,17 +751,20 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                     state.keyboard_manager = Some(manager);
                 }
                 "zwlr_virtual_pointer_manager_v1" => {
+                    // Version 2 is the newest version that is supported
+                    let version = version.min(2);
                     let manager = registry
                         .bind::<zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1, _, _>(
                         name,
-                        1,
+                        version,
                         qh,
                         (),
                     );
                     state.pointer_manager = Some(manager);
+                    state.pointer_manager_version = version;
                 }
                 "org_kde_kwin_fake_input" => {
-                    debug!("FAKE_INPUT AVAILABLE!");
+                    debug!("{}FAKE_INPUT AVAILABLE!", state.log_prefix);
                     let kde_input = registry
                         .bind::<org_kde_kwin_fake_input::OrgKdeKwinFakeInput, _, _>(
                             name,
@@ -416,66 +775,107 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                     state.kde_input = Some(kde_input);
                 }
                 s => {
-                    trace!("i: {}", s);
+                    trace!("{}i: {}", state.log_prefix, s);
                 }
             }
         }
     }
 }
 
+impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        (): &(),
+        _: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => {
+                debug!(
+                    "{}the size of the output is {width}x{height}",
+                    state.log_prefix
+                );
+                state.width = width;
+                state.height = height;
+            }
+            wl_output::Event::Scale { factor } if factor > 0 => {
+                debug!("{}the scale of the output is {factor}", state.log_prefix);
+                state.scale = factor;
+            }
+            _ => {}
+        }
+    }
+}
+
 impl Dispatch<zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _manager: &zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
         event: zwp_virtual_keyboard_manager_v1::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Received a virtual keyboard manager event {:?}", event);
+        warn!(
+            "{}Received a virtual keyboard manager event {:?}",
+            state.log_prefix, event
+        );
     }
 }
 
 impl Dispatch<zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _vk: &zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
         event: zwp_virtual_keyboard_v1::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Got a virtual keyboard event {:?}", event);
+        warn!(
+            "{}Got a virtual keyboard event {:?}",
+            state.log_prefix, event
+        );
     }
 }
 
 impl Dispatch<zwp_input_method_manager_v2::ZwpInputMethodManagerV2, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _manager: &zwp_input_method_manager_v2::ZwpInputMethodManagerV2,
         event: zwp_input_method_manager_v2::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Received an input method manager event {:?}", event);
+        warn!(
+            "{}Received an input method manager event {:?}",
+            state.log_prefix, event
+        );
     }
 }
 impl Dispatch<zwp_input_method_v2::ZwpInputMethodV2, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _vk: &zwp_input_method_v2::ZwpInputMethodV2,
         event: zwp_input_method_v2::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Got a input method event {:?}", event);
+        warn!("{}Got a input method event {:?}", state.log_prefix, event);
     }
 }
 impl Dispatch<org_kde_kwin_fake_input::OrgKdeKwinFakeInput, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _vk: &org_kde_kwin_fake_input::OrgKdeKwinFakeInput,
         event: org_kde_kwin_fake_input::Event,
         (): &(),
@@ -484,20 +884,104 @@ impl Dispatch<org_kde_kwin_fake_input::OrgKdeKwinFakeInput, ()> for WaylandState
     ) {
         // This should never happen, as there are no events specified for this
         // in the protocol
-        warn!("Got a plasma fake input event {:?}", event);
+        warn!(
+            "{}Got a plasma fake input event {:?}",
+            state.log_prefix, event
+        );
     }
 }
 
 impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
-        _seat: &wl_seat::WlSeat,
+        state: &mut Self,
+        seat: &wl_seat::WlSeat,
         event: wl_seat::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Got a seat event {:?}", event);
+        match event {
+            wl_seat::Event::Name { name } => {
+                debug!("{}the seat is named {name}", state.log_prefix);
+                if let Some((_, seat_name)) = state.seats.iter_mut().find(|(s, _)| s == seat) {
+                    *seat_name = Some(name);
+                }
+            }
+            wl_seat::Event::Capabilities { capabilities } => {
+                debug!(
+                    "{}the seat has the capabilities {capabilities:?}",
+                    state.log_prefix
+                );
+            }
+            _ => warn!("{}Got a seat event {:?}", state.log_prefix, event),
+        }
+    }
+}
+
+impl Dispatch<wl_callback::WlCallback, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _: &wl_callback::WlCallback,
+        event: wl_callback::Event,
+        (): &(),
+        _: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            state.synced = true;
+        }
+    }
+}
+
+/// Same as `EventQueue::roundtrip`, but it gives up waiting for the
+/// compositor after the timeout. Returns false if the compositor did not answer
+/// in time
+fn roundtrip_with_timeout(
+    event_queue: &mut EventQueue<WaylandState>,
+    state: &mut WaylandState,
+    display: &wl_display::WlDisplay,
+    timeout: Duration,
+) -> Result<bool, NewConError> {
+    let deadline = Instant::now() + timeout;
+    state.synced = false;
+    display.sync(&event_queue.handle(), ());
+    if event_queue.flush().is_err() {
+        return Err(NewConError::EstablishCon("wayland roundtrip not possible"));
+    }
+    loop {
+        if event_queue.dispatch_pending(state).is_err() {
+            return Err(NewConError::EstablishCon("wayland roundtrip not possible"));
+        }
+        if state.synced {
+            return Ok(true);
+        }
+        // Events were queued in the meantime and have to get dispatched first
+        let Some(guard) = event_queue.prepare_read() else {
+            continue;
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut fd = libc::pollfd {
+            fd: guard.connection_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = libc::c_int::try_from(remaining.as_millis()).unwrap_or(libc::c_int::MAX);
+        match unsafe { libc::poll(&mut fd, 1, timeout_ms) } {
+            // Dropping the guard cancels the read
+            0 => return Ok(false),
+            n if n < 0 => {
+                if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+                    return Err(NewConError::EstablishCon("wayland roundtrip not possible"));
+                }
+            }
+            _ => match guard.read() {
+                Ok(_) => (),
+                Err(WaylandError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => (),
+                Err(_) => {
+                    return Err(NewConError::EstablishCon("wayland roundtrip not possible"));
+                }
+            },
+        }
     }
 }
 
@@ -524,7 +1008,7 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
             } => {
                 state.width = x;
                 state.height = y;
-                warn!("x: {}, y: {}, physical_width: {}, physical_height: {}, make: {}, : {}",x,y,physical_width,physical_height,make,model,model);
+                warn!("{}x: {}, y: {}, physical_width: {}, physical_height: {}, make: {}, : {}",state.log_prefix,x,y,physical_width,physical_height,make,model,model);
             }
             wl_output::Event::Mode {
                 flags,
@@ -532,7 +1016,7 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
                 height,
                 refresh,
             } => {
-                warn!("width: {}, : {height}",width,height);
+                warn!("{}width: {}, : {height}",state.log_prefix,width,height);
             }
             _ => {}
         };
@@ -541,27 +1025,33 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandState {
 
 impl Dispatch<zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _manager: &zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1,
         event: zwlr_virtual_pointer_manager_v1::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Received a virtual keyboard manager event {:?}", event);
+        warn!(
+            "{}Received a virtual keyboard manager event {:?}",
+            state.log_prefix, event
+        );
     }
 }
 
 impl Dispatch<zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _vk: &zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1,
         event: zwlr_virtual_pointer_v1::Event,
         (): &(),
         _: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
-        warn!("Got a virtual keyboard event {:?}", event);
+        warn!(
+            "{}Got a virtual keyboard event {:?}",
+            state.log_prefix, event
+        );
     }
 }
 
@@ -579,39 +1069,77 @@ impl Drop for WaylandState {
 
 impl Keyboard for Con {
     fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
-        if let Some((im, serial)) = self.input_method.as_mut() {
-            is_alive(im)?;
-            trace!("fast text input with imput_method protocol");
-            im.commit_string(text.to_string());
-            im.commit(*serial);
-            *serial = serial.wrapping_add(1);
-            // TODO: Change to flush()
-            if self.event_queue.roundtrip(&mut self.state).is_err() {
-                return Err(InputError::Simulate("The roundtrip on Wayland failed"));
+        // The input_method protocol ignores the held modifiers
+        let Some(text) = shifted_text(text, self.keymap.held_modifiers()) else {
+            debug!(
+                "{}modifiers are held, so the text is entered as key events",
+                self.log_prefix
+            );
+            return Ok(None);
+        };
+        let text = text.as_str();
+        if self.input_method.is_some() {
+            if let Some(pending_text) = self.pending_text.as_mut() {
+                trace!("{}add the text to the batch", self.log_prefix);
+                pending_text.push_str(text);
+                return Ok(Some(()));
             }
-            return Ok(Some(()));
         }
-        Ok(None)
+        Ok(self.commit_text(text)?.then_some(()))
     }
 
     fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
         // Send the events to the compositor
         if let Ok(modifier) = Modifier::try_from(key) {
-            trace!("it is a modifier: {modifier:?}");
+            trace!("{}it is a modifier: {modifier:?}", self.log_prefix);
             if direction == Direction::Click || direction == Direction::Press {
-                let modifiers = self
-                    .keymap
-                    .enter_modifier(modifier.bitflag(), Direction::Press);
+                let modifiers = self.keymap.enter_modifier(modifier, Direction::Press);
                 self.send_modifier_event(modifiers)?;
             }
             if direction == Direction::Click || direction == Direction::Release {
-                let modifiers = self
-                    .keymap
-                    .enter_modifier(modifier.bitflag(), Direction::Release);
+                let modifiers = self.keymap.enter_modifier(modifier, Direction::Release);
                 self.send_modifier_event(modifiers)?;
             }
         } else {
-            let keycode = self.keymap.key_to_keycode(&(), key)?;
+            // The keymap only has one level, so Shift has to be applied here
+            let key = match key {
+                Key::Unicode(c) => {
+                    let shift_held = self.keymap.held_modifiers().contains(&Modifier::Shift);
+                    Key::Unicode(pressed_char(
+                        &mut self.pressed_chars,
+                        c,
+                        direction,
+                        shift_held,
+                    ))
+                }
+                key => key,
+            };
+            let keycode = match self.keymap.key_to_keycode(&(), key) {
+                Ok(keycode) => keycode,
+                // If the char could not be mapped (e.g. because the keymap is full), it can
+                // still be entered with the input_method protocol. The text is committed
+                // at once so there is nothing to do when the key is released
+                Err(e) => {
+                    let Key::Unicode(c) = key else {
+                        return Err(e);
+                    };
+                    warn!(
+                        "{}unable to map {c:?}, falling back to the input_method protocol: {e}",
+                        self.log_prefix
+                    );
+                    if direction == Direction::Release {
+                        return if self.input_method.is_some() {
+                            Ok(())
+                        } else {
+                            Err(e)
+                        };
+                    }
+                    return match self.fast_text(&c.to_string())? {
+                        Some(()) => Ok(()),
+                        None => Err(e),
+                    };
+                }
+            };
             self.raw(keycode, direction)?;
         }
 
@@ -624,126 +1152,277 @@ impl Keyboard for Con {
 }
 impl Mouse for Con {
     fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
-        if let Some(vp) = &self.virtual_pointer {
-            // Do nothing if one of the mouse scroll buttons was released
-            // Releasing one of the scroll mouse buttons has no effect
-            if direction == Direction::Release {
-                match button {
-                    Button::Left
-                    | Button::Right
-                    | Button::Back
-                    | Button::Forward
-                    | Button::Middle => {}
-                    Button::ScrollDown
-                    | Button::ScrollUp
-                    | Button::ScrollRight
-                    | Button::ScrollLeft => return Ok(()),
-                }
-            };
+        // Do nothing if one of the mouse scroll buttons was released
+        // Releasing one of the scroll mouse buttons has no effect
+        if direction == Direction::Release {
+            match button {
+                Button::Left | Button::Right | Button::Back | Button::Forward | Button::Middle => {}
+                Button::ScrollDown
+                | Button::ScrollUp
+                | Button::ScrollRight
+                | Button::ScrollLeft => return Ok(()),
+            }
+        };
 
-            let button = match button {
-                // Taken from /linux/input-event-codes.h
-                Button::Left => 0x110,
-                Button::Right => 0x111,
-                Button::Back => 0x116,
-                Button::Forward => 0x115,
-                Button::Middle => 0x112,
-                Button::ScrollDown => return self.scroll(1, Axis::Vertical),
-                Button::ScrollUp => return self.scroll(-1, Axis::Vertical),
-                Button::ScrollRight => return self.scroll(1, Axis::Horizontal),
-                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal),
-            };
+        let button = match button {
+            // Taken from /linux/input-event-codes.h
+            Button::Left => 0x110,
+            Button::Right => 0x111,
+            Button::Back => 0x116,
+            Button::Forward => 0x115,
+            Button::Middle => 0x112,
+            Button::ScrollDown => return self.scroll(1, Axis::Vertical).map(|_| ()),
+            Button::ScrollUp => return self.scroll(-1, Axis::Vertical).map(|_| ()),
+            Button::ScrollRight => return self.scroll(1, Axis::Horizontal).map(|_| ()),
+            Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal).map(|_| ()),
+        };
 
-            if direction == Direction::Press || direction == Direction::Click {
-                let time = self.get_time();
-                trace!("vp.button({time}, {button}, wl_pointer::ButtonState::Pressed)");
-                vp.button(time, button, wl_pointer::ButtonState::Pressed);
-                vp.frame(); // TODO: Check if this is needed
-            }
+        self.send_pointer_requests(&button_requests(button, direction))
+    }
 
-            if direction == Direction::Release || direction == Direction::Click {
-                let time = self.get_time();
-                trace!("vp.button({time}, {button}, wl_pointer::ButtonState::Released)");
-                vp.button(time, button, wl_pointer::ButtonState::Released);
-                vp.frame(); // TODO: Check if this is needed
-            }
-        }
-        // TODO: Change to flush()
-        match self.event_queue.roundtrip(&mut self.state) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(InputError::Simulate("The roundtrip on Wayland failed")),
+    /// Each motion is sent in its own frame, so the compositor does not merge
+    /// successive moves into one motion event. The queue is only flushed
+    /// without waiting for the compositor, because a roundtrip per move is
+    /// slow
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        let scale = self.physical_scale();
+        let size = self.output_size();
+        self.queue_pointer_requests(&motion_requests(x, y, coordinate, scale, size)?)?;
+        self.flush()?;
+        self.pointer_location = track_location(self.pointer_location, x, y, coordinate, size);
+        Ok(())
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
+        let stop = self
+            .virtual_pointer
+            .as_ref()
+            .is_some_and(|vp| vp.version() >= zwlr_virtual_pointer_v1::REQ_AXIS_STOP_SINCE);
+        let discrete = self.supports_axis_discrete();
+        self.send_pointer_requests(&scroll_requests(length, axis, discrete, stop))?;
+        // All notches are sent in a single axis or axis_discrete event
+        Ok(1)
+    }
+
+    /// The size is in logical pixels, unless the connection uses physical
+    /// pixels
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        if let Some(size) = self.output_size() {
+            return Ok(size);
         }
+        // Not an error, because the capabilities probe if this is supported
+        debug!("{}You tried to get the dimensions of the main display, but the compositor did not send the size of an output", self.log_prefix);
+        Err(InputError::Unsupported(
+            "the compositor did not send the size of an output",
+        ))
     }
 
-    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
-        if let Some(vp) = &self.virtual_pointer {
-            let time = self.get_time();
-            match coordinate {
-                Coordinate::Rel => {
-                    trace!("vp.motion({time}, {x}, {y})");
-                    vp.motion(time, x as f64, y as f64);
-                }
-                Coordinate::Abs => {
-                    let Ok(x) = x.try_into() else {
-                        return Err(InputError::InvalidInput(
-                            "the absolute coordinates cannot be negative",
-                        ));
-                    };
-                    let Ok(y) = y.try_into() else {
-                        return Err(InputError::InvalidInput(
-                            "the absolute coordinates cannot be negative",
-                        ));
-                    };
-                    trace!("vp.motion_absolute({time}, {x}, {y}, u32::MAX, u32::MAX)");
-                    vp.motion_absolute(
-                        time,
-                        x,
-                        y,
-                        u32::MAX, // TODO: Check what would be the correct value here
-                        u32::MAX, // TODO: Check what would be the correct value here
-                    );
-                }
-            }
-            vp.frame(); // TODO: Check if this is needed
+    /// The location is only an estimate. There is no protocol to get the
+    /// location of the mouse, so enigo keeps track of where it moved the
+    /// mouse to. It is only known after the mouse was moved to absolute
+    /// coordinates and it does not include the movements of the user
+    fn location(&self) -> InputResult<(i32, i32)> {
+        if let Some(location) = self.pointer_location {
+            return Ok(location);
         }
-        // TODO: Change to flush()
-        match self.event_queue.roundtrip(&mut self.state) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(InputError::Simulate("The roundtrip on Wayland failed")),
+        // Not an error, because the capabilities probe if this is supported
+        debug!("{}You tried to get the mouse location. I don't know how this is possible under Wayland. Let me know if there is a new protocol. The location is estimated after the mouse was moved to absolute coordinates", self.log_prefix);
+        Err(InputError::Unsupported(
+            "the location of the mouse is only known after it was moved to absolute coordinates",
+        ))
+    }
+}
+
+/// Estimate the location of the pointer after it was moved. A relative
+/// movement can only be tracked if the location was known before. The location
+/// is clamped to the size of the display if it is known
+fn track_location(
+    location: Option<(i32, i32)>,
+    x: i32,
+    y: i32,
+    coordinate: Coordinate,
+    size: Option<(i32, i32)>,
+) -> Option<(i32, i32)> {
+    let (x, y) = match coordinate {
+        Coordinate::Abs => (x, y),
+        Coordinate::Rel => {
+            let (current_x, current_y) = location?;
+            (current_x.saturating_add(x), current_y.saturating_add(y))
         }
+    };
+    let (max_x, max_y) = size.map_or((i32::MAX, i32::MAX), |(w, h)| (w - 1, h - 1));
+    Some((x.clamp(0, max_x), y.clamp(0, max_y)))
+}
+
+/// A request to the virtual pointer. The compositor treats all requests up to
+/// a [`PointerRequest::Frame`] as one logical event, so every button press,
+/// button release, motion and scroll has to be followed by exactly one frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PointerRequest {
+    Button(u32, wl_pointer::ButtonState),
+    Motion(f64, f64),
+    MotionAbsolute(u32, u32, u32, u32), // location and size of the output
+
+    AxisSource(wl_pointer::AxisSource),
+    Axis(wl_pointer::Axis, f64),
+    AxisDiscrete(wl_pointer::Axis, f64, i32),
+    AxisStop(wl_pointer::Axis),
+    Frame,
+}
+
+/// Requests to press and/or release the button. A click is made up of two
+/// logical events, so the press and the release each get their own frame
+fn button_requests(button: u32, direction: Direction) -> Vec<PointerRequest> {
+    let mut requests = vec![];
+    if direction == Direction::Press || direction == Direction::Click {
+        requests.push(PointerRequest::Button(
+            button,
+            wl_pointer::ButtonState::Pressed,
+        ));
+        requests.push(PointerRequest::Frame);
+    }
+    if direction == Direction::Release || direction == Direction::Click {
+        requests.push(PointerRequest::Button(
+            button,
+            wl_pointer::ButtonState::Released,
+        ));
+        requests.push(PointerRequest::Frame);
     }
+    requests
+}
 
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
-        if let Some(vp) = &self.virtual_pointer {
-            // TODO: Check what the value of length should be
-            // TODO: Check if it would be better to use .axis_discrete here
-            let time = self.get_time();
-            let axis = match axis {
-                Axis::Horizontal => wl_pointer::Axis::HorizontalScroll,
-                Axis::Vertical => wl_pointer::Axis::VerticalScroll,
+/// Requests to move the pointer. The coordinates and the size of the output
+/// are divided by the scale to convert them to the logical pixels the
+/// compositor expects. The compositor maps an absolute location to the output
+/// by its fraction of the size, so the size is needed for absolute movements
+fn motion_requests(
+    x: i32,
+    y: i32,
+    coordinate: Coordinate,
+    scale: i32,
+    size: Option<(i32, i32)>,
+) -> InputResult<Vec<PointerRequest>> {
+    let motion = match coordinate {
+        Coordinate::Rel => PointerRequest::Motion(
+            f64::from(x) / f64::from(scale),
+            f64::from(y) / f64::from(scale),
+        ),
+        Coordinate::Abs => {
+            let Some((width, height)) = size else {
+                return Err(InputError::Unsupported(
+                    "the compositor did not send the size of an output, so the pointer cannot be moved to absolute coordinates",
+                ));
             };
-            trace!("vp.axis(time, axis, length.into())");
-            vp.axis(time, axis, length.into());
-            vp.frame(); // TODO: Check if this is needed
-        }
-        // TODO: Change to flush()
-        match self.event_queue.roundtrip(&mut self.state) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(InputError::Simulate("The roundtrip on Wayland failed")),
+            let (Ok(x), Ok(y)) = ((x / scale).try_into(), (y / scale).try_into()) else {
+                return Err(InputError::InvalidInput(
+                    "the absolute coordinates cannot be negative",
+                ));
+            };
+            let (Ok(width), Ok(height)) = ((width / scale).try_into(), (height / scale).try_into())
+            else {
+                return Err(InputError::Unsupported("the size of the output is invalid"));
+            };
+            PointerRequest::MotionAbsolute(x, y, width, height)
         }
+    };
+    Ok(vec![motion, PointerRequest::Frame])
+}
+
+/// Requests to scroll `length` notches of a scroll wheel. The source of the
+/// axis event has to be in the same frame as the axis event. Some compositors
+/// ignore the continuous `axis` event, so the `axis_discrete` event is used if
+/// `discrete` is true. It contains the continuous value and the number of
+/// notches. Otherwise only the continuous value is sent with the `axis` event.
+/// If `stop` is true, the end of the scrolling is sent in its own frame
+/// afterwards, so the compositor does not continue with kinetic scrolling
+fn scroll_requests(length: i32, axis: Axis, discrete: bool, stop: bool) -> Vec<PointerRequest> {
+    let axis = match axis {
+        Axis::Horizontal => wl_pointer::Axis::HorizontalScroll,
+        Axis::Vertical => wl_pointer::Axis::VerticalScroll,
+    };
+    let value = f64::from(length) * crate::SCROLL_DEGREES_PER_NOTCH;
+    let mut requests = vec![
+        PointerRequest::AxisSource(wl_pointer::AxisSource::Wheel),
+        if discrete {
+            PointerRequest::AxisDiscrete(axis, value, length)
+        } else {
+            PointerRequest::Axis(axis, value)
+        },
+        PointerRequest::Frame,
+    ];
+    if stop {
+        requests.push(PointerRequest::AxisStop(axis));
+        requests.push(PointerRequest::Frame);
     }
+    requests
+}
 
-    fn main_display(&self) -> InputResult<(i32, i32)> {
-        // TODO Implement this
-        error!("You tried to get the dimensions of the main display. I don't know how this is possible under Wayland. Let me know if there is a new protocol");
-        Err(InputError::Simulate("Not implemented yet"))
+/// Get the timestamp of the next event from the elapsed milliseconds. If it
+/// is not larger than the timestamp of the previous event, the previous one is
+/// increased by one
+fn next_time(last: Option<u32>, elapsed: u32) -> u32 {
+    match last {
+        Some(last) if elapsed <= last => last.saturating_add(1),
+        _ => elapsed,
     }
+}
 
-    fn location(&self) -> InputResult<(i32, i32)> {
-        // TODO Implement this
-        error!("You tried to get the mouse location. I don't know how this is possible under Wayland. Let me know if there is a new protocol");
-        Err(InputError::Simulate("Not implemented yet"))
+/// Convert the xkb keycode to the evdev keycode that is sent to the compositor.
+/// They are offset by 8 due to the xkb/xwayland requirements. The keymap only
+/// contains the xkb keycodes from 8 to 255, so all other keycodes are invalid
+fn evdev_keycode(keycode: Keycode) -> InputResult<Keycode> {
+    if (8..=255).contains(&keycode) {
+        Ok(keycode - 8)
+    } else {
+        Err(InputError::InvalidInput(
+            "the keycode has to be between 8 and 255 on Wayland",
+        ))
+    }
+}
+
+/// Get the char that a key with the char produces while Shift is held
+fn shifted_char(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        // Chars like 'ß' do not have a single uppercase char
+        _ => c,
+    }
+}
+
+/// Get the char that is entered for `c`. While Shift is held, it is the
+/// uppercase char. A released char is always the char that was pressed for it,
+/// so the same keycode is released even if Shift was released in between
+fn pressed_char(
+    pressed_chars: &mut Vec<(char, char)>,
+    c: char,
+    direction: Direction,
+    shift_held: bool,
+) -> char {
+    if direction == Direction::Release {
+        if let Some(idx) = pressed_chars.iter().position(|&(held, _)| held == c) {
+            return pressed_chars.remove(idx).1;
+        }
+    }
+    let pressed = if shift_held { shifted_char(c) } else { c };
+    if direction == Direction::Press {
+        pressed_chars.push((c, pressed));
+    }
+    pressed
+}
+
+/// Apply the held modifiers to the text like a keyboard would. While Shift is
+/// held, the text is converted to uppercase. None is returned if any other
+/// modifier is held, because the text then has to be entered with key events,
+/// so the application sees the shortcut
+fn shifted_text(text: &str, held_modifiers: &[Modifier]) -> Option<String> {
+    if held_modifiers.iter().any(|&m| m != Modifier::Shift) {
+        return None;
+    }
+    if held_modifiers.is_empty() {
+        return Some(text.to_string());
     }
+    Some(text.chars().map(shifted_char).collect())
 }
 
 fn is_alive<P: wayland_client::Proxy>(proxy: &P) -> InputResult<()> {
@@ -753,3 +1432,203 @@ fn is_alive<P: wayland_client::Proxy>(proxy: &P) -> InputResult<()> {
         Err(InputError::Simulate("wayland proxy is dead"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(requests: &[PointerRequest]) -> usize {
+        requests
+            .iter()
+            .filter(|&&r| r == PointerRequest::Frame)
+            .count()
+    }
+
+    #[test]
+    fn one_frame_per_logical_event() {
+        assert_eq!(frames(&button_requests(0x110, Direction::Press)), 1);
+        assert_eq!(frames(&button_requests(0x110, Direction::Release)), 1);
+        assert_eq!(frames(&button_requests(0x110, Direction::Click)), 2);
+        assert_eq!(
+            frames(&motion_requests(5, -5, Coordinate::Rel, 1, Some((1920, 1080))).unwrap()),
+            1
+        );
+        assert_eq!(
+            frames(&motion_requests(5, 5, Coordinate::Abs, 1, Some((1920, 1080))).unwrap()),
+            1
+        );
+        assert_eq!(frames(&scroll_requests(3, Axis::Vertical, true, false)), 1);
+        assert_eq!(frames(&scroll_requests(3, Axis::Vertical, true, true)), 2);
+    }
+
+    #[test]
+    fn successive_moves_are_not_merged() {
+        let requests: Vec<_> = (0..100)
+            .flat_map(|i| motion_requests(i, i, Coordinate::Rel, 1, Some((1920, 1080))).unwrap())
+            .collect();
+        assert_eq!(frames(&requests), 100);
+        assert!(requests
+            .chunks(2)
+            .all(|c| matches!(c, [PointerRequest::Motion(..), PointerRequest::Frame])));
+    }
+
+    #[test]
+    fn physical_pixels_are_scaled() {
+        assert_eq!(
+            motion_requests(30, -15, Coordinate::Rel, 2, Some((1920, 1080))).unwrap()[0],
+            PointerRequest::Motion(15.0, -7.5)
+        );
+        assert_eq!(
+            motion_requests(300, 200, Coordinate::Abs, 2, Some((1920, 1080))).unwrap()[0],
+            PointerRequest::MotionAbsolute(150, 100, 960, 540)
+        );
+    }
+
+    #[test]
+    fn absolute_moves_are_relative_to_the_output_size() {
+        // A logical output of 960x540 with a scale of 2
+        assert_eq!(
+            motion_requests(480, 270, Coordinate::Abs, 1, Some((960, 540))).unwrap()[0],
+            PointerRequest::MotionAbsolute(480, 270, 960, 540)
+        );
+        // The same location and size in physical pixels
+        assert_eq!(
+            motion_requests(960, 540, Coordinate::Abs, 2, Some((1920, 1080))).unwrap()[0],
+            PointerRequest::MotionAbsolute(480, 270, 960, 540)
+        );
+        assert!(matches!(
+            motion_requests(10, 10, Coordinate::Abs, 1, None),
+            Err(InputError::Unsupported(_))
+        ));
+        assert!(motion_requests(10, 10, Coordinate::Rel, 1, None).is_ok());
+    }
+
+    #[test]
+    fn timestamps_are_strictly_increasing() {
+        let mut last = None;
+        let mut times = vec![];
+        for elapsed in [5, 5, 5, 6, 20, 20] {
+            let time = next_time(last, elapsed);
+            last = Some(time);
+            times.push(time);
+        }
+        assert_eq!(times, vec![5, 6, 7, 8, 20, 21]);
+        assert_eq!(next_time(None, 0), 0);
+    }
+
+    #[test]
+    fn text_is_uppercase_while_shift_is_held() {
+        assert_eq!(shifted_text("hello", &[]).unwrap(), "hello");
+        assert_eq!(
+            shifted_text("hello ß1", &[Modifier::Shift]).unwrap(),
+            "HELLO ß1"
+        );
+        // Held by both shift keys
+        assert_eq!(
+            shifted_text("é", &[Modifier::Shift, Modifier::Shift]).unwrap(),
+            "É"
+        );
+        assert_eq!(shifted_text("a", &[Modifier::Control]), None);
+        assert_eq!(shifted_text("a", &[Modifier::Shift, Modifier::Mod1]), None);
+    }
+
+    #[test]
+    fn released_char_is_the_pressed_one() {
+        let mut pressed_chars = Vec::new();
+        assert_eq!(
+            pressed_char(&mut pressed_chars, 'a', Direction::Press, true),
+            'A'
+        );
+        // Shift was released before the char
+        assert_eq!(
+            pressed_char(&mut pressed_chars, 'a', Direction::Release, false),
+            'A'
+        );
+        assert!(pressed_chars.is_empty());
+        assert_eq!(
+            pressed_char(&mut pressed_chars, 'a', Direction::Click, true),
+            'A'
+        );
+        assert_eq!(
+            pressed_char(&mut pressed_chars, 'b', Direction::Release, false),
+            'b'
+        );
+        assert!(pressed_chars.is_empty());
+    }
+
+    #[test]
+    fn scroll_discrete_notches() {
+        assert_eq!(
+            scroll_requests(-2, Axis::Vertical, true, true)[1],
+            PointerRequest::AxisDiscrete(wl_pointer::Axis::VerticalScroll, -30.0, -2)
+        );
+        // Without the axis_discrete request, only the continuous value is sent
+        assert_eq!(
+            scroll_requests(-2, Axis::Vertical, false, true)[1],
+            PointerRequest::Axis(wl_pointer::Axis::VerticalScroll, -30.0)
+        );
+    }
+
+    #[test]
+    fn scroll_is_stopped() {
+        assert_eq!(
+            scroll_requests(2, Axis::Horizontal, true, true)[3..],
+            [
+                PointerRequest::AxisStop(wl_pointer::Axis::HorizontalScroll),
+                PointerRequest::Frame
+            ]
+        );
+    }
+
+    #[test]
+    fn track_relative_moves_after_absolute_move() {
+        let size = Some((1920, 1080));
+        assert_eq!(track_location(None, 10, 10, Coordinate::Rel, size), None);
+        let location = track_location(None, 100, 200, Coordinate::Abs, size);
+        assert_eq!(location, Some((100, 200)));
+        let location = track_location(location, -50, 30, Coordinate::Rel, size);
+        assert_eq!(location, Some((50, 230)));
+        let location = track_location(location, -500, 5000, Coordinate::Rel, size);
+        assert_eq!(location, Some((0, 1079)));
+    }
+
+    #[test]
+    fn raw_keycode_out_of_range() {
+        assert!(matches!(evdev_keycode(0), Err(InputError::InvalidInput(_))));
+        assert!(matches!(
+            evdev_keycode(256),
+            Err(InputError::InvalidInput(_))
+        ));
+        assert_eq!(evdev_keycode(8).unwrap(), 0);
+        assert_eq!(evdev_keycode(255).unwrap(), 247);
+    }
+
+    #[test]
+    fn frame_is_sent_last() {
+        for requests in [
+            button_requests(0x110, Direction::Press),
+            motion_requests(5, 5, Coordinate::Abs, 1, Some((1920, 1080))).unwrap(),
+            scroll_requests(-1, Axis::Horizontal, true, false),
+            scroll_requests(-1, Axis::Horizontal, true, true),
+        ] {
+            assert_eq!(requests.last(), Some(&PointerRequest::Frame));
+        }
+    }
+
+    #[test]
+    fn click_at_is_grouped_into_motion_press_and_release() {
+        let mut requests = motion_requests(10, 20, Coordinate::Abs, 1, Some((1920, 1080))).unwrap();
+        requests.extend(button_requests(0x110, Direction::Click));
+        assert_eq!(
+            requests,
+            vec![
+                PointerRequest::MotionAbsolute(10, 20, 1920, 1080),
+                PointerRequest::Frame,
+                PointerRequest::Button(0x110, wl_pointer::ButtonState::Pressed),
+                PointerRequest::Frame,
+                PointerRequest::Button(0x110, wl_pointer::ButtonState::Released),
+                PointerRequest::Frame,
+            ]
+        );
+    }
+}
@@ -1,35 +1,88 @@
 use std::collections::VecDeque;
 use std::convert::TryInto;
+use std::fmt;
 
 use log::{debug, error, trace, warn};
 use x11rb::{
     connection::Connection,
     protocol::{
         randr::ConnectionExt as _,
-        xinput::DeviceUse,
-        xproto::{ConnectionExt as _, GetKeyboardMappingReply, GetModifierMappingReply, Screen},
+        xinput::{DeviceUse, ListInputDevicesReply},
+        xkb::{self, ConnectionExt as _, GetControlsReply},
+        xproto::{
+            Atom, AtomEnum, AutoRepeatMode, ButtonPressEvent, ChangeKeyboardControlAux,
+            ConnectionExt as _, EventMask, GetKeyboardMappingReply, GetModifierMappingReply,
+            GetPropertyReply, InputFocus, KeyButMask, KeyPressEvent, Motion, MotionNotifyEvent,
+            Screen, Window,
+        },
         xtest::ConnectionExt as _,
     },
     rust_connection::{ConnectError, ConnectionError, DefaultStream, ReplyError, RustConnection},
     wrapper::ConnectionExt as _,
 };
 
-use super::keymap::{Bind, KeyMap, Keysym, NO_SYMBOL};
+use super::{
+    keymap::{Bind, KeyMap, Keysym, NO_SYMBOL},
+    scroll_clicks, PointerAcceleration, Stats, X11Device,
+};
 use crate::{
     keycodes::Modifier, Axis, Button, Coordinate, Direction, InputError, InputResult, Key,
-    Keyboard, Mouse, NewConError,
+    Keyboard, Mouse, NewConError, WindowInfo,
 };
 
 type CompositorConnection = RustConnection<DefaultStream>;
 
 pub type Keycode = u8;
 
+#[allow(clippy::struct_excessive_bools)] // Most of the bools are copied from the settings
 pub struct Con {
     connection: CompositorConnection,
     screen: Screen,
     keymap: KeyMap<Keycode>,
     modifiers: Vec<Keycode>,
-    delay: u32, // milliseconds
+    modifier_mapping: Vec<Keycode>, // all keycodes of the eight modifiers
+    delay: u32,                     // milliseconds
+    disable_autorepeat: bool,
+    use_send_event: bool,
+    allow_remap: bool, // map keysyms that are not part of the layout to unused keycodes
+    held_buttons: u16, // mask of the held buttons, needed for the state of sent events
+    pointer_buttons: usize, // number of buttons of the pointer
+    keyboard_device: Option<u8>, // XTEST device of the keyboard, looked up once if not pinned
+    pointer_device: Option<u8>, // XTEST device of the pointer, looked up once if not pinned
+    stats: Stats,
+    batch_keys: bool, // the key events are not synced until the batch of keys ends
+    #[cfg(feature = "clipboard")]
+    dpy_name: Option<String>, // needed to connect the owner of the primary selection
+    #[cfg(feature = "clipboard")]
+    primary_selection: Option<std::thread::JoinHandle<()>>, /* thread that serves the primary
+                       * selection */
+    log_prefix: String, // prefix of the log messages of the instance of Enigo
+}
+
+/// Window that receives the events sent with `send_event` and the position of
+/// the pointer
+struct EventTarget {
+    window: Window,
+    root_x: i16,
+    root_y: i16,
+    event_x: i16,
+    event_y: i16,
+}
+
+impl fmt::Debug for Con {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Con")
+            .field("delay", &self.delay)
+            .field(
+                "screen_size",
+                &(self.screen.width_in_pixels, self.screen.height_in_pixels),
+            )
+            .field("free_keycodes", &self.keymap.free_keycodes())
+            .field("disable_autorepeat", &self.disable_autorepeat)
+            .field("use_send_event", &self.use_send_event)
+            .field("allow_remap", &self.allow_remap)
+            .finish_non_exhaustive()
+    }
 }
 
 impl From<ConnectionError> for NewConError {
@@ -60,11 +113,29 @@ impl Con {
     ///   properly enter all chars
     /// * `dpy_name` - If no `dpy_name` is provided, the value from $DISPLAY is
     ///   used
+    /// * `disable_autorepeat` - Turn off the auto-repeat of the X server for a
+    ///   keycode while it is held
+    /// * `use_send_event` - Send the events to the focused window with
+    ///   `send_event` instead of simulating them with the XTEST extension
+    /// * `keysym_cache_size` - Maximum number of keysyms that are mapped to
+    ///   unused keycodes before the least recently used one gets unmapped
+    /// * `allow_remap` - Map keysyms that are not part of the keyboard layout
+    ///   to unused keycodes. If it is false, only the keys of the layout can be
+    ///   entered
+    /// * `log_prefix` - Prefix of the log messages of the instance of Enigo
     ///
     /// # Errors
     /// TODO
-    pub fn new(dpy_name: &Option<String>, delay: u32) -> Result<Con, NewConError> {
-        debug!("using x11rb");
+    pub fn new(
+        dpy_name: &Option<String>,
+        delay: u32,
+        disable_autorepeat: bool,
+        use_send_event: bool,
+        keysym_cache_size: Option<usize>,
+        allow_remap: bool,
+        log_prefix: &str,
+    ) -> Result<Con, NewConError> {
+        debug!("{log_prefix}using x11rb");
         let (connection, screen_idx) = x11rb::connect(dpy_name.as_deref())?;
         let setup = connection.setup();
         let screen = setup.roots[screen_idx].clone();
@@ -72,8 +143,13 @@ impl Con {
         let max_keycode = setup.max_keycode;
         let (keysyms_per_keycode, keysyms) =
             Self::get_keyboard_mapping(&connection, min_keycode, max_keycode)?; // Check if a mapping is possible
-        let unused_keycodes =
-            Self::unused_keycodes(min_keycode, max_keycode, keysyms_per_keycode, &keysyms); // Check if a mapping is possible
+        let unused_keycodes = Self::unused_keycodes(
+            min_keycode,
+            max_keycode,
+            keysyms_per_keycode,
+            &keysyms,
+            log_prefix,
+        ); // Check if a mapping is possible
 
         if unused_keycodes.is_empty() {
             return Err(NewConError::NoEmptyKeycodes);
@@ -84,20 +160,213 @@ impl Con {
             unused_keycodes,
             keysyms_per_keycode,
             keysyms,
+            keysym_cache_size,
+            log_prefix,
         );
 
         // Get the keycodes of the modifiers
-        let modifiers = Self::find_modifier_keycodes(&connection)?;
+        let (modifiers, modifier_mapping) = Self::find_modifier_keycodes(&connection, log_prefix)?;
+        let pointer_buttons = connection.get_pointer_mapping()?.reply()?.map.len();
+        debug!("{log_prefix}the pointer has {pointer_buttons} buttons");
 
         Ok(Con {
             connection,
             screen,
             keymap,
             modifiers,
+            modifier_mapping,
             delay,
+            disable_autorepeat,
+            use_send_event,
+            allow_remap,
+            held_buttons: 0,
+            pointer_buttons,
+            keyboard_device: None,
+            pointer_device: None,
+            stats: Stats::default(),
+            batch_keys: false,
+            #[cfg(feature = "clipboard")]
+            dpy_name: dpy_name.clone(),
+            #[cfg(feature = "clipboard")]
+            primary_selection: None,
+            log_prefix: log_prefix.to_string(),
         })
     }
 
+    /// Pin the ids of the XTEST devices that are used to send the key and
+    /// pointer events. If an id is `None`, the first device with the usage is
+    /// looked up when it is needed for the first time
+    #[must_use]
+    pub fn with_devices(mut self, keyboard: Option<u8>, pointer: Option<u8>) -> Self {
+        self.keyboard_device = keyboard;
+        self.pointer_device = pointer;
+        self
+    }
+
+    /// Check if the pointer has the button. The X server rejects events of
+    /// buttons that the pointer does not have
+    pub fn supports_button(&self, button: Button) -> bool {
+        has_button(self.pointer_buttons, button)
+    }
+
+    /// List the input devices of the X server
+    pub fn devices(&mut self) -> InputResult<Vec<X11Device>> {
+        let reply = self.list_input_devices()?;
+        Ok(x11_devices(&reply))
+    }
+
+    /// Set the text of the PRIMARY selection, which is pasted with a click of
+    /// the middle mouse button. The selection is owned by a window of a
+    /// separate connection and its requests are answered by a background
+    /// thread until another client takes over the selection. The text is
+    /// offered as `UTF8_STRING` and, if all of its chars are part of Latin-1,
+    /// as `STRING`
+    #[cfg(feature = "clipboard")]
+    pub fn set_primary_selection(&mut self, text: &str) -> InputResult<()> {
+        use x11rb::protocol::{
+            xproto::{CreateWindowAux, PropMode, WindowClass},
+            Event,
+        };
+
+        fn simulate_error<E: fmt::Debug>(log_prefix: &str, e: E) -> InputError {
+            error!("{log_prefix}{e:?}");
+            InputError::Simulate("failed to own the primary selection")
+        }
+
+        let log_prefix = &self.log_prefix;
+        let (connection, screen_idx) =
+            x11rb::connect(self.dpy_name.as_deref()).map_err(|e| simulate_error(log_prefix, e))?;
+        let root = connection.setup().roots[screen_idx].root;
+        let window = connection
+            .generate_id()
+            .map_err(|e| simulate_error(log_prefix, e))?;
+        connection
+            .create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            )
+            .map_err(|e| simulate_error(log_prefix, e))?;
+        let mut atoms = [0; 2];
+        for (atom, name) in atoms.iter_mut().zip([&b"UTF8_STRING"[..], b"TARGETS"]) {
+            *atom = connection
+                .intern_atom(false, name)
+                .map_err(|e| simulate_error(log_prefix, e))?
+                .reply()
+                .map_err(|e| simulate_error(log_prefix, e))?
+                .atom;
+        }
+        let [utf8_string, targets] = atoms;
+
+        // The ICCCM forbids owning a selection with CURRENT_TIME. The timestamp
+        // of the server is taken from the event of a zero-length append to a
+        // property of the window
+        connection
+            .change_property8(
+                PropMode::APPEND,
+                window,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                &[],
+            )
+            .map_err(|e| simulate_error(log_prefix, e))?;
+        connection
+            .flush()
+            .map_err(|e| simulate_error(log_prefix, e))?;
+        let time = loop {
+            if let Event::PropertyNotify(event) = connection
+                .wait_for_event()
+                .map_err(|e| simulate_error(log_prefix, e))?
+            {
+                break event.time;
+            }
+        };
+
+        connection
+            .set_selection_owner(window, u32::from(AtomEnum::PRIMARY), time)
+            .map_err(|e| simulate_error(log_prefix, e))?;
+        let owner = connection
+            .get_selection_owner(u32::from(AtomEnum::PRIMARY))
+            .map_err(|e| simulate_error(log_prefix, e))?
+            .reply()
+            .map_err(|e| simulate_error(log_prefix, e))?
+            .owner;
+        self.stats.roundtrips += 4;
+        if owner != window {
+            return Err(InputError::Simulate(
+                "another client owns the primary selection",
+            ));
+        }
+        debug!("{log_prefix}owning the primary selection");
+
+        // The previous owner lost the selection to the new window, so its
+        // thread stops serving it
+        if let Some(previous) = self.primary_selection.take() {
+            if previous.join().is_err() {
+                error!("{log_prefix}the thread of the previous primary selection panicked");
+            }
+        }
+
+        let text = text.to_string();
+        let log_prefix = log_prefix.clone();
+        self.primary_selection = Some(std::thread::spawn(move || {
+            serve_primary_selection(&connection, &text, utf8_string, targets, &log_prefix);
+        }));
+        Ok(())
+    }
+
+    /// Start a batch of keys. Their events are sent, but the connection is
+    /// only synced once with `end_key_batch`
+    pub fn begin_key_batch(&mut self) {
+        self.batch_keys = true;
+    }
+
+    /// Sync the events of the batch of keys with the X server and end it
+    pub fn end_key_batch(&mut self) -> InputResult<()> {
+        self.batch_keys = false;
+        self.sync()
+    }
+
+    /// Wait until the X server processed all requests
+    fn sync(&mut self) -> InputResult<()> {
+        self.stats.roundtrips += 1;
+        self.connection.sync().map_err(|e| {
+            error!("{}{e}", self.log_prefix);
+            InputError::Simulate("error when syncing with X server using x11rb")
+        })
+    }
+
+    /// Get the number of roundtrips, sent events and changes of the keymap
+    /// since the connection was established or the stats were reset. Each
+    /// change of the keymap is synced with the X server, so it is counted as
+    /// a roundtrip as well
+    pub fn stats(&self) -> Stats {
+        let mut stats = self.keymap.stats();
+        stats.roundtrips = stats.remaps;
+        stats += self.stats;
+        stats
+    }
+
+    /// Reset the stats to zero
+    pub fn reset_stats(&mut self) {
+        self.stats = Stats::default();
+        self.keymap.reset_stats();
+    }
+
+    /// Check if the key can be entered. If remapping is not allowed, only the
+    /// modifiers and the keys of the keyboard layout can be entered
+    pub fn supports_key(&self, key: Key) -> bool {
+        self.allow_remap || Modifier::try_from(key).is_ok() || self.keymap.is_in_layout(key)
+    }
+
     /// Get the delay per keypress in milliseconds
     #[must_use]
     pub fn delay(&self) -> u32 {
@@ -132,97 +401,824 @@ impl Con {
         keycode_max: Keycode,
         keysyms_per_keycode: u8,
         keysyms: &[u32],
+        log_prefix: &str,
     ) -> VecDeque<Keycode> {
         let mut unused_keycodes: VecDeque<Keycode> =
             VecDeque::with_capacity((keycode_max - keycode_min) as usize);
 
         // Split the mapping into the chunks of keysyms that are mapped to each keycode
-        trace!("initial keymap:");
+        trace!("{log_prefix}initial keymap:");
         let keysyms = keysyms.chunks(keysyms_per_keycode as usize);
         for (syms, kc) in keysyms.zip(keycode_min..=keycode_max) {
             // Check if the keycode is unused
             if log::log_enabled!(log::Level::Trace) {
                 let syms_name: Vec<Keysym> = syms.iter().map(|&s| Keysym::from(s)).collect();
-                trace!("{kc}:  {syms_name:?}");
+                trace!("{log_prefix}{kc}:  {syms_name:?}");
             }
 
             if syms.iter().all(|&s| s == NO_SYMBOL.raw()) {
                 unused_keycodes.push_back(kc);
             }
         }
-        debug!("unused keycodes: {unused_keycodes:?}");
+        debug!("{log_prefix}unused keycodes: {unused_keycodes:?}");
         unused_keycodes
     }
 
     /// Find the keycodes that must be used for the modifiers
+    /// Get one keycode for each of the eight modifiers and all keycodes that
+    /// are associated with them
     fn find_modifier_keycodes(
         connection: &CompositorConnection,
-    ) -> Result<Vec<Keycode>, ReplyError> {
+        log_prefix: &str,
+    ) -> Result<(Vec<Keycode>, Vec<Keycode>), ReplyError> {
         let modifier_reply = connection.get_modifier_mapping()?.reply()?;
         let keycodes_per_modifier = modifier_reply.keycodes_per_modifier() as usize;
         let GetModifierMappingReply {
             keycodes: modifiers,
             ..
         } = modifier_reply;
-        trace!("keycodes per modifier: {keycodes_per_modifier:?}");
-        trace!("the keycodes associated with the modifiers are:\n{modifiers:?}");
+        trace!("{log_prefix}keycodes per modifier: {keycodes_per_modifier:?}");
+        trace!("{log_prefix}the keycodes associated with the modifiers are:\n{modifiers:?}");
 
-        debug!("modifier mapping:");
+        debug!("{log_prefix}modifier mapping:");
         let mut modifier_keycodes = vec![0; 8];
         'mods: for (mod_no, mod_keycode) in modifier_keycodes.iter_mut().enumerate().take(8) {
             let start = mod_no * keycodes_per_modifier;
             for &keycode in &modifiers[start..start + keycodes_per_modifier] {
                 if keycode != 0 {
                     // Found one keycode that can be used for this modifier
-                    debug!("mod_no: {mod_no} -> {keycode}");
+                    debug!("{log_prefix}mod_no: {mod_no} -> {keycode}");
                     *mod_keycode = keycode;
                     continue 'mods;
                 }
             }
-            warn!("modifier_no: {mod_no} is unmapped");
+            warn!("{log_prefix}modifier_no: {mod_no} is unmapped");
         }
 
-        Ok(modifier_keycodes)
+        Ok((modifier_keycodes, modifiers))
     }
 
     // Get the device id of the first device that is found which has the same usage
     // as the input parameter
-    fn device_id(&self, usage: DeviceUse) -> InputResult<u8> {
-        x11rb::protocol::xinput::list_input_devices(&self.connection)
+    fn device_id(&mut self, usage: DeviceUse) -> InputResult<u8> {
+        let cached = if usage == DeviceUse::IS_X_KEYBOARD {
+            self.keyboard_device
+        } else {
+            self.pointer_device
+        };
+        if let Some(id) = cached {
+            return Ok(id);
+        }
+        let id = self
+            .list_input_devices()?
+            .devices
+            .iter()
+            .find(|d| d.device_use == usage)
+            .map(|d| d.device_id)
+            .ok_or(InputError::Simulate(
+                "no input device with the usage was found",
+            ))?;
+        debug!(
+            "{}using the input device {id} for {usage:?}",
+            self.log_prefix
+        );
+        if usage == DeviceUse::IS_X_KEYBOARD {
+            self.keyboard_device = Some(id);
+        } else {
+            self.pointer_device = Some(id);
+        }
+        Ok(id)
+    }
+
+    fn list_input_devices(&mut self) -> InputResult<ListInputDevicesReply> {
+        let reply = x11rb::protocol::xinput::list_input_devices(&self.connection)
             .map_err(|e| {
-                error!("{e}");
-                InputError::Simulate("error when listing input devices with x11rb: {e:?}")
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when listing input devices with x11rb")
             })?
             .reply()
             .map_err(|e| {
-                error!("{e}");
-                InputError::Simulate(
-                    "error with the reply from listing input devices with x11rb: {e:?}",
-                )
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply from listing input devices with x11rb")
+            })?;
+        self.stats.roundtrips += 1;
+        Ok(reply)
+    }
+
+    /// Get the title, class and process id of the window that currently has
+    /// the focus
+    pub fn focused_window(&self) -> InputResult<WindowInfo> {
+        let net_active_window = self.atom(b"_NET_ACTIVE_WINDOW")?;
+        let Some(window) = self
+            .property(self.screen.root, net_active_window, AtomEnum::WINDOW)?
+            .value32()
+            .and_then(|mut value| value.next())
+            .filter(|&window| window != x11rb::NONE)
+        else {
+            return Err(InputError::Simulate("there is no active window"));
+        };
+        debug!("{}the active window is {window}", self.log_prefix);
+
+        // Prefer the UTF-8 encoded title and fall back to WM_NAME
+        let net_wm_name = self.atom(b"_NET_WM_NAME")?;
+        let utf8_string = self.atom(b"UTF8_STRING")?;
+        let mut title = self.property(window, net_wm_name, utf8_string)?.value;
+        if title.is_empty() {
+            title = self
+                .property(window, AtomEnum::WM_NAME.into(), AtomEnum::ANY)?
+                .value;
+        }
+        let title = String::from_utf8_lossy(&title).into_owned();
+
+        // WM_CLASS contains the instance name and the class name separated by null
+        // bytes
+        let class = self
+            .property(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING)?
+            .value;
+        let class = class
+            .split(|&b| b == 0)
+            .nth(1)
+            .filter(|class| !class.is_empty())
+            .map(|class| String::from_utf8_lossy(class).into_owned());
+
+        let net_wm_pid = self.atom(b"_NET_WM_PID")?;
+        let pid = self
+            .property(window, net_wm_pid, AtomEnum::CARDINAL)?
+            .value32()
+            .and_then(|mut value| value.next());
+
+        Ok(WindowInfo {
+            title,
+            class,
+            pid,
+            id: window.into(),
+        })
+    }
+
+    /// Get the keycode of the key and the keycodes of the modifiers that
+    /// select the level
+    pub fn level_keycodes(&self, key: Key, level: u8) -> InputResult<(Keycode, Vec<Keycode>)> {
+        self.keymap.level_keycodes(key, level)
+    }
+
+    /// Get the acceleration of the pointer
+    pub fn pointer_acceleration(&self) -> InputResult<PointerAcceleration> {
+        let control = self
+            .connection
+            .get_pointer_control()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting get_pointer_control with x11rb")
             })?
-            .devices
-            .iter()
-            .find(|d| d.device_use == usage)
-            .map_or_else(
-                || {
-                    Err(InputError::Simulate(
-                        "error with the reply from listing input devices with x11rb: {e:?}",
-                    ))
-                },
-                |d| Ok(d.device_id),
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of get_pointer_control with x11rb")
+            })?;
+        Ok(PointerAcceleration {
+            numerator: control.acceleration_numerator,
+            denominator: control.acceleration_denominator,
+            threshold: control.threshold,
+        })
+    }
+
+    /// Get the modifiers that are held according to the X server
+    pub fn physical_modifiers(&self) -> InputResult<Vec<Key>> {
+        let reply = self
+            .connection
+            .query_pointer(self.screen.root)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting query_pointer with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of query_pointer with x11rb")
+            })?;
+        Ok(mask_to_modifiers(reply.mask))
+    }
+
+    /// Set the acceleration of the pointer
+    pub fn set_pointer_acceleration(
+        &mut self,
+        acceleration: PointerAcceleration,
+    ) -> InputResult<()> {
+        let PointerAcceleration {
+            numerator,
+            denominator,
+            threshold,
+        } = acceleration;
+        let (Ok(numerator), Ok(denominator), Ok(threshold)) = (
+            i16::try_from(numerator),
+            i16::try_from(denominator),
+            i16::try_from(threshold),
+        ) else {
+            return Err(InputError::InvalidInput(
+                "the values of the pointer acceleration cannot be larger than i16::MAX",
+            ));
+        };
+        if denominator == 0 {
+            return Err(InputError::InvalidInput(
+                "the denominator of the pointer acceleration cannot be zero",
+            ));
+        }
+        self.stats.roundtrips += 1;
+        self.connection
+            .change_pointer_control(numerator, denominator, threshold, true, true)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting change_pointer_control with x11rb")
+            })?
+            .check()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when changing the pointer control with x11rb")
+            })
+    }
+
+    /// Get the controls of the core keyboard from the XKB extension
+    fn xkb_controls(&self) -> InputResult<GetControlsReply> {
+        // The extension has to be initialized before any other request of it
+        self.connection
+            .xkb_use_extension(1, 0)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting xkb_use_extension with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of xkb_use_extension with x11rb")
+            })?;
+        self.connection
+            .xkb_get_controls(xkb::ID::USE_CORE_KBD.into())
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting xkb_get_controls with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of xkb_get_controls with x11rb")
+            })
+    }
+
+    /// Get the delay in milliseconds before a held key starts to repeat and
+    /// the number of repetitions per second
+    pub fn autorepeat_rate(&mut self) -> InputResult<(u32, u32)> {
+        let controls = self.xkb_controls()?;
+        // Getting the controls needs two roundtrips
+        self.stats.roundtrips += 2;
+        let rate_hz = match controls.repeat_interval {
+            0 => 0,
+            interval => 1000 / u32::from(interval),
+        };
+        Ok((controls.repeat_delay.into(), rate_hz))
+    }
+
+    /// Set the delay in milliseconds before a held key starts to repeat and
+    /// the number of repetitions per second
+    pub fn set_autorepeat_rate(&mut self, delay_ms: u32, rate_hz: u32) -> InputResult<()> {
+        let Ok(repeat_delay) = u16::try_from(delay_ms) else {
+            return Err(InputError::InvalidInput(
+                "the delay of the auto-repeat cannot be larger than u16::MAX",
+            ));
+        };
+        if rate_hz == 0 || rate_hz > 1000 {
+            return Err(InputError::InvalidInput(
+                "the rate of the auto-repeat has to be between 1 and 1000 Hz",
+            ));
+        }
+        // It is okay to unwrap here, because the rate is at least 1 Hz
+        let repeat_interval = u16::try_from(1000 / rate_hz).unwrap();
+        let c = self.xkb_controls()?;
+        // Getting the controls needs two roundtrips and the checked request
+        // to change them another one
+        self.stats.roundtrips += 3;
+        // Only the controls in the mask get changed, all other values are ignored
+        self.connection
+            .xkb_set_controls(
+                xkb::ID::USE_CORE_KBD.into(),
+                0u8.into(),
+                0u8.into(),
+                0u8.into(),
+                0u8.into(),
+                0u8.into(),
+                0u8.into(),
+                0u8.into(),
+                0u8.into(),
+                c.mouse_keys_dflt_btn,
+                c.groups_wrap,
+                c.access_x_option,
+                0u8.into(),
+                0u8.into(),
+                u32::from(xkb::BoolCtrl::REPEAT_KEYS).into(),
+                repeat_delay,
+                repeat_interval,
+                c.slow_keys_delay,
+                c.debounce_delay,
+                c.mouse_keys_delay,
+                c.mouse_keys_interval,
+                c.mouse_keys_time_to_max,
+                c.mouse_keys_max_speed,
+                c.mouse_keys_curve,
+                c.access_x_timeout,
+                c.access_x_timeout_mask,
+                c.access_x_timeout_values,
+                c.access_x_timeout_options_mask,
+                c.access_x_timeout_options_values,
+                &c.per_key_repeat,
             )
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting xkb_set_controls with x11rb")
+            })?
+            .check()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when changing the auto-repeat rate with x11rb")
+            })
+    }
+
+    /// Get the center of the window in root window coordinates
+    pub fn window_center(&self, window: u32) -> InputResult<(i32, i32)> {
+        let geometry = self
+            .connection
+            .get_geometry(window)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting get_geometry with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::InvalidInput("the window id is invalid")
+            })?;
+        // The geometry is relative to the parent window, so the origin of the
+        // window has to be translated to the root window
+        let (x, y) = self.window_to_root(window, 0, 0)?;
+        Ok((
+            x + i32::from(geometry.width) / 2,
+            y + i32::from(geometry.height) / 2,
+        ))
+    }
+
+    /// Translate the coordinates relative to the window to root window
+    /// coordinates
+    pub fn window_to_root(&self, window: u32, x: i16, y: i16) -> InputResult<(i32, i32)> {
+        let translated = self
+            .connection
+            .translate_coordinates(window, self.screen.root, x, y)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting translate_coordinates with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::InvalidInput("the window id is invalid")
+            })?;
+        Ok((i32::from(translated.dst_x), i32::from(translated.dst_y)))
+    }
+
+    /// Find the window that has the focus and the position of the pointer
+    /// relative to it. If a position is provided, it is used instead of the
+    /// current position of the pointer
+    fn event_target(&self, position: Option<(i16, i16)>) -> InputResult<EventTarget> {
+        let focus = self
+            .connection
+            .get_input_focus()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting get_input_focus with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of get_input_focus with x11rb")
+            })?
+            .focus;
+        let pointer = self
+            .connection
+            .query_pointer(self.screen.root)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting query_pointer with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of query_pointer with x11rb")
+            })?;
+
+        // If the focus follows the pointer, the window below the pointer receives the
+        // events
+        let window = if focus == x11rb::NONE || focus == u32::from(InputFocus::POINTER_ROOT) {
+            if pointer.child == x11rb::NONE {
+                self.screen.root
+            } else {
+                pointer.child
+            }
+        } else {
+            focus
+        };
+        let (root_x, root_y) = position.unwrap_or((pointer.root_x, pointer.root_y));
+        let translated = self
+            .connection
+            .translate_coordinates(self.screen.root, window, root_x, root_y)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting translate_coordinates with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of translate_coordinates with x11rb")
+            })?;
+
+        Ok(EventTarget {
+            window,
+            root_x,
+            root_y,
+            event_x: translated.dst_x,
+            event_y: translated.dst_y,
+        })
+    }
+
+    /// State of the modifiers and the mouse buttons right before the event.
+    /// Events sent with `send_event` do not go through the modifier tracking
+    /// of the server, so the state has to be set explicitly
+    fn event_state(&self) -> KeyButMask {
+        KeyButMask::from(
+            self.held_buttons | modifier_mask(&self.modifier_mapping, &self.keymap.held_keycodes),
+        )
+    }
+
+    fn send_event_to(
+        &self,
+        window: Window,
+        event_mask: EventMask,
+        event: impl Into<[u8; 32]>,
+    ) -> InputResult<()> {
+        self.connection
+            .send_event(true, window, event_mask, event)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when using send_event with x11rb")
+            })?;
+        Ok(())
+    }
+
+    /// Send a key event to the focused window with `send_event`
+    fn send_key_event(&self, keycode: Keycode, direction: Direction) -> InputResult<()> {
+        let (response_type, event_mask) = if direction == Direction::Release {
+            (
+                x11rb::protocol::xproto::KEY_RELEASE_EVENT,
+                EventMask::KEY_RELEASE,
+            )
+        } else {
+            (
+                x11rb::protocol::xproto::KEY_PRESS_EVENT,
+                EventMask::KEY_PRESS,
+            )
+        };
+        let target = self.event_target(None)?;
+        debug!(
+            "{}send_event with keycode {keycode} to window {}",
+            self.log_prefix, target.window
+        );
+        let event = KeyPressEvent {
+            response_type,
+            detail: keycode,
+            sequence: 0,
+            time: x11rb::CURRENT_TIME,
+            root: self.screen.root,
+            event: target.window,
+            child: x11rb::NONE,
+            root_x: target.root_x,
+            root_y: target.root_y,
+            event_x: target.event_x,
+            event_y: target.event_y,
+            state: self.event_state(),
+            same_screen: true,
+        };
+        self.send_event_to(target.window, event_mask, event)
+    }
+
+    /// Send a button event to the focused window with `send_event`
+    fn send_button_event(&mut self, button: u8, direction: Direction) -> InputResult<()> {
+        let (response_type, event_mask) = if direction == Direction::Release {
+            (
+                x11rb::protocol::xproto::BUTTON_RELEASE_EVENT,
+                EventMask::BUTTON_RELEASE,
+            )
+        } else {
+            (
+                x11rb::protocol::xproto::BUTTON_PRESS_EVENT,
+                EventMask::BUTTON_PRESS,
+            )
+        };
+        let target = self.event_target(None)?;
+        debug!(
+            "{}send_event with button {button} to window {}",
+            self.log_prefix, target.window
+        );
+        let event = ButtonPressEvent {
+            response_type,
+            detail: button,
+            sequence: 0,
+            time: x11rb::CURRENT_TIME,
+            root: self.screen.root,
+            event: target.window,
+            child: x11rb::NONE,
+            root_x: target.root_x,
+            root_y: target.root_y,
+            event_x: target.event_x,
+            event_y: target.event_y,
+            state: self.event_state(),
+            same_screen: true,
+        };
+        self.send_event_to(target.window, event_mask, event)?;
+
+        // Only the first five buttons are part of the state
+        if (1..=5).contains(&button) {
+            let button_mask = 1 << (7 + button);
+            if direction == Direction::Release {
+                self.held_buttons &= !button_mask;
+            } else {
+                self.held_buttons |= button_mask;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a motion event to the focused window with `send_event`. This does
+    /// not move the actual pointer
+    fn send_motion_event(&self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        let (x, y) = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => {
+                let (current_x, current_y) = self.location()?;
+                (current_x + x, current_y + y)
+            }
+        };
+        let (Ok(root_x), Ok(root_y)) = (x.try_into(), y.try_into()) else {
+            return Err(InputError::InvalidInput("the coordinates must fit in i16"));
+        };
+        let target = self.event_target(Some((root_x, root_y)))?;
+        debug!(
+            "{}send_event with x {root_x}, y {root_y} to window {}",
+            self.log_prefix, target.window
+        );
+        let event = MotionNotifyEvent {
+            response_type: x11rb::protocol::xproto::MOTION_NOTIFY_EVENT,
+            detail: Motion::NORMAL,
+            sequence: 0,
+            time: x11rb::CURRENT_TIME,
+            root: self.screen.root,
+            event: target.window,
+            child: x11rb::NONE,
+            root_x: target.root_x,
+            root_y: target.root_y,
+            event_x: target.event_x,
+            event_y: target.event_y,
+            state: self.event_state(),
+            same_screen: true,
+        };
+        self.send_event_to(target.window, EventMask::POINTER_MOTION, event)
+    }
+
+    /// Get the atom with the given name
+    fn atom(&self, name: &[u8]) -> InputResult<Atom> {
+        let reply = self
+            .connection
+            .intern_atom(false, name)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting intern_atom with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of intern_atom with x11rb")
+            })?;
+        Ok(reply.atom)
+    }
+
+    /// Get the whole value of the property of a window
+    fn property(
+        &self,
+        window: Window,
+        property: Atom,
+        type_: impl Into<Atom>,
+    ) -> InputResult<GetPropertyReply> {
+        self.connection
+            .get_property(false, window, property, type_, 0, u32::MAX)
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error when requesting get_property with x11rb")
+            })?
+            .reply()
+            .map_err(|e| {
+                error!("{}{e}", self.log_prefix);
+                InputError::Simulate("error with the reply of get_property with x11rb")
+            })
+    }
+
+    /// Change the auto-repeat mode of a single keycode
+    fn set_autorepeat(&self, keycode: Keycode, mode: AutoRepeatMode) -> InputResult<()> {
+        debug!(
+            "{}set the auto-repeat mode of keycode {keycode} to {mode:?}",
+            self.log_prefix
+        );
+        let aux = ChangeKeyboardControlAux::new()
+            .key(u32::from(keycode))
+            .auto_repeat_mode(mode);
+        self.connection.change_keyboard_control(&aux).map_err(|e| {
+            error!("{}{e}", self.log_prefix);
+            InputError::Simulate("error when changing the auto-repeat mode with x11rb")
+        })?;
+        Ok(())
     }
 }
 
+/// Number of the button in the X protocol
+fn button_number(button: Button) -> u8 {
+    match button {
+        Button::Left => 1,
+        Button::Middle => 2,
+        Button::Right => 3,
+        Button::ScrollUp => 4,
+        Button::ScrollDown => 5,
+        Button::ScrollLeft => 6,
+        Button::ScrollRight => 7,
+        Button::Back => 8,
+        Button::Forward => 9,
+    }
+}
+
+/// Check if a pointer with the number of buttons has the button
+fn has_button(pointer_buttons: usize, button: Button) -> bool {
+    usize::from(button_number(button)) <= pointer_buttons
+}
+
+/// Convert the reply of listing the input devices. The names are listed in the
+/// same order as the devices
+fn x11_devices(reply: &ListInputDevicesReply) -> Vec<X11Device> {
+    reply
+        .devices
+        .iter()
+        .zip(&reply.names)
+        .map(|(device, name)| X11Device {
+            id: device.device_id,
+            name: String::from_utf8_lossy(&name.name).into_owned(),
+            is_keyboard: matches!(
+                device.device_use,
+                DeviceUse::IS_X_KEYBOARD | DeviceUse::IS_X_EXTENSION_KEYBOARD
+            ),
+            is_pointer: matches!(
+                device.device_use,
+                DeviceUse::IS_X_POINTER | DeviceUse::IS_X_EXTENSION_POINTER
+            ),
+        })
+        .collect()
+}
+
+/// Answer the requests for the PRIMARY selection until another client takes it
+/// over
+#[cfg(feature = "clipboard")]
+fn serve_primary_selection(
+    connection: &CompositorConnection,
+    text: &str,
+    utf8_string: Atom,
+    targets: Atom,
+    log_prefix: &str,
+) {
+    use x11rb::protocol::{
+        xproto::{PropMode, SelectionNotifyEvent, SELECTION_NOTIFY_EVENT},
+        Event,
+    };
+
+    let string = u32::from(AtomEnum::STRING);
+    // STRING is encoded in Latin-1, so it is only offered if the text can be
+    // encoded
+    let latin1 = latin1(text);
+    let mut offered = vec![targets, utf8_string];
+    if latin1.is_some() {
+        offered.push(string);
+    }
+
+    loop {
+        let event = match connection.wait_for_event() {
+            Ok(event) => event,
+            Err(e) => {
+                error!("{log_prefix}stopped serving the primary selection: {e:?}");
+                return;
+            }
+        };
+        match event {
+            Event::SelectionClear(_) => {
+                debug!("{log_prefix}another client took over the primary selection");
+                return;
+            }
+            Event::SelectionRequest(request) => {
+                // Obsolete clients do not specify a property
+                let property = if request.property == x11rb::NONE {
+                    request.target
+                } else {
+                    request.property
+                };
+                let value = if request.target == utf8_string {
+                    Some(text.as_bytes())
+                } else if request.target == string {
+                    latin1.as_deref()
+                } else {
+                    None
+                };
+                // None if the target is not supported
+                let changed = if request.target == targets {
+                    Some(connection.change_property32(
+                        PropMode::REPLACE,
+                        request.requestor,
+                        property,
+                        AtomEnum::ATOM,
+                        &offered,
+                    ))
+                } else if let Some(value) = value {
+                    Some(connection.change_property8(
+                        PropMode::REPLACE,
+                        request.requestor,
+                        property,
+                        request.target,
+                        value,
+                    ))
+                } else {
+                    debug!("{log_prefix}the target {} is not supported", request.target);
+                    None
+                };
+                if let Some(Err(e)) = &changed {
+                    error!("{log_prefix}unable to store the primary selection: {e:?}");
+                }
+                let notify = SelectionNotifyEvent {
+                    response_type: SELECTION_NOTIFY_EVENT,
+                    sequence: 0,
+                    time: request.time,
+                    requestor: request.requestor,
+                    selection: request.selection,
+                    target: request.target,
+                    // The request is refused by not setting a property
+                    property: if matches!(changed, Some(Ok(_))) {
+                        property
+                    } else {
+                        x11rb::NONE
+                    },
+                };
+                if let Err(e) = connection
+                    .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+                    .map(|_| ())
+                    .and_then(|()| connection.flush())
+                {
+                    error!(
+                        "{log_prefix}unable to answer the request for the primary selection: {e:?}"
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Encode the text in Latin-1, which is the encoding of the `STRING` target.
+/// `None` is returned if a char is not part of Latin-1
+#[cfg(feature = "clipboard")]
+fn latin1(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
 impl Drop for Con {
     fn drop(&mut self) {
         // Map all previously mapped keycodes to the NoSymbol keysym to revert all
         // changes
-        debug!("x11rb connection was dropped");
+        debug!("{}x11rb connection was dropped", self.log_prefix);
+        // Restore the auto-repeat of the keycodes that are still held
+        if self.disable_autorepeat {
+            for &keycode in &self.keymap.held_keycodes {
+                if let Err(e) = self.set_autorepeat(keycode, AutoRepeatMode::DEFAULT) {
+                    error!(
+                        "{}unable to restore the auto-repeat of keycode {keycode:?}. {e:?}",
+                        self.log_prefix
+                    );
+                }
+            }
+        }
         for &keycode in self.keymap.additionally_mapped.values() {
             match self.connection.bind_key(keycode, NO_SYMBOL) {
-                Ok(()) => debug!("unmapped keycode {keycode:?}"),
-                Err(e) => error!("unable to unmap keycode {keycode:?}. {e:?}"),
+                Ok(()) => debug!("{}unmapped keycode {keycode:?}", self.log_prefix),
+                Err(e) => error!(
+                    "{}unable to unmap keycode {keycode:?}. {e:?}",
+                    self.log_prefix
+                ),
             };
         }
     }
@@ -243,7 +1239,10 @@ impl Bind<Keycode> for CompositorConnection {
 
 impl Keyboard for Con {
     fn fast_text(&mut self, _text: &str) -> InputResult<Option<()>> {
-        warn!("fast text entry is not yet implemented with x11rb");
+        warn!(
+            "{}fast text entry is not yet implemented with x11rb",
+            self.log_prefix
+        );
         // TODO: Add fast method
         // xdotools can do it, so it is possible
         Ok(None)
@@ -254,11 +1253,12 @@ impl Keyboard for Con {
         let keycode: u16 = match Modifier::try_from(key) {
             // If it is a modifier, the already mapped keycode must be used
             Ok(modifier) => {
-                debug!("it is a modifier: {modifier:?}");
+                debug!("{}it is a modifier: {modifier:?}", self.log_prefix);
                 self.modifiers[modifier.no()].into()
             }
             // All regular keys might have to get mapped
-            _ => self.keymap.key_to_keycode(&self.connection, key)?.into(),
+            _ if self.allow_remap => self.keymap.key_to_keycode(&self.connection, key)?.into(),
+            _ => self.keymap.key_to_layout_keycode(key)?.into(),
         };
 
         self.raw(keycode, direction)
@@ -277,25 +1277,35 @@ impl Keyboard for Con {
         let deviceid = self.device_id(DeviceUse::IS_X_KEYBOARD)?;
 
         debug!(
-            "xtest_fake_input with keycode {}, deviceid {}, delay {}",
-            keycode, deviceid, time
+            "{}xtest_fake_input with keycode {}, deviceid {}, delay {}",
+            self.log_prefix, keycode, deviceid, time
         );
+        // The X server would start to repeat the key if it is held long enough. A
+        // click is released right away so there is no need to change anything
+        if self.disable_autorepeat && direction == Direction::Press {
+            self.set_autorepeat(keycode, AutoRepeatMode::OFF)?;
+        }
         if direction == Direction::Press || direction == Direction::Click {
-            self.connection
-                .xtest_fake_input(
-                    x11rb::protocol::xproto::KEY_PRESS_EVENT,
-                    keycode,
-                    time,
-                    root,
-                    root_x,
-                    root_y,
-                    deviceid,
-                )
-                .map_err(|e| {
-                    error!("{e}");
-                    InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
-                })?;
-            trace!("press");
+            if self.use_send_event {
+                self.send_key_event(keycode, Direction::Press)?;
+            } else {
+                self.connection
+                    .xtest_fake_input(
+                        x11rb::protocol::xproto::KEY_PRESS_EVENT,
+                        keycode,
+                        time,
+                        root,
+                        root_x,
+                        root_y,
+                        deviceid,
+                    )
+                    .map_err(|e| {
+                        error!("{}{e}", self.log_prefix);
+                        InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
+                    })?;
+            }
+            self.stats.events_sent += 1;
+            trace!("{}press", self.log_prefix);
         }
 
         // TODO: Check if we need to update the delays again
@@ -303,28 +1313,34 @@ impl Keyboard for Con {
         // let time = self.keymap.pending_delays();
 
         if direction == Direction::Release || direction == Direction::Click {
-            self.connection
-                .xtest_fake_input(
-                    x11rb::protocol::xproto::KEY_RELEASE_EVENT,
-                    keycode,
-                    time, // TODO: Check if there needs to be a delay here
-                    root,
-                    root_x,
-                    root_y,
-                    deviceid,
-                )
-                .map_err(|e| {
-                    error!("{e}");
-                    InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
-                })?;
-            trace!("released");
+            if self.use_send_event {
+                self.send_key_event(keycode, Direction::Release)?;
+            } else {
+                self.connection
+                    .xtest_fake_input(
+                        x11rb::protocol::xproto::KEY_RELEASE_EVENT,
+                        keycode,
+                        time, // TODO: Check if there needs to be a delay here
+                        root,
+                        root_x,
+                        root_y,
+                        deviceid,
+                    )
+                    .map_err(|e| {
+                        error!("{}{e}", self.log_prefix);
+                        InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
+                    })?;
+            }
+            self.stats.events_sent += 1;
+            trace!("{}released", self.log_prefix);
+        }
+        if self.disable_autorepeat && direction == Direction::Release {
+            self.set_autorepeat(keycode, AutoRepeatMode::DEFAULT)?;
         }
 
-        self.connection.sync()
-            .map_err(|e| {
-                error!("{e}");
-                InputError::Simulate("error when syncing with X server using x11rb after the keyboard mapping was changed: {e:?}")
-            })?;
+        if !self.batch_keys {
+            self.sync()?;
+        }
 
         // Let the keymap know that the key was held/no longer held
         // This is important to avoid unmapping held keys
@@ -336,17 +1352,12 @@ impl Keyboard for Con {
 
 impl Mouse for Con {
     fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
-        let detail = match button {
-            Button::Left => 1,
-            Button::Middle => 2,
-            Button::Right => 3,
-            Button::ScrollUp => 4,
-            Button::ScrollDown => 5,
-            Button::ScrollLeft => 6,
-            Button::ScrollRight => 7,
-            Button::Back => 8,
-            Button::Forward => 9,
-        };
+        if !self.supports_button(button) {
+            return Err(InputError::Unsupported(
+                "the pointer does not have the button",
+            ));
+        }
+        let detail = button_number(button);
         let time = self.delay;
         let root = self.screen.root;
         let root_x = 0;
@@ -354,56 +1365,68 @@ impl Mouse for Con {
         let deviceid = self.device_id(DeviceUse::IS_X_POINTER)?;
 
         debug!(
-            "xtest_fake_input with button {}, deviceid {}, delay {}",
-            detail, deviceid, time
+            "{}xtest_fake_input with button {}, deviceid {}, delay {}",
+            self.log_prefix, detail, deviceid, time
         );
         if direction == Direction::Press || direction == Direction::Click {
-            self.connection
-                .xtest_fake_input(
-                    x11rb::protocol::xproto::BUTTON_PRESS_EVENT,
-                    detail,
-                    time,
-                    root,
-                    root_x,
-                    root_y,
-                    deviceid,
-                )
-                .map_err(|e| {
-                    error!("{e}");
-                    InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
-                })?;
+            if self.use_send_event {
+                self.send_button_event(detail, Direction::Press)?;
+            } else {
+                self.connection
+                    .xtest_fake_input(
+                        x11rb::protocol::xproto::BUTTON_PRESS_EVENT,
+                        detail,
+                        time,
+                        root,
+                        root_x,
+                        root_y,
+                        deviceid,
+                    )
+                    .map_err(|e| {
+                        error!("{}{e}", self.log_prefix);
+                        InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
+                    })?;
+            }
+            self.stats.events_sent += 1;
         }
         if direction == Direction::Release || direction == Direction::Click {
-            // Add a delay for the release part of a click
-            // TODO: Maybe calculate here if a delay is needed as well
-            if direction == Direction::Click {
-                // time = DEFAULT_DELAY;
+            // The gap between the press and release of a click is added by
+            // Enigo::button if it was configured
+            if self.use_send_event {
+                self.send_button_event(detail, Direction::Release)?;
+            } else {
+                self.connection
+                    .xtest_fake_input(
+                        x11rb::protocol::xproto::BUTTON_RELEASE_EVENT,
+                        detail,
+                        time,
+                        root,
+                        root_x,
+                        root_y,
+                        deviceid,
+                    )
+                    .map_err(|e| {
+                        error!("{}{e}", self.log_prefix);
+                        InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
+                    })?;
             }
-
-            self.connection
-                .xtest_fake_input(
-                    x11rb::protocol::xproto::BUTTON_RELEASE_EVENT,
-                    detail,
-                    time,
-                    root,
-                    root_x,
-                    root_y,
-                    deviceid,
-                )
-                .map_err(|e| {
-                    error!("{e}");
-                    InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
-                })?;
+            self.stats.events_sent += 1;
         }
+        self.stats.roundtrips += 1;
         self.connection.sync()
             .map_err(|e| {
-                error!("{e}");
+                error!("{}{e}", self.log_prefix);
                 InputError::Simulate("error when syncing with X server using x11rb after the keyboard mapping was changed: {e:?}")
             })?;
         Ok(())
     }
 
     fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        if self.use_send_event {
+            self.send_motion_event(x, y, coordinate)?;
+            self.stats.events_sent += 1;
+            return Ok(());
+        }
         let type_ = x11rb::protocol::xproto::MOTION_NOTIFY_EVENT;
         let detail = match coordinate {
             Coordinate::Rel => 1,
@@ -425,28 +1448,31 @@ impl Mouse for Con {
         let deviceid = self.device_id(DeviceUse::IS_X_POINTER)?;
 
         debug!(
-            "xtest_fake_input with coordinate {}, deviceid {}, x {}, y {}, delay {}",
-            detail, deviceid, root_x, root_y, time
+            "{}xtest_fake_input with coordinate {}, deviceid {}, x {}, y {}, delay {}",
+            self.log_prefix, detail, deviceid, root_x, root_y, time
         );
 
         self.connection
             .xtest_fake_input(type_, detail, time, root, root_x, root_y, deviceid) // TODO: Check if using x11rb::protocol::xproto::warp_pointer would be better
             .map_err(|e| {
-                error!("{e}");
+                error!("{}{e}", self.log_prefix);
                 InputError::Simulate("error when using xtest_fake_input with x11rb: {e:?}")
             })?;
-        self.connection.sync()
-            .map_err(|e| {
-                error!("{e}");
-                InputError::Simulate("error when syncing with X server using x11rb after the keyboard mapping was changed: {e:?}")
-            })?;
+        self.stats.events_sent += 1;
+        self.stats.roundtrips += 1;
+        // The X server processes the fake input before it answers the sync, so
+        // the pointer was moved once this returns and `location` is up to date
+        self.connection.sync().map_err(|e| {
+            error!("{}{e}", self.log_prefix);
+            InputError::Simulate(
+                "error when syncing with X server using x11rb after moving the mouse: {e:?}",
+            )
+        })?;
         Ok(())
     }
 
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
-        let mut length = length;
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
         let button = if length < 0 {
-            length = -length;
             match axis {
                 Axis::Horizontal => Button::ScrollLeft,
                 Axis::Vertical => Button::ScrollUp,
@@ -457,10 +1483,11 @@ impl Mouse for Con {
                 Axis::Vertical => Button::ScrollDown,
             }
         };
-        for _ in 0..length {
+        let clicks = scroll_clicks(length, &self.log_prefix);
+        for _ in 0..clicks {
             self.button(button, Direction::Click)?;
         }
-        Ok(())
+        Ok(clicks)
     }
 
     fn main_display(&self) -> InputResult<(i32, i32)> {
@@ -468,14 +1495,14 @@ impl Mouse for Con {
             .connection
             .randr_get_screen_resources(self.screen.root)
             .map_err(|e| {
-                error!("{e}");
+                error!("{}{e}", self.log_prefix);
                 InputError::Simulate(
                     "error when requesting randr_get_screen_resources with x11rb: {e:?}",
                 )
             })?
             .reply()
             .map_err(|e| {
-                error!("{e}");
+                error!("{}{e}", self.log_prefix);
                 InputError::Simulate(
                     "error with the reply of randr_get_screen_resources with x11rb: {e:?}",
                 )
@@ -490,14 +1517,154 @@ impl Mouse for Con {
             .connection
             .query_pointer(self.screen.root)
             .map_err(|e| {
-                error!("{e}");
+                error!("{}{e}", self.log_prefix);
                 InputError::Simulate("error when requesting query_pointer with x11rb: {e:?}")
             })?
             .reply()
             .map_err(|e| {
-                error!("{e}");
+                error!("{}{e}", self.log_prefix);
                 InputError::Simulate("error with the reply of query_pointer with x11rb: {e:?}")
             })?;
         Ok((reply.root_x as i32, reply.root_y as i32))
     }
 }
+
+/// Get the bitmask of the modifiers that are held. The modifier mapping
+/// contains the same number of keycodes for each of the eight modifiers. A
+/// modifier is active if any of its keycodes is held (e.g. `Shift_L` or
+/// `Shift_R`)
+fn modifier_mask(modifier_mapping: &[Keycode], held_keycodes: &[Keycode]) -> u16 {
+    let keycodes_per_modifier = modifier_mapping.len() / 8;
+    if keycodes_per_modifier == 0 {
+        return 0;
+    }
+    modifier_mapping
+        .chunks(keycodes_per_modifier)
+        .enumerate()
+        .filter(|(_, keycodes)| {
+            keycodes
+                .iter()
+                .any(|keycode| *keycode != 0 && held_keycodes.contains(keycode))
+        })
+        .fold(0, |mask, (mod_no, _)| mask | 1 << mod_no)
+}
+
+/// Get the modifier keys of the state of the keys and buttons. Mod1 is
+/// usually Alt and Mod4 is usually Super
+fn mask_to_modifiers(mask: KeyButMask) -> Vec<Key> {
+    [
+        (KeyButMask::SHIFT, Key::Shift),
+        (KeyButMask::LOCK, Key::CapsLock),
+        (KeyButMask::CONTROL, Key::Control),
+        (KeyButMask::MOD1, Key::Alt),
+        (KeyButMask::MOD4, Key::Meta),
+    ]
+    .into_iter()
+    .filter(|&(modifier, _)| mask.contains(modifier))
+    .map(|(_, key)| key)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_of_mask() {
+        assert!(mask_to_modifiers(KeyButMask::BUTTON1).is_empty());
+        assert_eq!(
+            mask_to_modifiers(KeyButMask::SHIFT | KeyButMask::MOD4 | KeyButMask::BUTTON1),
+            vec![Key::Shift, Key::Meta]
+        );
+        assert_eq!(
+            mask_to_modifiers(KeyButMask::CONTROL | KeyButMask::MOD1),
+            vec![Key::Control, Key::Alt]
+        );
+    }
+
+    #[test]
+    fn modifier_mask_of_held_keycodes() {
+        // Two keycodes per modifier: Shift_L and Shift_R for Shift, Control_L for
+        // Control and Alt_L for Mod1
+        let mapping = [50, 62, 0, 0, 37, 0, 64, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(modifier_mask(&mapping, &[]), 0);
+        // Shift+a
+        assert_eq!(modifier_mask(&mapping, &[62, 38]), 0x1);
+        assert_eq!(modifier_mask(&mapping, &[50, 62, 37]), 0x1 | 0x4);
+        assert_eq!(modifier_mask(&mapping, &[64]), 0x8);
+        assert_eq!(modifier_mask(&[], &[50]), 0);
+    }
+
+    #[test]
+    fn buttons_of_the_pointer() {
+        let supported = |pointer_buttons| {
+            crate::BUTTONS
+                .iter()
+                .copied()
+                .filter(|&button| has_button(pointer_buttons, button))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(supported(9), crate::BUTTONS);
+        assert_eq!(
+            supported(7),
+            [
+                Button::Left,
+                Button::Middle,
+                Button::Right,
+                Button::ScrollUp,
+                Button::ScrollDown,
+                Button::ScrollLeft,
+                Button::ScrollRight
+            ]
+        );
+        assert_eq!(supported(3), [Button::Left, Button::Middle, Button::Right]);
+    }
+
+    #[test]
+    fn devices_of_reply() {
+        use x11rb::protocol::{xinput::DeviceInfo, xproto::Str};
+
+        let device = |device_id, device_use| DeviceInfo {
+            device_type: 0,
+            device_id,
+            num_class_info: 0,
+            device_use,
+        };
+        let name = |name: &str| Str {
+            name: name.as_bytes().to_vec(),
+        };
+        let reply = ListInputDevicesReply {
+            xi_reply_type: 0,
+            sequence: 0,
+            length: 0,
+            devices: vec![
+                device(2, DeviceUse::IS_X_POINTER),
+                device(3, DeviceUse::IS_X_KEYBOARD),
+                device(4, DeviceUse::IS_X_EXTENSION_POINTER),
+                device(9, DeviceUse::IS_X_EXTENSION_DEVICE),
+            ],
+            infos: Vec::new(),
+            names: vec![
+                name("Virtual core pointer"),
+                name("Virtual core keyboard"),
+                name("Virtual core XTEST pointer"),
+                name("Power Button"),
+            ],
+        };
+        let devices = x11_devices(&reply);
+        assert_eq!(devices.len(), 4);
+        assert_eq!(devices[1].id, 3);
+        assert_eq!(devices[1].name, "Virtual core keyboard");
+        assert!(devices[1].is_keyboard && !devices[1].is_pointer);
+        assert!(devices[0].is_pointer && devices[2].is_pointer);
+        assert!(!devices[3].is_keyboard && !devices[3].is_pointer);
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn text_is_encoded_in_latin1() {
+        assert_eq!(latin1("abc").unwrap(), b"abc");
+        assert_eq!(latin1("é ÿ").unwrap(), vec![0xe9, b' ', 0xff]);
+        assert_eq!(latin1("€"), None);
+    }
+}
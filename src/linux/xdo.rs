@@ -1,14 +1,16 @@
 use std::{
-    ffi::{c_char, c_int, c_ulong, c_void, CString},
-    ptr,
+    ffi::{c_char, c_int, c_uchar, c_ulong, c_void, CString},
+    fmt, ptr, slice,
 };
 
 use libc::useconds_t;
 
-use log::debug;
+use log::{debug, warn};
 
+use super::scroll_clicks;
 use crate::{
-    Axis, Button, Coordinate, Direction, InputError, InputResult, Key, Keyboard, Mouse, NewConError,
+    Axis, Button, Coordinate, Direction, InputError, InputResult, Key, Keyboard, Mouse,
+    NewConError, WindowInfo,
 };
 use xkeysym::Keysym;
 
@@ -68,6 +70,21 @@ extern "C" {
         screen: *mut c_int,
         window: *mut Window,
     ) -> c_int;
+
+    fn xdo_get_active_window(xdo: Xdo, window_ret: *mut Window) -> c_int;
+    fn xdo_get_window_name(
+        xdo: Xdo,
+        window: Window,
+        name_ret: *mut *mut c_uchar,
+        name_len_ret: *mut c_int,
+        name_type: *mut c_int,
+    ) -> c_int;
+    fn xdo_get_pid_window(xdo: Xdo, window: Window) -> c_int;
+}
+
+#[link(name = "X11")]
+extern "C" {
+    fn XFree(data: *mut c_void) -> c_int;
 }
 
 fn mousebutton(button: Button) -> c_int {
@@ -87,18 +104,49 @@ fn mousebutton(button: Button) -> c_int {
 /// The main struct for handling the event emitting
 pub struct Con {
     xdo: Xdo,
-    delay: u32, // microseconds
+    delay: u32,         // microseconds
+    log_prefix: String, // prefix of the log messages of the instance of Enigo
 }
 // This is safe, we have a unique pointer.
 // TODO: use Unique<c_char> once stable.
 unsafe impl Send for Con {}
 
+impl fmt::Debug for Con {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Con")
+            .field("delay", &self.delay)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Con {
     /// Create a new Enigo instance
     /// If no `dyp_name` is provided, the $DISPLAY environment variable is read
     /// and used instead
-    pub fn new(dyp_name: &Option<String>, delay: u32) -> Result<Self, NewConError> {
-        debug!("using xdo");
+    /// Disabling the auto-repeat of held keys and sending the events with
+    /// `XSendEvent` is not supported with xdo
+    pub fn new(
+        dyp_name: &Option<String>,
+        delay: u32,
+        disable_autorepeat: bool,
+        use_send_event: bool,
+        keysym_cache_size: Option<usize>,
+        allow_remap: bool,
+        log_prefix: &str,
+    ) -> Result<Self, NewConError> {
+        debug!("{log_prefix}using xdo");
+        if disable_autorepeat {
+            warn!("{log_prefix}disabling the auto-repeat of held keys is not supported with xdo");
+        }
+        if use_send_event {
+            warn!("{log_prefix}sending the events with XSendEvent is not supported with xdo");
+        }
+        if keysym_cache_size.is_some() {
+            warn!("{log_prefix}limiting the number of mapped keysyms is not supported with xdo");
+        }
+        if !allow_remap {
+            warn!("{log_prefix}disallowing changes of the keymap is not supported with xdo");
+        }
         let xdo = match dyp_name {
             Some(name) => {
                 let Ok(string) = CString::new(name.as_bytes()) else {
@@ -119,6 +167,7 @@ impl Con {
         Ok(Self {
             xdo,
             delay: delay * 1000,
+            log_prefix: log_prefix.to_string(),
         })
     }
 
@@ -132,6 +181,54 @@ impl Con {
     pub fn set_delay(&mut self, delay: u32) {
         self.delay = delay * 1000;
     }
+
+    /// Get the title and process id of the window that currently has the
+    /// focus. xdo does not allow getting the class of the window
+    pub fn focused_window(&self) -> InputResult<WindowInfo> {
+        let mut window: Window = 0;
+        debug!("{}xdo_get_active_window", self.log_prefix);
+        let res = unsafe { xdo_get_active_window(self.xdo, &mut window) };
+        if res != XDO_SUCCESS {
+            return Err(InputError::Simulate("unable to get the active window"));
+        }
+
+        let mut name = ptr::null_mut();
+        let mut name_len = 0;
+        let mut name_type = 0;
+        debug!("{}xdo_get_window_name of window {window}", self.log_prefix);
+        let res = unsafe {
+            xdo_get_window_name(self.xdo, window, &mut name, &mut name_len, &mut name_type)
+        };
+        if res != XDO_SUCCESS {
+            return Err(InputError::Simulate(
+                "unable to get the name of the active window",
+            ));
+        }
+        let title = if name.is_null() {
+            String::new()
+        } else {
+            let title = unsafe { slice::from_raw_parts(name, name_len.try_into().unwrap_or(0)) };
+            let title = String::from_utf8_lossy(title).into_owned();
+            unsafe { XFree(name.cast()) };
+            title
+        };
+
+        debug!("{}xdo_get_pid_window of window {window}", self.log_prefix);
+        // A pid of 0 means the window does not have the _NET_WM_PID property
+        let pid = unsafe { xdo_get_pid_window(self.xdo, window) };
+        let pid = u32::try_from(pid).ok().filter(|&pid| pid != 0);
+
+        // c_ulong only has 32 bits on some targets
+        #[allow(clippy::useless_conversion)]
+        let id = window.into();
+
+        Ok(WindowInfo {
+            title,
+            class: None,
+            pid,
+            id,
+        })
+    }
 }
 
 impl Drop for Con {
@@ -150,8 +247,8 @@ impl Keyboard for Con {
             ));
         };
         debug!(
-            "xdo_enter_text_window with string {:?}, delay {}",
-            string, self.delay
+            "{}xdo_enter_text_window with string {:?}, delay {}",
+            self.log_prefix, string, self.delay
         );
         let res = unsafe {
             xdo_enter_text_window(
@@ -169,11 +266,12 @@ impl Keyboard for Con {
 
     fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
         let keysym = Keysym::from(key);
-        let Some(keysym_name) = keysym.name() else {
-            // this should never happen, because we only use keysyms with a known name
-            return Err(InputError::InvalidInput("the keysym does not have a name"));
+        let keysym_name = match keysym.name() {
+            Some(keysym_name) => keysym_name.replace("XK_", ""), /* TODO: remove if xkeysym changed their names (https://github.com/rust-windowing/xkeysym/issues/18) */
+            // Keysyms without a name (e.g. from Key::Other) can be entered by their
+            // hexadecimal value
+            None => format!("{:#x}", keysym.raw()),
         };
-        let keysym_name = keysym_name.replace("XK_", ""); // TODO: remove if xkeysym changed their names (https://github.com/rust-windowing/xkeysym/issues/18)
 
         let Ok(string) = CString::new(keysym_name) else {
             // this should never happen, because none of the names contain NULL bytes
@@ -185,8 +283,8 @@ impl Keyboard for Con {
         let res = match direction {
             Direction::Click => {
                 debug!(
-                    "xdo_send_keysequence_window with string {:?}, delay {}",
-                    string, self.delay
+                    "{}xdo_send_keysequence_window with string {:?}, delay {}",
+                    self.log_prefix, string, self.delay
                 );
                 unsafe {
                     xdo_send_keysequence_window(
@@ -199,8 +297,8 @@ impl Keyboard for Con {
             }
             Direction::Press => {
                 debug!(
-                    "xdo_send_keysequence_window_down with string {:?}, delay {}",
-                    string, self.delay
+                    "{}xdo_send_keysequence_window_down with string {:?}, delay {}",
+                    self.log_prefix, string, self.delay
                 );
                 unsafe {
                     xdo_send_keysequence_window_down(
@@ -213,8 +311,8 @@ impl Keyboard for Con {
             }
             Direction::Release => {
                 debug!(
-                    "xdo_send_keysequence_window_up with string {:?}, delay {}",
-                    string, self.delay
+                    "{}xdo_send_keysequence_window_up with string {:?}, delay {}",
+                    self.log_prefix, string, self.delay
                 );
                 unsafe {
                     xdo_send_keysequence_window_up(
@@ -245,15 +343,24 @@ impl Mouse for Con {
         let button = mousebutton(button);
         let res = match direction {
             Direction::Press => {
-                debug!("xdo_mouse_down with mouse button {}", button);
+                debug!(
+                    "{}xdo_mouse_down with mouse button {}",
+                    self.log_prefix, button
+                );
                 unsafe { xdo_mouse_down(self.xdo, CURRENT_WINDOW, button) }
             }
             Direction::Release => {
-                debug!("xdo_mouse_up with mouse button {}", button);
+                debug!(
+                    "{}xdo_mouse_up with mouse button {}",
+                    self.log_prefix, button
+                );
                 unsafe { xdo_mouse_up(self.xdo, CURRENT_WINDOW, button) }
             }
             Direction::Click => {
-                debug!("xdo_click_window with mouse button {}", button);
+                debug!(
+                    "{}xdo_click_window with mouse button {}",
+                    self.log_prefix, button
+                );
                 unsafe { xdo_click_window(self.xdo, CURRENT_WINDOW, button) }
             }
         };
@@ -266,11 +373,17 @@ impl Mouse for Con {
     fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
         let res = match coordinate {
             Coordinate::Rel => {
-                debug!("xdo_move_mouse_relative with x {}, y {}", x, y);
+                debug!(
+                    "{}xdo_move_mouse_relative with x {}, y {}",
+                    self.log_prefix, x, y
+                );
                 unsafe { xdo_move_mouse_relative(self.xdo, x as c_int, y as c_int) }
             }
             Coordinate::Abs => {
-                debug!("xdo_move_mouse with mouse button with x {}, y {}", x, y);
+                debug!(
+                    "{}xdo_move_mouse with mouse button with x {}, y {}",
+                    self.log_prefix, x, y
+                );
                 unsafe { xdo_move_mouse(self.xdo, x as c_int, y as c_int, 0) }
             }
         };
@@ -280,10 +393,8 @@ impl Mouse for Con {
         Ok(())
     }
 
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
-        let mut length = length;
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
         let button = if length < 0 {
-            length = -length;
             match axis {
                 Axis::Horizontal => Button::ScrollLeft,
                 Axis::Vertical => Button::ScrollUp,
@@ -294,10 +405,11 @@ impl Mouse for Con {
                 Axis::Vertical => Button::ScrollDown,
             }
         };
-        for _ in 0..length {
+        let clicks = scroll_clicks(length, &self.log_prefix);
+        for _ in 0..clicks {
             self.button(button, Direction::Click)?;
         }
-        Ok(())
+        Ok(clicks)
     }
 
     fn main_display(&self) -> InputResult<(i32, i32)> {
@@ -305,7 +417,7 @@ impl Mouse for Con {
         let mut width = 0;
         let mut height = 0;
 
-        debug!("xdo_get_viewport_dimensions");
+        debug!("{}xdo_get_viewport_dimensions", self.log_prefix);
         let res =
             unsafe { xdo_get_viewport_dimensions(self.xdo, &mut width, &mut height, MAIN_SCREEN) };
 
@@ -320,7 +432,7 @@ impl Mouse for Con {
         let mut y = 0;
         let mut unused_screen_index = 0;
         let mut unused_window_index = CURRENT_WINDOW;
-        debug!("xdo_get_mouse_location2");
+        debug!("{}xdo_get_mouse_location2", self.log_prefix);
         let res = unsafe {
             xdo_get_mouse_location2(
                 self.xdo,
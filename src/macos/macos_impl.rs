@@ -1,14 +1,14 @@
 use std::os::raw::{c_char, c_int, c_uchar, c_uint, c_ulong, c_ushort, c_void};
 use std::{
-    thread,
+    fmt, thread,
     time::{Duration, Instant},
 };
 
 use core_graphics::{
     display::{CFIndex, CGDisplay, CGPoint},
     event::{
-        CGEvent, CGEventRef, CGEventTapLocation, CGEventType, CGKeyCode, CGMouseButton, EventField,
-        KeyCode, ScrollEventUnit,
+        CGEvent, CGEventFlags, CGEventRef, CGEventTapLocation, CGEventType, CGKeyCode,
+        CGMouseButton, EventField, KeyCode, ScrollEventUnit,
     },
     event_source::{CGEventSource, CGEventSourceStateID},
 };
@@ -18,12 +18,44 @@ use log::{debug, error, info};
 use objc2::msg_send;
 
 use crate::{
-    Axis, Button, Coordinate, Direction, InputError, InputResult, Key, Keyboard, Mouse,
-    NewConError, Settings,
+    Axis, Button, Capabilities, Coordinate, Direction, EventTap, InputError, InputResult, Key,
+    Keyboard, Mouse, NewConError, ScrollMethod, Settings, WindowInfo,
 };
 
 type CFDataRef = *const c_void;
 
+// Fields of scroll events that are not yet exposed by core-graphics
+// https://developer.apple.com/documentation/coregraphics/cgeventfield
+#[cfg(feature = "platform_specific")]
+const SCROLL_WHEEL_EVENT_SCROLL_PHASE: u32 = 99;
+#[cfg(feature = "platform_specific")]
+const SCROLL_WHEEL_EVENT_MOMENTUM_PHASE: u32 = 123;
+
+// Values of CGScrollPhase
+#[cfg(feature = "platform_specific")]
+const SCROLL_PHASE_NONE: i64 = 0;
+#[cfg(feature = "platform_specific")]
+const SCROLL_PHASE_BEGAN: i64 = 1;
+#[cfg(feature = "platform_specific")]
+const SCROLL_PHASE_CHANGED: i64 = 2;
+#[cfg(feature = "platform_specific")]
+const SCROLL_PHASE_ENDED: i64 = 4;
+
+// Values of CGMomentumScrollPhase
+#[cfg(feature = "platform_specific")]
+const MOMENTUM_PHASE_NONE: i64 = 0;
+#[cfg(feature = "platform_specific")]
+const MOMENTUM_PHASE_BEGIN: i64 = 1;
+#[cfg(feature = "platform_specific")]
+const MOMENTUM_PHASE_CONTINUE: i64 = 2;
+#[cfg(feature = "platform_specific")]
+const MOMENTUM_PHASE_END: i64 = 3;
+
+// Maximum number of events of the scroll phase. Longer scrolls are coalesced
+// into fewer events, each of them scrolling multiple lines
+#[cfg(feature = "platform_specific")]
+const MAX_SCROLL_PHASE_EVENTS: u32 = 64;
+
 #[repr(C)]
 struct __TISInputSource;
 type TISInputSourceRef = *const __TISInputSource;
@@ -116,6 +148,9 @@ extern "C" {
 
     fn LMGetKbdType() -> UInt8;
 
+    #[allow(non_snake_case)]
+    fn CGEventSourceFlagsState(stateID: CGEventSourceStateID) -> u64;
+
     #[allow(non_snake_case)]
     fn CFStringCreateWithCharacters(
         alloc: CFAllocatorRef,
@@ -139,11 +174,13 @@ extern "C" {
 }
 
 /// The main struct for handling the event emitting
+#[allow(clippy::struct_excessive_bools)] // The bools are copied from the settings
 pub struct Enigo {
     delay: u64,
     event_source: CGEventSource,
     display: CGDisplay,
     held: (Vec<Key>, Vec<CGKeyCode>), // Currently held keys
+    last_click_location: Option<(i32, i32)>,
     event_source_user_data: i64,
     release_keys_when_dropped: bool,
     double_click_delay: Duration,
@@ -155,12 +192,77 @@ pub struct Enigo {
                                             * determine double clicks and handle cases where
                                             * another button is clicked while the other one has
                                             * not yet been released */
+    ignore_carriage_return: bool,
+    max_text_length: Option<usize>,
+    allow_system_keys: bool,
+    ignore_unmatched_release: bool,
+    invert_scroll_x: bool,
+    invert_scroll_y: bool,
+    sticky_modifiers: crate::StickyModifiers,
+    click_gap: Duration,        // time between the press and release of a click
+    double_click_gap: Duration, // time between the clicks of a multi click
+    #[cfg(feature = "unicode")]
+    normalize_text: Option<crate::NormalizationForm>,
+    event_tap: CGEventTapLocation,
+    target_pid: Option<i32>, // Events are only posted to this process if it is set
+    use_physical_pixels: bool, // The coordinates are in pixels instead of points
+    log_prefix: String,
+}
+
+impl fmt::Debug for Enigo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Enigo")
+            .field("delay", &self.delay)
+            .field("held", &self.held)
+            .field("last_click_location", &self.last_click_location)
+            .field("release_keys_when_dropped", &self.release_keys_when_dropped)
+            .field("event_source_user_data", &self.event_source_user_data)
+            .field(
+                "event_tap",
+                &match self.event_tap {
+                    CGEventTapLocation::HID => "HID",
+                    CGEventTapLocation::Session => "Session",
+                    CGEventTapLocation::AnnotatedSession => "AnnotatedSession",
+                },
+            )
+            .field("target_pid", &self.target_pid)
+            .field("double_click_delay", &self.double_click_delay)
+            .field("ignore_carriage_return", &self.ignore_carriage_return)
+            .field("max_text_length", &self.max_text_length)
+            .field("allow_system_keys", &self.allow_system_keys)
+            .field("ignore_unmatched_release", &self.ignore_unmatched_release)
+            .field("invert_scroll_x", &self.invert_scroll_x)
+            .field("invert_scroll_y", &self.invert_scroll_y)
+            .field("sticky_modifiers", &self.sticky_modifiers)
+            .field("click_gap", &self.click_gap)
+            .field("double_click_gap", &self.double_click_gap)
+            .field(
+                "main_display",
+                &(self.display.pixels_wide(), self.display.pixels_high()),
+            )
+            .finish_non_exhaustive()
+    }
 }
 
 impl Mouse for Enigo {
+    fn double_click_gap(&self) -> Duration {
+        self.double_click_gap
+    }
+
+    fn last_click_location(&self) -> Option<(i32, i32)> {
+        debug!("\x1b[93m{}last_click_location()\x1b[0m", self.log_prefix);
+        self.last_click_location
+    }
+
     // Sends a button event to the X11 server via `XTest` extension
     fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mbutton(button: {button:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}button(button: {button:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        if crate::needs_click_gap(button, direction, self.click_gap) {
+            return crate::click_with_gap(self, button, self.click_gap);
+        }
         let (current_x, current_y) = self.location()?;
 
         if direction == Direction::Click || direction == Direction::Press {
@@ -169,10 +271,10 @@ impl Mouse for Enigo {
                 Button::Left => (CGMouseButton::Left, CGEventType::LeftMouseDown),
                 Button::Middle => (CGMouseButton::Center, CGEventType::OtherMouseDown),
                 Button::Right => (CGMouseButton::Right, CGEventType::RightMouseDown),
-                Button::ScrollUp => return self.scroll(-1, Axis::Vertical),
-                Button::ScrollDown => return self.scroll(1, Axis::Vertical),
-                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal),
-                Button::ScrollRight => return self.scroll(1, Axis::Horizontal),
+                Button::ScrollUp => return self.scroll(-1, Axis::Vertical).map(|_| ()),
+                Button::ScrollDown => return self.scroll(1, Axis::Vertical).map(|_| ()),
+                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal).map(|_| ()),
+                Button::ScrollRight => return self.scroll(1, Axis::Horizontal).map(|_| ()),
             };
             let dest = CGPoint::new(current_x as f64, current_y as f64);
 
@@ -188,7 +290,8 @@ impl Mouse for Enigo {
                 EventField::EVENT_SOURCE_USER_DATA,
                 self.event_source_user_data,
             );
-            event.post(CGEventTapLocation::HID);
+            self.post_event(&event)?;
+            self.last_click_location = Some((current_x, current_y));
         }
         if direction == Direction::Click || direction == Direction::Release {
             let click_count = self.nth_button_press(button, Direction::Release);
@@ -200,7 +303,7 @@ impl Mouse for Enigo {
                 | Button::ScrollDown
                 | Button::ScrollLeft
                 | Button::ScrollRight => {
-                    info!("On macOS the mouse_up function has no effect when called with one of the Scroll buttons");
+                    info!("{}On macOS the mouse_up function has no effect when called with one of the Scroll buttons", self.log_prefix);
                     return Ok(());
                 }
             };
@@ -218,13 +321,16 @@ impl Mouse for Enigo {
                 EventField::EVENT_SOURCE_USER_DATA,
                 self.event_source_user_data,
             );
-            event.post(CGEventTapLocation::HID);
+            self.post_event(&event)?;
         }
         Ok(())
     }
 
     fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
-        debug!("\x1b[93mmove_mouse(x: {x:?}, y: {y:?}, coordinate:{coordinate:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}move_mouse(x: {x:?}, y: {y:?}, coordinate:{coordinate:?})\x1b[0m",
+            self.log_prefix
+        );
         let pressed = unsafe { AppKit::NSEvent::pressedMouseButtons() };
         let (current_x, current_y) = self.location()?;
 
@@ -244,7 +350,13 @@ impl Mouse for Enigo {
                                                            // it can be anything
         };
 
-        let dest = CGPoint::new(absolute.0 as f64, absolute.1 as f64);
+        // The events are created with coordinates in points
+        let scale = self.scale();
+        let dest = CGPoint::new(absolute.0 as f64 / scale, absolute.1 as f64 / scale);
+        let relative = (
+            (relative.0 as f64 / scale).round() as i32,
+            (relative.1 as f64 / scale).round() as i32,
+        );
         let Ok(event) =
             CGEvent::new_mouse_event(self.event_source.clone(), event_type, dest, button)
         else {
@@ -267,16 +379,20 @@ impl Mouse for Enigo {
             EventField::EVENT_SOURCE_USER_DATA,
             self.event_source_user_data,
         );
-        event.post(CGEventTapLocation::HID);
+        self.post_event(&event)?;
         Ok(())
     }
 
     // Sends a scroll event to the X11 server via `XTest` extension
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
-        debug!("\x1b[93mscroll(length: {length:?}, axis: {axis:?})\x1b[0m");
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
+        debug!(
+            "\x1b[93m{}scroll(length: {length:?}, axis: {axis:?})\x1b[0m",
+            self.log_prefix
+        );
+        let length = crate::scroll_length(length, axis, self.invert_scroll_x, self.invert_scroll_y);
         let (ax, len_x, len_y) = match axis {
-            Axis::Horizontal => (2, 0, -length),
-            Axis::Vertical => (1, -length, 0),
+            Axis::Horizontal => (2, 0, length.saturating_neg()),
+            Axis::Vertical => (1, length.saturating_neg(), 0),
         };
 
         let Ok(event) = CGEvent::new_scroll_event(
@@ -294,28 +410,43 @@ impl Mouse for Enigo {
             EventField::EVENT_SOURCE_USER_DATA,
             self.event_source_user_data,
         );
-        event.post(CGEventTapLocation::HID);
-        Ok(())
+        self.post_event(&event)?;
+        Ok(1)
     }
 
     fn main_display(&self) -> InputResult<(i32, i32)> {
-        debug!("\x1b[93mmain_display()\x1b[0m");
+        debug!("\x1b[93m{}main_display()\x1b[0m", self.log_prefix);
+        let scale = self.scale();
         Ok((
-            self.display.pixels_wide() as i32,
-            self.display.pixels_high() as i32,
+            (self.display.pixels_wide() as f64 * scale) as i32,
+            (self.display.pixels_high() as f64 * scale) as i32,
         ))
     }
 
     fn location(&self) -> InputResult<(i32, i32)> {
-        debug!("\x1b[93mlocation()\x1b[0m");
+        debug!("\x1b[93m{}location()\x1b[0m", self.log_prefix);
         let pt = unsafe { AppKit::NSEvent::mouseLocation() };
-        let (x, y_inv) = (pt.x as i32, pt.y as i32);
-        Ok((x, self.display.pixels_high() as i32 - y_inv))
+        let (x, y) = (pt.x, self.display.pixels_high() as f64 - pt.y);
+        let scale = self.scale();
+        Ok(((x * scale) as i32, (y * scale) as i32))
     }
 }
 
 // https://stackoverflow.com/questions/1918841/how-to-convert-ascii-character-to-cgkeycode
 impl Keyboard for Enigo {
+    #[cfg(feature = "unicode")]
+    fn normalization_form(&self) -> Option<crate::NormalizationForm> {
+        self.normalize_text
+    }
+
+    fn max_text_length(&self) -> Option<usize> {
+        self.max_text_length
+    }
+
+    fn take_sticky_modifiers(&mut self) -> Vec<Key> {
+        self.sticky_modifiers.take_all()
+    }
+
     fn fast_text(&mut self, text: &str) -> InputResult<Option<()>> {
         // Fn to create an iterator over sub slices of a str that have the specified
         // length
@@ -336,7 +467,7 @@ impl Keyboard for Enigo {
             })
         }
 
-        debug!("\x1b[93mfast_text(text: {text})\x1b[0m");
+        debug!("\x1b[93m{}fast_text(text: {text})\x1b[0m", self.log_prefix);
         // WORKAROUND: This is a fix for issue https://github.com/enigo-rs/enigo/issues/68
         // The CGEventKeyboardSetUnicodeString function (used inside of
         // event.set_string(chunk)) truncates strings down to 20 characters
@@ -374,7 +505,7 @@ impl Keyboard for Enigo {
                 EventField::EVENT_SOURCE_USER_DATA,
                 self.event_source_user_data,
             );
-            event.post(CGEventTapLocation::HID);
+            self.post_event(&event)?;
         }
         thread::sleep(Duration::from_millis(2));
         Ok(Some(()))
@@ -382,87 +513,182 @@ impl Keyboard for Enigo {
 
     #[allow(clippy::too_many_lines)]
     fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mkey(key: {key:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}key(key: {key:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
         // Nothing to do
         if key == Key::Unicode('\0') {
             return Ok(());
         }
+        key.check_system_key(self.allow_system_keys)?;
+        key.check_enterable()?;
+        if let Some(result) =
+            crate::enter_sticky(self, |enigo| &mut enigo.sticky_modifiers, key, direction)
+        {
+            return result;
+        }
+        if let Some((modifiers, key)) = key.shortcut() {
+            debug!("{}the key is entered as a shortcut", self.log_prefix);
+            return crate::enter_shortcut(self, modifiers, key, direction);
+        }
+        if key == Key::LaunchAssistant {
+            return Err(InputError::Unsupported(
+                "there is no key or shortcut for Key::LaunchAssistant on macOS",
+            ));
+        }
+        // Handle special characters separately
+        let key = match key {
+            Key::Unicode('\r') if self.ignore_carriage_return => {
+                debug!("{}entering a carriage return is a noop", self.log_prefix);
+                return Ok(());
+            }
+            Key::Unicode('\n' | '\r') => Key::Return,
+            Key::Unicode('\t') => Key::Tab,
+            key => key,
+        };
+        if self.ignore_unmatched_release
+            && crate::is_unmatched_release(&self.held.0, &key, direction)
+        {
+            debug!(
+                "{}releasing the key {key:?} that is not held is a noop",
+                self.log_prefix
+            );
+            return Ok(());
+        }
         match key {
             Key::VolumeUp => {
-                debug!("special case for handling the VolumeUp key");
+                debug!(
+                    "{}special case for handling the VolumeUp key",
+                    self.log_prefix
+                );
                 self.special_keys(0, direction)?;
             }
             Key::VolumeDown => {
-                debug!("special case for handling the VolumeDown key");
+                debug!(
+                    "{}special case for handling the VolumeDown key",
+                    self.log_prefix
+                );
                 self.special_keys(1, direction)?;
             }
             Key::BrightnessUp => {
-                debug!("special case for handling the BrightnessUp key");
+                debug!(
+                    "{}special case for handling the BrightnessUp key",
+                    self.log_prefix
+                );
                 self.special_keys(2, direction)?;
             }
             Key::BrightnessDown => {
-                debug!("special case for handling the BrightnessDown key");
+                debug!(
+                    "{}special case for handling the BrightnessDown key",
+                    self.log_prefix
+                );
                 self.special_keys(3, direction)?;
             }
             Key::Power => {
-                debug!("special case for handling the VolumeMute key");
+                debug!(
+                    "{}special case for handling the VolumeMute key",
+                    self.log_prefix
+                );
                 self.special_keys(6, direction)?;
             }
             Key::VolumeMute => {
-                debug!("special case for handling the VolumeMute key");
+                debug!(
+                    "{}special case for handling the VolumeMute key",
+                    self.log_prefix
+                );
                 self.special_keys(7, direction)?;
             }
 
             Key::ContrastUp => {
-                debug!("special case for handling the VolumeUp key");
+                debug!(
+                    "{}special case for handling the VolumeUp key",
+                    self.log_prefix
+                );
                 self.special_keys(11, direction)?;
             }
             Key::ContrastDown => {
-                debug!("special case for handling the VolumeDown key");
+                debug!(
+                    "{}special case for handling the VolumeDown key",
+                    self.log_prefix
+                );
                 self.special_keys(12, direction)?;
             }
             Key::LaunchPanel => {
-                debug!("special case for handling the MediaPlayPause key");
+                debug!(
+                    "{}special case for handling the MediaPlayPause key",
+                    self.log_prefix
+                );
                 self.special_keys(13, direction)?;
             }
             Key::Eject => {
-                debug!("special case for handling the MediaNextTrack key");
+                debug!(
+                    "{}special case for handling the MediaNextTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(14, direction)?;
             }
             Key::VidMirror => {
-                debug!("special case for handling the MediaPrevTrack key");
+                debug!(
+                    "{}special case for handling the MediaPrevTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(15, direction)?;
             }
             Key::MediaPlayPause => {
-                debug!("special case for handling the MediaPlayPause key");
+                debug!(
+                    "{}special case for handling the MediaPlayPause key",
+                    self.log_prefix
+                );
                 self.special_keys(16, direction)?;
             }
             Key::MediaNextTrack => {
-                debug!("special case for handling the MediaNextTrack key");
+                debug!(
+                    "{}special case for handling the MediaNextTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(17, direction)?;
             }
             Key::MediaPrevTrack => {
-                debug!("special case for handling the MediaPrevTrack key");
+                debug!(
+                    "{}special case for handling the MediaPrevTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(18, direction)?;
             }
             Key::MediaFast => {
-                debug!("special case for handling the MediaNextTrack key");
+                debug!(
+                    "{}special case for handling the MediaNextTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(19, direction)?;
             }
             Key::MediaRewind => {
-                debug!("special case for handling the MediaPrevTrack key");
+                debug!(
+                    "{}special case for handling the MediaPrevTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(20, direction)?;
             }
             Key::IlluminationUp => {
-                debug!("special case for handling the MediaPrevTrack key");
+                debug!(
+                    "{}special case for handling the MediaPrevTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(21, direction)?;
             }
             Key::IlluminationDown => {
-                debug!("special case for handling the MediaNextTrack key");
+                debug!(
+                    "{}special case for handling the MediaNextTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(22, direction)?;
             }
             Key::IlluminationToggle => {
-                debug!("special case for handling the MediaPrevTrack key");
+                debug!(
+                    "{}special case for handling the MediaPrevTrack key",
+                    self.log_prefix
+                );
                 self.special_keys(23, direction)?;
             }
             _ => {
@@ -479,11 +705,14 @@ impl Keyboard for Enigo {
         // They are a duplicate
         match direction {
             Direction::Press => {
-                debug!("added the key {key:?} to the held keys");
+                debug!("{}added the key {key:?} to the held keys", self.log_prefix);
                 self.held.0.push(key);
             }
             Direction::Release => {
-                debug!("removed the key {key:?} from the held keys");
+                debug!(
+                    "{}removed the key {key:?} from the held keys",
+                    self.log_prefix
+                );
                 self.held.0.retain(|&k| k != key);
             }
             Direction::Click => (),
@@ -493,7 +722,19 @@ impl Keyboard for Enigo {
     }
 
     fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mraw(keycode: {keycode:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}raw(keycode: {keycode:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        if self.ignore_unmatched_release
+            && crate::is_unmatched_release(&self.held.1, &keycode, direction)
+        {
+            debug!(
+                "{}releasing the keycode {keycode:?} that is not held is a noop",
+                self.log_prefix
+            );
+            return Ok(());
+        }
 
         if direction == Direction::Click || direction == Direction::Press {
             thread::sleep(Duration::from_millis(self.delay));
@@ -508,7 +749,7 @@ impl Keyboard for Enigo {
                 EventField::EVENT_SOURCE_USER_DATA,
                 self.event_source_user_data,
             );
-            event.post(CGEventTapLocation::HID);
+            self.post_event(&event)?;
         }
 
         if direction == Direction::Click || direction == Direction::Release {
@@ -524,16 +765,22 @@ impl Keyboard for Enigo {
                 EventField::EVENT_SOURCE_USER_DATA,
                 self.event_source_user_data,
             );
-            event.post(CGEventTapLocation::HID);
+            self.post_event(&event)?;
         }
 
         match direction {
             Direction::Press => {
-                debug!("added the keycode {keycode:?} to the held keys");
+                debug!(
+                    "{}added the keycode {keycode:?} to the held keys",
+                    self.log_prefix
+                );
                 self.held.1.push(keycode);
             }
             Direction::Release => {
-                debug!("removed the keycode {keycode:?} from the held keys");
+                debug!(
+                    "{}removed the keycode {keycode:?} from the held keys",
+                    self.log_prefix
+                );
                 self.held.1.retain(|&k| k != keycode);
             }
             Direction::Click => (),
@@ -555,10 +802,22 @@ impl Enigo {
             mac_delay: delay,
             release_keys_when_dropped,
             event_source_user_data,
+            instance_name,
+            ignore_carriage_return,
+            max_text_length,
+            allow_system_keys,
+            ignore_unmatched_release,
+            click_press_release_gap_ms,
+            double_click_gap_ms,
+            macos_event_tap,
+            invert_scroll_x,
+            invert_scroll_y,
+            sticky_modifiers,
             ..
         } = settings;
 
         let held = (Vec::new(), Vec::new());
+        let log_prefix = crate::log_prefix(instance_name.as_deref());
 
         let double_click_delay = Duration::from_secs(1);
         let double_click_delay_setting = unsafe { AppKit::NSEvent::doubleClickInterval() };
@@ -570,17 +829,40 @@ impl Enigo {
             return Err(NewConError::EstablishCon("failed creating event source"));
         };
 
-        debug!("\x1b[93mconnection established on macOS\x1b[0m");
+        debug!("\x1b[93m{log_prefix}connection established on macOS\x1b[0m");
 
         Ok(Enigo {
             delay: (*delay).into(),
             event_source,
             display: CGDisplay::main(),
             held,
+            last_click_location: None,
             release_keys_when_dropped: *release_keys_when_dropped,
             double_click_delay,
             last_mouse_click: [(0, Instant::now()); 7],
             event_source_user_data: event_source_user_data.unwrap_or(crate::EVENT_MARKER as i64),
+            ignore_carriage_return: *ignore_carriage_return,
+            max_text_length: *max_text_length,
+            allow_system_keys: *allow_system_keys,
+            ignore_unmatched_release: *ignore_unmatched_release,
+            invert_scroll_x: *invert_scroll_x,
+            invert_scroll_y: *invert_scroll_y,
+            sticky_modifiers: crate::StickyModifiers::new(*sticky_modifiers),
+            click_gap: Duration::from_millis((*click_press_release_gap_ms).into()),
+            double_click_gap: Duration::from_millis((*double_click_gap_ms).into()),
+            #[cfg(feature = "unicode")]
+            normalize_text: settings.normalize_text,
+            event_tap: match macos_event_tap {
+                EventTap::Hid => CGEventTapLocation::HID,
+                EventTap::Session => CGEventTapLocation::Session,
+                EventTap::AnnotatedSession => CGEventTapLocation::AnnotatedSession,
+            },
+            #[cfg(feature = "platform_specific")]
+            target_pid: settings.macos_target_pid,
+            #[cfg(not(feature = "platform_specific"))]
+            target_pid: None,
+            use_physical_pixels: settings.use_physical_pixels,
+            log_prefix,
         })
     }
 
@@ -607,6 +889,232 @@ impl Enigo {
         self.event_source_user_data
     }
 
+    /// Scroll like a trackpad does. Unlike [`Mouse::scroll`], the events carry
+    /// the scroll phase and are followed by momentum events, so apps can
+    /// animate inertial scrolling. Because of the momentum, the content is
+    /// scrolled further than `length` lines, just like after flicking the
+    /// trackpad.
+    ///
+    /// This is only available on macOS.
+    ///
+    /// # Arguments
+    /// * `axis` - The axis to scroll on
+    /// * `length` - Number of lines to scroll before the momentum phase starts.
+    ///   The direction is the same as with [`Mouse::scroll`]. Long scrolls are
+    ///   sent as at most 64 events that scroll multiple lines each.
+    ///
+    /// # Errors
+    /// Have a look at the documentation of [`InputError`] to see under which
+    /// conditions an error will be returned.
+    #[cfg(feature = "platform_specific")]
+    pub fn scroll_with_momentum(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}scroll_with_momentum(length: {length:?}, axis: {axis:?})\x1b[0m",
+            self.log_prefix
+        );
+        let length = crate::scroll_length(length, axis, self.invert_scroll_x, self.invert_scroll_y);
+        let step = -length.signum();
+        let lines = length.unsigned_abs();
+        // The user is scrolling
+        let events = lines.min(MAX_SCROLL_PHASE_EVENTS);
+        for n in 0..events {
+            let phase = if n == 0 {
+                SCROLL_PHASE_BEGAN
+            } else {
+                SCROLL_PHASE_CHANGED
+            };
+            // Spread the lines over the events so that their sum is `lines`
+            let delta = lines / events + u32::from(n < lines % events);
+            let delta = i32::try_from(delta).unwrap_or(i32::MAX);
+            self.phased_scroll_event(axis, step * delta, phase, MOMENTUM_PHASE_NONE)?;
+        }
+        self.phased_scroll_event(axis, 0, SCROLL_PHASE_ENDED, MOMENTUM_PHASE_NONE)?;
+
+        // The content keeps on scrolling and slows down
+        let mut delta = i32::try_from(lines / 2).unwrap_or(i32::MAX);
+        let mut momentum_phase = MOMENTUM_PHASE_BEGIN;
+        while delta > 0 {
+            self.phased_scroll_event(axis, step * delta, SCROLL_PHASE_NONE, momentum_phase)?;
+            momentum_phase = MOMENTUM_PHASE_CONTINUE;
+            delta /= 2;
+        }
+        self.phased_scroll_event(axis, 0, SCROLL_PHASE_NONE, MOMENTUM_PHASE_END)
+    }
+
+    /// Post a scroll event with the given scroll and momentum phase
+    #[cfg(feature = "platform_specific")]
+    fn phased_scroll_event(
+        &self,
+        axis: Axis,
+        delta: i32,
+        scroll_phase: i64,
+        momentum_phase: i64,
+    ) -> InputResult<()> {
+        let (len_x, len_y) = match axis {
+            Axis::Horizontal => (0, delta),
+            Axis::Vertical => (delta, 0),
+        };
+        let Ok(event) = CGEvent::new_scroll_event(
+            self.event_source.clone(),
+            ScrollEventUnit::LINE,
+            2,
+            len_x,
+            len_y,
+            0,
+        ) else {
+            return Err(InputError::Simulate("failed creating event to scroll"));
+        };
+        event.set_integer_value_field(SCROLL_WHEEL_EVENT_SCROLL_PHASE, scroll_phase);
+        event.set_integer_value_field(SCROLL_WHEEL_EVENT_MOMENTUM_PHASE, momentum_phase);
+        event.set_integer_value_field(
+            EventField::EVENT_SOURCE_USER_DATA,
+            self.event_source_user_data,
+        );
+        self.post_event(&event)?;
+        // Give the apps time to animate the scrolling
+        thread::sleep(Duration::from_millis(16));
+        Ok(())
+    }
+
+    /// Post the event at the event tap or to the target process if one was
+    /// set
+    fn post_event(&self, event: &CGEvent) -> InputResult<()> {
+        let Some(pid) = self.target_pid else {
+            event.post(self.event_tap);
+            return Ok(());
+        };
+        let is_running = pid > 0
+            && unsafe {
+                AppKit::NSRunningApplication::runningApplicationWithProcessIdentifier(pid)
+            }
+            .is_some();
+        if !is_running {
+            error!(
+                "{}there is no running application with the PID {pid}",
+                self.log_prefix
+            );
+            return Err(InputError::InvalidInput(
+                "there is no running application with the target PID",
+            ));
+        }
+        event.post_to_pid(pid);
+        Ok(())
+    }
+
+    /// Get the capabilities of the connection
+    #[must_use]
+    pub fn capabilities(&self) -> Capabilities {
+        debug!("\x1b[93m{}capabilities()\x1b[0m", self.log_prefix);
+        Capabilities {
+            scroll_method: Some(ScrollMethod::Wheel),
+            buttons: crate::BUTTONS.to_vec(),
+            ..Default::default()
+        }
+        .with_queries(self)
+    }
+
+    /// Get all keys without any data (e.g. no [`Key::Unicode`]) that can be
+    /// entered. These are the keys that have a keycode, are system defined
+    /// special keys or are entered as a shortcut
+    #[must_use]
+    pub fn supported_keys(&self) -> Vec<Key> {
+        debug!("\x1b[93m{}supported_keys()\x1b[0m", self.log_prefix);
+        crate::keycodes::NAMED_KEYS
+            .iter()
+            .copied()
+            .filter(|&key| self.allow_system_keys || !key.is_system_key())
+            .filter(|&key| {
+                key.shortcut().is_some()
+                    || CGKeyCode::try_from(key).is_ok()
+                    || matches!(
+                        key,
+                        Key::BrightnessDown
+                            | Key::BrightnessUp
+                            | Key::ContrastDown
+                            | Key::ContrastUp
+                            | Key::Eject
+                            | Key::IlluminationDown
+                            | Key::IlluminationToggle
+                            | Key::IlluminationUp
+                            | Key::LaunchPanel
+                            | Key::MediaFast
+                            | Key::MediaNextTrack
+                            | Key::MediaPlayPause
+                            | Key::MediaPrevTrack
+                            | Key::MediaRewind
+                            | Key::Power
+                            | Key::VidMirror
+                            | Key::VolumeDown
+                            | Key::VolumeMute
+                            | Key::VolumeUp
+                    )
+            })
+            .collect()
+    }
+
+    /// Get the number of pixels per point of the main display if the
+    /// coordinates are in physical pixels. Otherwise it is always 1, because
+    /// macOS uses points for the coordinates of the events
+    fn scale(&self) -> f64 {
+        if !self.use_physical_pixels {
+            return 1.0;
+        }
+        self.display
+            .display_mode()
+            .map_or(1.0, |mode| mode.pixel_width() as f64 / mode.width() as f64)
+    }
+
+    /// Get the number of lines one unit of the length of [`Mouse::scroll`]
+    /// scrolls. The scroll events are created with lines as the unit, so it
+    /// is always one line
+    ///
+    /// # Errors
+    /// No error is returned on macOS
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn scroll_lines_per_notch(&self) -> InputResult<u32> {
+        debug!("\x1b[93m{}scroll_lines_per_notch()\x1b[0m", self.log_prefix);
+        Ok(1)
+    }
+
+    /// Get the modifiers that are currently held down (e.g. by the user).
+    /// Unlike [`Enigo::held`], this is not limited to the keys entered with
+    /// this instance. The state of the hardware is queried, so the modifiers
+    /// held by simulated input are only included if they were posted at the
+    /// HID level
+    ///
+    /// # Errors
+    /// No error is returned on macOS
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn physical_modifiers(&self) -> InputResult<Vec<Key>> {
+        debug!("\x1b[93m{}physical_modifiers()\x1b[0m", self.log_prefix);
+        let flags = CGEventFlags::from_bits_truncate(unsafe {
+            CGEventSourceFlagsState(CGEventSourceStateID::HIDSystemState)
+        });
+        Ok([
+            (CGEventFlags::CGEventFlagShift, Key::Shift),
+            (CGEventFlags::CGEventFlagAlphaShift, Key::CapsLock),
+            (CGEventFlags::CGEventFlagControl, Key::Control),
+            (CGEventFlags::CGEventFlagAlternate, Key::Option),
+            (CGEventFlags::CGEventFlagCommand, Key::Meta),
+        ]
+        .into_iter()
+        .filter(|&(flag, _)| flags.contains(flag))
+        .map(|(_, key)| key)
+        .collect())
+    }
+
+    /// Get the title, class and process id of the window that currently has
+    /// the focus
+    ///
+    /// # Errors
+    /// This is not yet implemented on macOS, so an error is always returned
+    pub fn focused_window(&self) -> InputResult<WindowInfo> {
+        debug!("\x1b[93m{}focused_window()\x1b[0m", self.log_prefix);
+        Err(InputError::Unsupported(
+            "getting the focused window is not implemented on macOS",
+        ))
+    }
+
     // On macOS, we have to determine ourselves if it was a double click of a mouse
     // button. The Enigo struct stores the information needed to do so. This
     // function checks if the button was pressed down again fast enough to issue a
@@ -624,7 +1132,7 @@ impl Enigo {
             }
         }
         let nth_button_press = self.last_mouse_click[button as usize].0;
-        debug!("nth_button_press: {nth_button_press}");
+        debug!("{}nth_button_press: {nth_button_press}", self.log_prefix);
         nth_button_press
     }
 
@@ -650,7 +1158,7 @@ impl Enigo {
                     EventField::EVENT_SOURCE_USER_DATA,
                     self.event_source_user_data,
                 );
-                cg_event.post(CGEventTapLocation::HID);
+                self.post_event(&cg_event)?;
             } else {
                 return Err(InputError::Simulate(
                     "failed creating event to press special key",
@@ -679,7 +1187,7 @@ impl Enigo {
                     EventField::EVENT_SOURCE_USER_DATA,
                     self.event_source_user_data,
                 );
-                cg_event.post(CGEventTapLocation::HID);
+                self.post_event(&cg_event)?;
             } else {
                 return Err(InputError::Simulate(
                     "failed creating event to release special key",
@@ -739,7 +1247,9 @@ impl TryFrom<Key> for core_graphics::event::CGKeyCode {
             Key::Home => KeyCode::HOME,
             Key::Launchpad => 160,
             Key::LeftArrow => KeyCode::LEFT_ARROW,
+            Key::Menu => 110, // kVK_ContextualMenu
             Key::MissionControl => 131,
+            Key::NumpadEnter => 76, // kVK_ANSI_KeypadEnter
             Key::PageDown => KeyCode::PAGE_DOWN,
             Key::PageUp => KeyCode::PAGE_UP,
             Key::RCommand => KeyCode::RIGHT_COMMAND,
@@ -778,7 +1288,10 @@ impl TryFrom<Key> for core_graphics::event::CGKeyCode {
             | Key::MediaPrevTrack
             | Key::MediaRewind
             | Key::Power
-            | Key::VidMirror => return Err(()),
+            | Key::VidMirror
+            | Key::EmojiPicker
+            | Key::LaunchAssistant
+            | Key::LaunchSearch => return Err(()),
         };
         Ok(key)
     }
@@ -880,17 +1393,7 @@ impl Drop for Enigo {
         }
 
         let (held_keys, held_keycodes) = self.held();
-        for key in held_keys {
-            if self.key(key, Direction::Release).is_err() {
-                error!("unable to release {key:?}");
-            };
-        }
-
-        for keycode in held_keycodes {
-            if self.raw(keycode, Direction::Release).is_err() {
-                error!("unable to release {keycode:?}");
-            };
-        }
-        debug!("released all held keys");
+        crate::release_held(self, &held_keys, &held_keycodes);
+        debug!("{}released all held keys", self.log_prefix);
     }
 }
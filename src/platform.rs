@@ -18,7 +18,7 @@ impl Mouse for Enigo {
         match self.never {}
     }
 
-    fn scroll(&mut self, _: i32, _: crate::Axis) -> crate::InputResult<()> {
+    fn scroll(&mut self, _: i32, _: crate::Axis) -> crate::InputResult<u32> {
         match self.never {}
     }
 
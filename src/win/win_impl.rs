@@ -1,36 +1,94 @@
-use std::mem::size_of;
+use std::{fmt, mem::size_of, time::Duration};
 
 use log::{debug, error, info};
 use windows::Win32::Foundation::POINT;
+#[cfg(feature = "platform_specific")]
+use windows::Win32::Foundation::RECT;
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    MapVirtualKeyW, SendInput, VkKeyScanW, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT,
-    KEYBD_EVENT_FLAGS, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE,
-    KEYEVENTF_UNICODE, MAP_VIRTUAL_KEY_TYPE, MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_HWHEEL,
-    MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP,
-    MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEEVENTF_WHEEL,
-    MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP, MOUSEINPUT, MOUSE_EVENT_FLAGS, VIRTUAL_KEY,
+    GetAsyncKeyState, MapVirtualKeyW, SendInput, VkKeyScanW, INPUT, INPUT_0, INPUT_KEYBOARD,
+    INPUT_MOUSE, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP,
+    KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE, MAP_VIRTUAL_KEY_TYPE, MOUSEEVENTF_ABSOLUTE,
+    MOUSEEVENTF_HWHEEL, MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN,
+    MOUSEEVENTF_MIDDLEUP, MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
+    MOUSEEVENTF_VIRTUALDESK, MOUSEEVENTF_WHEEL, MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP, MOUSEINPUT,
+    MOUSE_EVENT_FLAGS, VIRTUAL_KEY, VK_CAPITAL, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+};
+#[cfg(feature = "platform_specific")]
+use windows::Win32::UI::Input::Pointer::{
+    InitializeTouchInjection, InjectTouchInput, POINTER_FLAGS, POINTER_FLAG_DOWN,
+    POINTER_FLAG_INCONTACT, POINTER_FLAG_INRANGE, POINTER_FLAG_UP, POINTER_FLAG_UPDATE,
+    POINTER_INFO, POINTER_TOUCH_INFO, TOUCH_FEEDBACK_DEFAULT,
 };
-
 use windows::Win32::UI::WindowsAndMessaging::{
-    GetCursorPos, GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN, WHEEL_DELTA,
+    GetClassNameW, GetCursorPos, GetForegroundWindow, GetSystemMetrics, GetWindowTextLengthW,
+    GetWindowTextW, GetWindowThreadProcessId, SystemParametersInfoW, SM_CXSCREEN,
+    SM_CXVIRTUALSCREEN, SM_CYSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+    SPI_GETWHEELSCROLLLINES, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
 };
+#[cfg(feature = "platform_specific")]
+use windows::Win32::UI::WindowsAndMessaging::{PT_TOUCH, TOUCH_FLAG_NONE, TOUCH_MASK_CONTACTAREA};
 
 use crate::{
-    Axis, Button, Coordinate, Direction, InputError, InputResult, Key, Keyboard, Mouse,
-    NewConError, Settings,
+    Axis, Button, Capabilities, Coordinate, Direction, InputError, InputResult, Key, Keyboard,
+    Mouse, NewConError, ScrollMethod, Settings, WindowInfo,
 };
 
 type ScanCode = u16;
 pub const EXT: u16 = 0xFF00;
+/// Maximum number of touch points that can be in contact at the same time
+#[cfg(feature = "platform_specific")]
+const MAX_TOUCH_COUNT: u32 = 10;
+/// Radius of the contact area of a touch point in pixels
+#[cfg(feature = "platform_specific")]
+const TOUCH_RADIUS: i32 = 2;
 
 /// The main struct for handling the event emitting
+#[allow(clippy::struct_excessive_bools)] // The bools are copied from the settings
 pub struct Enigo {
     held: (Vec<Key>, Vec<ScanCode>), // Currently held keys
+    last_click_location: Option<(i32, i32)>,
     release_keys_when_dropped: bool,
     dw_extra_info: usize,
+    ignore_carriage_return: bool,
+    max_text_length: Option<usize>,
+    allow_system_keys: bool,
+    ignore_unmatched_release: bool,
+    invert_scroll_x: bool,
+    invert_scroll_y: bool,
+    sticky_modifiers: crate::StickyModifiers,
+    click_gap: Duration,        // time between the press and release of a click
+    double_click_gap: Duration, // time between the clicks of a multi click
+    #[cfg(feature = "platform_specific")]
+    touch_injection_initialized: bool,
+    #[cfg(feature = "platform_specific")]
+    touches: Vec<POINTER_TOUCH_INFO>, // Touch points that are currently in contact
+    #[cfg(feature = "unicode")]
+    normalize_text: Option<crate::NormalizationForm>,
+    log_prefix: String,
 }
 
-fn send_input(input: &[INPUT]) -> InputResult<()> {
+impl fmt::Debug for Enigo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Enigo")
+            .field("held", &self.held)
+            .field("last_click_location", &self.last_click_location)
+            .field("release_keys_when_dropped", &self.release_keys_when_dropped)
+            .field("dw_extra_info", &self.dw_extra_info)
+            .field("ignore_carriage_return", &self.ignore_carriage_return)
+            .field("max_text_length", &self.max_text_length)
+            .field("allow_system_keys", &self.allow_system_keys)
+            .field("ignore_unmatched_release", &self.ignore_unmatched_release)
+            .field("invert_scroll_x", &self.invert_scroll_x)
+            .field("invert_scroll_y", &self.invert_scroll_y)
+            .field("sticky_modifiers", &self.sticky_modifiers)
+            .field("click_gap", &self.click_gap)
+            .field("double_click_gap", &self.double_click_gap)
+            .field("main_display", &self.main_display().ok())
+            .finish_non_exhaustive()
+    }
+}
+
+fn send_input(input: &[INPUT], log_prefix: &str) -> InputResult<()> {
     let Ok(input_size): Result<i32, _> = size_of::<INPUT>().try_into() else {
         return Err(InputError::InvalidInput(
             "the size of the INPUT was so large, the size exceeded i32::MAX",
@@ -41,15 +99,16 @@ fn send_input(input: &[INPUT]) -> InputResult<()> {
             "the number of INPUT was so large, the length of the Vec exceeded i32::MAX",
         ));
     };
-    if unsafe { SendInput(input, input_size) } == input_len {
-        Ok(())
-    } else {
-        let last_err = std::io::Error::last_os_error();
-        error!("{last_err}");
-        Err(InputError::Simulate(
-            "not all input events were sent. they may have been blocked by UIPI",
-        ))
+    let inserted = unsafe { SendInput(input, input_size) };
+    if inserted == input_len {
+        return Ok(());
     }
+    // Neither the return value nor the last error indicate if the input was
+    // blocked by UIPI or because the secure desktop is shown, but the OS does
+    // not insert all of the events in these cases
+    let last_err = std::io::Error::last_os_error();
+    error!("{log_prefix}only {inserted} of {input_len} input events were inserted. They may have been blocked by UIPI or the secure desktop. {last_err}");
+    Err(InputError::Simulate("event blocked by the OS"))
 }
 
 fn mouse_event(
@@ -95,10 +154,31 @@ fn keybd_event(
     }
 }
 
+/// Check if the key has to be entered with the key function, because it is
+/// entered as a shortcut or is a special char
+fn needs_key_function(key: Key) -> bool {
+    key.shortcut().is_some() || matches!(key, Key::Unicode('\n' | '\r' | '\t' | '\0'))
+}
+
 impl Mouse for Enigo {
+    fn double_click_gap(&self) -> Duration {
+        self.double_click_gap
+    }
+
+    fn last_click_location(&self) -> Option<(i32, i32)> {
+        debug!("\x1b[93m{}last_click_location()\x1b[0m", self.log_prefix);
+        self.last_click_location
+    }
+
     // Sends a button event to the X11 server via `XTest` extension
     fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mbutton(button: {button:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}button(button: {button:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        if crate::needs_click_gap(button, direction, self.click_gap) {
+            return crate::click_with_gap(self, button, self.click_gap);
+        }
         let mut input = vec![];
         let button_no = match button {
             Button::Back => 1,
@@ -111,10 +191,10 @@ impl Mouse for Enigo {
                 Button::Middle => MOUSEEVENTF_MIDDLEDOWN,
                 Button::Right => MOUSEEVENTF_RIGHTDOWN,
                 Button::Back | Button::Forward => MOUSEEVENTF_XDOWN,
-                Button::ScrollUp => return self.scroll(-1, Axis::Vertical),
-                Button::ScrollDown => return self.scroll(1, Axis::Vertical),
-                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal),
-                Button::ScrollRight => return self.scroll(1, Axis::Horizontal),
+                Button::ScrollUp => return self.scroll(-1, Axis::Vertical).map(|_| ()),
+                Button::ScrollDown => return self.scroll(1, Axis::Vertical).map(|_| ()),
+                Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal).map(|_| ()),
+                Button::ScrollRight => return self.scroll(1, Axis::Horizontal).map(|_| ()),
             };
             input.push(mouse_event(
                 mouse_event_flag,
@@ -134,7 +214,7 @@ impl Mouse for Enigo {
                 | Button::ScrollDown
                 | Button::ScrollLeft
                 | Button::ScrollRight => {
-                    info!("On Windows the mouse_up function has no effect when called with one of the Scroll buttons");
+                    info!("{}On Windows the mouse_up function has no effect when called with one of the Scroll buttons", self.log_prefix);
                     return Ok(());
                 }
             };
@@ -146,55 +226,76 @@ impl Mouse for Enigo {
                 self.dw_extra_info,
             ));
         }
-        send_input(&input)
+        send_input(&input, &self.log_prefix)?;
+        if direction != Direction::Release && !button.is_scroll() {
+            self.last_click_location = self.location().ok();
+        }
+        Ok(())
     }
 
     fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
-        debug!("\x1b[93mmove_mouse(x: {x:?}, y: {y:?}, coordinate:{coordinate:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}move_mouse(x: {x:?}, y: {y:?}, coordinate:{coordinate:?})\x1b[0m",
+            self.log_prefix
+        );
         let (flags, x, y) = if coordinate == Coordinate::Abs {
-            // 0-screen width/height - 1 map to 0-65535
+            // The coordinates are relative to the virtual desktop, which can start at
+            // negative coordinates if there are multiple monitors
+            // 0-virtual desktop width/height - 1 map to 0-65535
             // Add w/2 or h/2 to round off
             // See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-mouse_event#remarks
-            let (w, h) = self.main_display()?;
-            let w = w as i64 - 1;
-            let h = h as i64 - 1;
-            let x = x as i64;
-            let y = y as i64;
+            let (min_x, min_y, max_x, max_y) = self.virtual_desktop_bounds()?;
+            let w = max_x as i64 - min_x as i64;
+            let h = max_y as i64 - min_y as i64;
+            let x = x as i64 - min_x as i64;
+            let y = y as i64 - min_y as i64;
             let x = (x * 65535 + w / 2 * x.signum()) / w;
             let y = (y * 65535 + h / 2 * y.signum()) / h;
-            (MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE, x as i32, y as i32)
+            (
+                MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK,
+                x as i32,
+                y as i32,
+            )
         } else {
             (MOUSEEVENTF_MOVE, x, y)
         };
         let input = mouse_event(flags, 0, x, y, self.dw_extra_info);
-        send_input(&[input])
+        send_input(&[input], &self.log_prefix)
     }
 
     // Sends a scroll event to the X11 server via `XTest` extension
-    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
-        debug!("\x1b[93mscroll(length: {length:?}, axis: {axis:?})\x1b[0m");
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<u32> {
+        debug!(
+            "\x1b[93m{}scroll(length: {length:?}, axis: {axis:?})\x1b[0m",
+            self.log_prefix
+        );
+        let length = crate::scroll_length(length, axis, self.invert_scroll_x, self.invert_scroll_y);
+        // A positive mouseData tilts the wheel to the right, but rotates it
+        // forward (scrolls up), so only the vertical length is negated
         let input = match axis {
             Axis::Horizontal => mouse_event(
                 MOUSEEVENTF_HWHEEL,
-                length * (WHEEL_DELTA as i32),
+                length.saturating_mul(crate::SCROLL_VALUE120_PER_NOTCH),
                 0,
                 0,
                 self.dw_extra_info,
             ),
             Axis::Vertical => mouse_event(
                 MOUSEEVENTF_WHEEL,
-                -length * (WHEEL_DELTA as i32),
+                length
+                    .saturating_neg()
+                    .saturating_mul(crate::SCROLL_VALUE120_PER_NOTCH),
                 0,
                 0,
                 self.dw_extra_info,
             ),
         };
-        send_input(&[input])?;
-        Ok(())
+        send_input(&[input], &self.log_prefix)?;
+        Ok(1)
     }
 
     fn main_display(&self) -> InputResult<(i32, i32)> {
-        debug!("\x1b[93mmain_display()\x1b[0m");
+        debug!("\x1b[93m{}main_display()\x1b[0m", self.log_prefix);
         let w = unsafe { GetSystemMetrics(SM_CXSCREEN) };
         let h = unsafe { GetSystemMetrics(SM_CYSCREEN) };
         if w == 0 || h == 0 {
@@ -209,7 +310,7 @@ impl Mouse for Enigo {
     }
 
     fn location(&self) -> InputResult<(i32, i32)> {
-        debug!("\x1b[93mlocation()\x1b[0m");
+        debug!("\x1b[93m{}location()\x1b[0m", self.log_prefix);
         let mut point = POINT { x: 0, y: 0 };
         if unsafe { GetCursorPos(&mut point) }.is_ok() {
             Ok((point.x, point.y))
@@ -219,9 +320,39 @@ impl Mouse for Enigo {
             ))
         }
     }
+
+    fn virtual_desktop_bounds(&self) -> InputResult<(i32, i32, i32, i32)> {
+        debug!("\x1b[93m{}virtual_desktop_bounds()\x1b[0m", self.log_prefix);
+        let x = unsafe { GetSystemMetrics(SM_XVIRTUALSCREEN) };
+        let y = unsafe { GetSystemMetrics(SM_YVIRTUALSCREEN) };
+        let w = unsafe { GetSystemMetrics(SM_CXVIRTUALSCREEN) };
+        let h = unsafe { GetSystemMetrics(SM_CYVIRTUALSCREEN) };
+        if w == 0 || h == 0 {
+            // Last error does not contain information about why there was an issue so it is
+            // not used here
+            Err(InputError::Simulate(
+                "could not get the dimensions of the virtual desktop",
+            ))
+        } else {
+            Ok((x, y, x + w - 1, y + h - 1))
+        }
+    }
 }
 
 impl Keyboard for Enigo {
+    #[cfg(feature = "unicode")]
+    fn normalization_form(&self) -> Option<crate::NormalizationForm> {
+        self.normalize_text
+    }
+
+    fn max_text_length(&self) -> Option<usize> {
+        self.max_text_length
+    }
+
+    fn take_sticky_modifiers(&mut self) -> Vec<Key> {
+        self.sticky_modifiers.take_all()
+    }
+
     fn fast_text(&mut self, _text: &str) -> InputResult<Option<()>> {
         Ok(None)
     }
@@ -230,20 +361,31 @@ impl Keyboard for Enigo {
     /// This is much faster if you type longer text at the cost of keyboard
     /// shortcuts not getting recognized
     fn text(&mut self, text: &str) -> InputResult<()> {
-        debug!("\x1b[93mtext(text: {text})\x1b[0m");
+        debug!("\x1b[93m{}text(text: {text})\x1b[0m", self.log_prefix);
         if text.is_empty() {
             return Ok(()); // Nothing to simulate.
         }
+        let latched = self.sticky_modifiers.take_all();
+        if !latched.is_empty() {
+            return crate::hold_modifiers(self, latched, |enigo| enigo.text_via_keys(text));
+        }
+        let text = crate::normalize(self, text);
+        let (text, cut_off) = crate::limit_length(self, &text);
         let mut buffer = [0; 2];
 
         let mut input = vec![];
         for c in text.chars() {
             // Handle special characters separately
             match c {
-                '\n' => return self.key(Key::Return, Direction::Click),
-                '\r' => { // TODO: What is the correct key to type here?
+                '\n' | '\r' | '\t' => {
+                    // Enter the text up to this point before entering the key
+                    if !input.is_empty() {
+                        send_input(&input, &self.log_prefix)?;
+                        input.clear();
+                    }
+                    self.key(Key::Unicode(c), Direction::Click)?;
+                    continue;
                 }
-                '\t' => return self.key(Key::Tab, Direction::Click),
                 '\0' => return Err(InputError::InvalidInput("the text contained a null byte")),
                 _ => (),
             }
@@ -269,74 +411,68 @@ impl Keyboard for Enigo {
                 ));
             }
         }
-        send_input(&input)
+        if !input.is_empty() {
+            send_input(&input, &self.log_prefix)?;
+        }
+        if !cut_off.is_empty() {
+            return Err(crate::TEXT_TOO_LONG);
+        }
+        Ok(())
     }
 
     /// Sends a key event to the X11 server via `XTest` extension
     fn key(&mut self, key: Key, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mkey(key: {key:?}, direction: {direction:?})\x1b[0m");
-        let mut input = vec![];
-
-        if let Key::Unicode(c) = key {
-            // Handle special characters separately
-            match c {
-                '\n' => return self.key(Key::Return, direction),
-                '\r' => { // TODO: What is the correct key to type here?
-                }
-                '\t' => return self.key(Key::Tab, direction),
-                '\0' => {
-                    debug!("entering Key::Unicode('\\0') is a noop");
-                    return Ok(());
-                }
-                _ => (),
-            }
-            let scancodes = self.get_scancode(c)?;
-            if direction == Direction::Click || direction == Direction::Press {
-                for scan in &scancodes {
-                    input.push(keybd_event(
-                        KEYEVENTF_SCANCODE,
-                        VIRTUAL_KEY(0),
-                        *scan,
-                        self.dw_extra_info,
-                    ));
-                }
-            }
-            if direction == Direction::Click || direction == Direction::Release {
-                for scan in &scancodes {
-                    input.push(keybd_event(
-                        KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP,
-                        VIRTUAL_KEY(0),
-                        *scan,
-                        self.dw_extra_info,
-                    ));
-                }
-            }
-        } else {
-            // It is okay to unwrap here because key_to_keycode only returns a None for
-            // Key::Unicode and we already ensured that is not the case
-            let keycode = VIRTUAL_KEY::try_from(key).unwrap();
-            let keyflags = get_key_flags(keycode);
-            if direction == Direction::Click || direction == Direction::Press {
-                input.push(keybd_event(keyflags, keycode, 0u16, self.dw_extra_info));
+        debug!(
+            "\x1b[93m{}key(key: {key:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        key.check_system_key(self.allow_system_keys)?;
+        key.check_enterable()?;
+        if let Some(result) =
+            crate::enter_sticky(self, |enigo| &mut enigo.sticky_modifiers, key, direction)
+        {
+            return result;
+        }
+        if let Some((modifiers, key)) = key.shortcut() {
+            debug!("{}the key is entered as a shortcut", self.log_prefix);
+            return crate::enter_shortcut(self, modifiers, key, direction);
+        }
+        // Handle special characters separately
+        let key = match key {
+            Key::Unicode('\r') if self.ignore_carriage_return => {
+                debug!("{}entering a carriage return is a noop", self.log_prefix);
+                return Ok(());
             }
-            if direction == Direction::Click || direction == Direction::Release {
-                input.push(keybd_event(
-                    keyflags | KEYEVENTF_KEYUP,
-                    keycode,
-                    0u16,
-                    self.dw_extra_info,
-                ));
+            Key::Unicode('\n' | '\r') => Key::Return,
+            Key::Unicode('\t') => Key::Tab,
+            Key::Unicode('\0') => {
+                debug!("{}entering Key::Unicode('\\0') is a noop", self.log_prefix);
+                return Ok(());
             }
+            key => key,
         };
-        send_input(&input)?;
+        if self.ignore_unmatched_release
+            && crate::is_unmatched_release(&self.held.0, &key, direction)
+        {
+            debug!(
+                "{}releasing the key {key:?} that is not held is a noop",
+                self.log_prefix
+            );
+            return Ok(());
+        }
+        let input = self.key_inputs(key, direction)?;
+        send_input(&input, &self.log_prefix)?;
 
         match direction {
             Direction::Press => {
-                debug!("added the key {key:?} to the held keys");
+                debug!("{}added the key {key:?} to the held keys", self.log_prefix);
                 self.held.0.push(key);
             }
             Direction::Release => {
-                debug!("removed the key {key:?} from the held keys");
+                debug!(
+                    "{}removed the key {key:?} from the held keys",
+                    self.log_prefix
+                );
                 self.held.0.retain(|&k| k != key);
             }
             Direction::Click => (),
@@ -345,8 +481,134 @@ impl Keyboard for Enigo {
         Ok(())
     }
 
+    /// The modifiers and the key are sent with a single call of `SendInput`.
+    /// This guarantees that the modifiers are down before the key and up
+    /// after it, even if the thread is preempted in between
+    fn key_combo(&mut self, modifiers: &[Key], key: Key) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}key_combo(modifiers: {modifiers:?}, key: {key:?})\x1b[0m",
+            self.log_prefix
+        );
+        // Latched sticky modifiers are held like the other modifiers
+        let modifiers: Vec<Key> = self
+            .sticky_modifiers
+            .take(key, Direction::Click)
+            .into_iter()
+            .chain(modifiers.iter().copied())
+            .collect();
+        let modifiers = modifiers.as_slice();
+        // Shortcuts and special characters are handled by the key function, so
+        // these keys are entered one after the other
+        if modifiers
+            .iter()
+            .chain([&key])
+            .any(|&k| needs_key_function(k))
+        {
+            debug!(
+                "{}the keys are entered one after the other",
+                self.log_prefix
+            );
+            self.press_keys(modifiers)?;
+            let result = self.key(key, Direction::Click);
+            let modifiers: Vec<Key> = modifiers.iter().rev().copied().collect();
+            return result.and(self.release_keys(&modifiers));
+        }
+        let mut input = vec![];
+        for &modifier in modifiers {
+            modifier.check_system_key(self.allow_system_keys)?;
+            modifier.check_enterable()?;
+            input.extend(self.key_inputs(modifier, Direction::Press)?);
+        }
+        key.check_system_key(self.allow_system_keys)?;
+        key.check_enterable()?;
+        input.extend(self.key_inputs(key, Direction::Click)?);
+        for &modifier in modifiers.iter().rev() {
+            input.extend(self.key_inputs(modifier, Direction::Release)?);
+        }
+        send_input(&input, &self.log_prefix)
+    }
+
+    /// The keys are pressed with a single call of `SendInput`, unless one of
+    /// them has to be entered differently (e.g. as a shortcut) or sticky
+    /// modifiers are latched
+    fn press_keys(&mut self, keys: &[Key]) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}press_keys(keys: {keys:?})\x1b[0m",
+            self.log_prefix
+        );
+        if self.sticky_modifiers.is_latched() || keys.iter().any(|&k| needs_key_function(k)) {
+            debug!(
+                "{}the keys are pressed one after the other",
+                self.log_prefix
+            );
+            return crate::press_keys(self, keys);
+        }
+        let mut input = vec![];
+        for &key in keys {
+            key.check_system_key(self.allow_system_keys)?;
+            key.check_enterable()?;
+            input.extend(self.key_inputs(key, Direction::Press)?);
+        }
+        send_input(&input, &self.log_prefix)?;
+        debug!(
+            "{}added the keys {keys:?} to the held keys",
+            self.log_prefix
+        );
+        self.held.0.extend_from_slice(keys);
+        Ok(())
+    }
+
+    /// The keys are released with a single call of `SendInput`, unless one of
+    /// them has to be entered differently (e.g. as a shortcut)
+    fn release_keys(&mut self, keys: &[Key]) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}release_keys(keys: {keys:?})\x1b[0m",
+            self.log_prefix
+        );
+        if keys.iter().any(|&k| needs_key_function(k)) {
+            debug!(
+                "{}the keys are released one after the other",
+                self.log_prefix
+            );
+            return crate::release_keys(self, keys);
+        }
+        let keys: Vec<Key> = keys
+            .iter()
+            .copied()
+            .filter(|key| {
+                !(self.ignore_unmatched_release
+                    && crate::is_unmatched_release(&self.held.0, key, Direction::Release))
+            })
+            .collect();
+        let mut input = vec![];
+        for &key in &keys {
+            key.check_system_key(self.allow_system_keys)?;
+            key.check_enterable()?;
+            input.extend(self.key_inputs(key, Direction::Release)?);
+        }
+        send_input(&input, &self.log_prefix)?;
+        debug!(
+            "{}removed the keys {keys:?} from the held keys",
+            self.log_prefix
+        );
+        self.held.0.retain(|k| !keys.contains(k));
+        Ok(())
+    }
+
     fn raw(&mut self, keycode: u16, direction: Direction) -> InputResult<()> {
-        debug!("\x1b[93mraw(keycode: {keycode:?}, direction: {direction:?})\x1b[0m");
+        debug!(
+            "\x1b[93m{}raw(keycode: {keycode:?}, direction: {direction:?})\x1b[0m",
+            self.log_prefix
+        );
+        if self.ignore_unmatched_release
+            && crate::is_unmatched_release(&self.held.1, &keycode, direction)
+        {
+            debug!(
+                "{}releasing the keycode {keycode:?} that is not held is a noop",
+                self.log_prefix
+            );
+            return Ok(());
+        }
         let mut input = vec![];
 
         // Some keycodes also need to have the KEYEVENTF_EXTENDEDKEY flag set because
@@ -381,15 +643,21 @@ impl Keyboard for Enigo {
             ));
         }
 
-        send_input(&input)?;
+        send_input(&input, &self.log_prefix)?;
 
         match direction {
             Direction::Press => {
-                debug!("added the key {keycode:?} to the held keys");
+                debug!(
+                    "{}added the key {keycode:?} to the held keys",
+                    self.log_prefix
+                );
                 self.held.1.push(keycode);
             }
             Direction::Release => {
-                debug!("removed the key {keycode:?} from the held keys");
+                debug!(
+                    "{}removed the key {keycode:?} from the held keys",
+                    self.log_prefix
+                );
                 self.held.1.retain(|&k| k != keycode);
             }
             Direction::Click => (),
@@ -410,17 +678,45 @@ impl Enigo {
         let Settings {
             windows_dw_extra_info: dw_extra_info,
             release_keys_when_dropped,
+            instance_name,
+            ignore_carriage_return,
+            max_text_length,
+            allow_system_keys,
+            ignore_unmatched_release,
+            click_press_release_gap_ms,
+            double_click_gap_ms,
+            invert_scroll_x,
+            invert_scroll_y,
+            sticky_modifiers,
             ..
         } = settings;
 
         let held = (vec![], vec![]);
+        let log_prefix = crate::log_prefix(instance_name.as_deref());
 
-        debug!("\x1b[93mconnection established on windows\x1b[0m");
+        debug!("\x1b[93m{log_prefix}connection established on windows\x1b[0m");
 
         Ok(Self {
             held,
+            last_click_location: None,
             release_keys_when_dropped: *release_keys_when_dropped,
             dw_extra_info: dw_extra_info.unwrap_or(crate::EVENT_MARKER as usize),
+            ignore_carriage_return: *ignore_carriage_return,
+            max_text_length: *max_text_length,
+            allow_system_keys: *allow_system_keys,
+            ignore_unmatched_release: *ignore_unmatched_release,
+            invert_scroll_x: *invert_scroll_x,
+            invert_scroll_y: *invert_scroll_y,
+            sticky_modifiers: crate::StickyModifiers::new(*sticky_modifiers),
+            click_gap: Duration::from_millis((*click_press_release_gap_ms).into()),
+            double_click_gap: Duration::from_millis((*double_click_gap_ms).into()),
+            #[cfg(feature = "platform_specific")]
+            touch_injection_initialized: false,
+            #[cfg(feature = "platform_specific")]
+            touches: vec![],
+            #[cfg(feature = "unicode")]
+            normalize_text: settings.normalize_text,
+            log_prefix,
         })
     }
 
@@ -445,7 +741,7 @@ impl Enigo {
                 }
                 Ok(keystate) => keystate,
                 Err(e) => {
-                    error!("{e:?}");
+                    error!("{}{e:?}", self.log_prefix);
                     return Err(InputError::InvalidInput(
                         "key state could not be converted to u32",
                     ));
@@ -461,7 +757,7 @@ impl Enigo {
                     scancodes.push(scan_code);
                 }
                 Err(e) => {
-                    error!("{e:?}");
+                    error!("{}{e:?}", self.log_prefix);
                     return Err(InputError::InvalidInput("scan code did not fit into u16"));
                 }
             };
@@ -479,9 +775,340 @@ impl Enigo {
     pub fn get_marker_value(&self) -> usize {
         self.dw_extra_info
     }
+
+    /// Get the capabilities of the connection
+    #[must_use]
+    pub fn capabilities(&self) -> Capabilities {
+        debug!("\x1b[93m{}capabilities()\x1b[0m", self.log_prefix);
+        Capabilities {
+            scroll_method: Some(ScrollMethod::Wheel),
+            buttons: crate::BUTTONS.to_vec(),
+            ..Default::default()
+        }
+        .with_queries(self)
+    }
+
+    /// Get all keys without any data (e.g. no [`Key::Unicode`]) that can be
+    /// entered. These are the keys that have a virtual key or are entered as
+    /// a shortcut
+    #[must_use]
+    pub fn supported_keys(&self) -> Vec<Key> {
+        debug!("\x1b[93m{}supported_keys()\x1b[0m", self.log_prefix);
+        crate::keycodes::NAMED_KEYS
+            .iter()
+            .copied()
+            .filter(|&key| self.allow_system_keys || !key.is_system_key())
+            .filter(|&key| key.shortcut().is_some() || VIRTUAL_KEY::try_from(key).is_ok())
+            .collect()
+    }
+
+    /// Create the inputs to enter the key. Special characters have to be
+    /// converted to their keys beforehand
+    fn key_inputs(&self, key: Key, direction: Direction) -> InputResult<Vec<INPUT>> {
+        let mut input = vec![];
+
+        if let Key::Unicode(c) = key {
+            // Characters without a key in the layout (e.g. combining marks) are
+            // entered as Unicode
+            let (flags, scancodes) = match self.get_scancode(c) {
+                Ok(scancodes) => (KEYEVENTF_SCANCODE, scancodes),
+                Err(e) => {
+                    debug!(
+                        "{}entering {c:?} as unicode, because it has no scancode: {e}",
+                        self.log_prefix
+                    );
+                    let mut buffer = [0; 2];
+                    (KEYEVENTF_UNICODE, c.encode_utf16(&mut buffer).to_vec())
+                }
+            };
+            if direction == Direction::Click || direction == Direction::Press {
+                for scan in &scancodes {
+                    input.push(keybd_event(
+                        flags,
+                        VIRTUAL_KEY(0),
+                        *scan,
+                        self.dw_extra_info,
+                    ));
+                }
+            }
+            if direction == Direction::Click || direction == Direction::Release {
+                for scan in &scancodes {
+                    input.push(keybd_event(
+                        flags | KEYEVENTF_KEYUP,
+                        VIRTUAL_KEY(0),
+                        *scan,
+                        self.dw_extra_info,
+                    ));
+                }
+            }
+        } else {
+            // It is okay to unwrap here because key_to_keycode only returns a None for
+            // Key::Unicode and we already ensured that is not the case
+            let keycode = VIRTUAL_KEY::try_from(key).unwrap();
+            // The enter key on the numeric keypad has the same virtual key as the
+            // regular one and is only distinguished by the extended flag
+            let keyflags = if key == Key::NumpadEnter {
+                KEYEVENTF_EXTENDEDKEY
+            } else {
+                get_key_flags(keycode, &self.log_prefix)
+            };
+            if direction == Direction::Click || direction == Direction::Press {
+                input.push(keybd_event(keyflags, keycode, 0u16, self.dw_extra_info));
+            }
+            if direction == Direction::Click || direction == Direction::Release {
+                input.push(keybd_event(
+                    keyflags | KEYEVENTF_KEYUP,
+                    keycode,
+                    0u16,
+                    self.dw_extra_info,
+                ));
+            }
+        }
+        Ok(input)
+    }
+
+    /// Get the modifiers that are currently held down (e.g. by the user).
+    /// Unlike [`Enigo::held`], this is not limited to the keys entered with
+    /// this instance. Windows merges the state of all keyboards, so the
+    /// modifiers held by simulated input are included as well
+    ///
+    /// # Errors
+    /// No error is returned on Windows
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn physical_modifiers(&self) -> InputResult<Vec<Key>> {
+        debug!("\x1b[93m{}physical_modifiers()\x1b[0m", self.log_prefix);
+        // The most significant bit is set if the key is down
+        let is_down = |vk: VIRTUAL_KEY| unsafe { GetAsyncKeyState(i32::from(vk.0)) } < 0;
+        let modifiers: [(&[VIRTUAL_KEY], Key); 5] = [
+            (&[VK_SHIFT], Key::Shift),
+            (&[VK_CAPITAL], Key::CapsLock),
+            (&[VK_CONTROL], Key::Control),
+            (&[VK_MENU], Key::Alt),
+            (&[VK_LWIN, VK_RWIN], Key::Meta),
+        ];
+        Ok(modifiers
+            .into_iter()
+            .filter(|(vks, _)| vks.iter().any(|&vk| is_down(vk)))
+            .map(|(_, key)| key)
+            .collect())
+    }
+
+    /// Get the number of lines one unit of the length of [`Mouse::scroll`]
+    /// scrolls. This is the setting of the user for the scroll wheel. If it is
+    /// `WHEEL_PAGESCROLL` (`u32::MAX`), a whole page is scrolled per notch
+    ///
+    /// # Errors
+    /// An error is returned if the setting could not be retrieved
+    pub fn scroll_lines_per_notch(&self) -> InputResult<u32> {
+        debug!("\x1b[93m{}scroll_lines_per_notch()\x1b[0m", self.log_prefix);
+        let mut lines: u32 = 0;
+        unsafe {
+            SystemParametersInfoW(
+                SPI_GETWHEELSCROLLLINES,
+                0,
+                Some(std::ptr::addr_of_mut!(lines).cast()),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            )
+        }
+        .map_err(|_| {
+            InputError::Simulate("unable to get the number of lines to scroll per notch")
+        })?;
+        Ok(lines)
+    }
+
+    /// Get the title, class and process id of the window that currently has
+    /// the focus
+    ///
+    /// # Errors
+    /// An error is returned if no window has the focus
+    pub fn focused_window(&self) -> InputResult<WindowInfo> {
+        debug!("\x1b[93m{}focused_window()\x1b[0m", self.log_prefix);
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd.0 == 0 {
+            return Err(InputError::Simulate("there is no focused window"));
+        }
+
+        let title_len = unsafe { GetWindowTextLengthW(hwnd) };
+        let mut title = vec![0; usize::try_from(title_len).unwrap_or(0) + 1];
+        let title_len = unsafe { GetWindowTextW(hwnd, &mut title) };
+        let title = String::from_utf16_lossy(&title[..usize::try_from(title_len).unwrap_or(0)]);
+
+        // The maximum length of a class name is 256
+        let mut class = [0; 257];
+        let class_len = unsafe { GetClassNameW(hwnd, &mut class) };
+        let class = usize::try_from(class_len)
+            .ok()
+            .filter(|&len| len > 0)
+            .map(|len| String::from_utf16_lossy(&class[..len]));
+
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+        let pid = Some(pid).filter(|&pid| pid != 0);
+
+        // The handle is only used as an id, so the sign does not matter
+        #[allow(clippy::cast_sign_loss)]
+        let id = hwnd.0 as u64;
+
+        Ok(WindowInfo {
+            title,
+            class,
+            pid,
+            id,
+        })
+    }
+
+    /// Put a finger on the touch screen at the given absolute coordinates in
+    /// pixels. The id identifies the touch point, so multiple touch points
+    /// can be in contact at the same time to simulate multi-touch gestures.
+    /// Unlike the functions of [`Mouse`], this does not emulate a mouse but
+    /// injects real touch input.
+    ///
+    /// This is only available on Windows. The touch injection gets
+    /// initialized the first time a touch point is put down. At most ten
+    /// touch points can be in contact at the same time.
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if a touch point with the
+    /// id is already in contact or if there are too many touch points. An
+    /// [`InputError::Simulate`] is returned if the touch injection could not
+    /// be initialized or if the input could not be injected
+    #[cfg(feature = "platform_specific")]
+    pub fn touch_down(&mut self, id: u32, x: i32, y: i32) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}touch_down(id: {id:?}, x: {x:?}, y: {y:?})\x1b[0m",
+            self.log_prefix
+        );
+        if self.touches.iter().any(|t| t.pointerInfo.pointerId == id) {
+            return Err(InputError::InvalidInput(
+                "the touch point is already in contact",
+            ));
+        }
+        if self.touches.len() >= MAX_TOUCH_COUNT as usize {
+            return Err(InputError::InvalidInput(
+                "too many touch points are in contact",
+            ));
+        }
+        if !self.touch_injection_initialized {
+            if let Err(e) =
+                unsafe { InitializeTouchInjection(MAX_TOUCH_COUNT, TOUCH_FEEDBACK_DEFAULT) }
+            {
+                error!("{}{e}", self.log_prefix);
+                return Err(InputError::Simulate(
+                    "unable to initialize the touch injection",
+                ));
+            }
+            self.touch_injection_initialized = true;
+        }
+        self.touches.push(touch_info(id, x, y));
+        let result = self.inject_touches(
+            id,
+            POINTER_FLAG_DOWN | POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT,
+        );
+        if result.is_err() {
+            self.touches.retain(|t| t.pointerInfo.pointerId != id);
+        }
+        result
+    }
+
+    /// Move the touch point with the id to the given absolute coordinates in
+    /// pixels while it stays in contact.
+    ///
+    /// This is only available on Windows.
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if there is no touch point
+    /// with the id in contact. An [`InputError::Simulate`] is returned if the
+    /// input could not be injected
+    #[cfg(feature = "platform_specific")]
+    pub fn touch_move(&mut self, id: u32, x: i32, y: i32) -> InputResult<()> {
+        debug!(
+            "\x1b[93m{}touch_move(id: {id:?}, x: {x:?}, y: {y:?})\x1b[0m",
+            self.log_prefix
+        );
+        let Some(touch) = self
+            .touches
+            .iter_mut()
+            .find(|t| t.pointerInfo.pointerId == id)
+        else {
+            return Err(InputError::InvalidInput(
+                "the touch point is not in contact",
+            ));
+        };
+        *touch = touch_info(id, x, y);
+        self.inject_touches(
+            id,
+            POINTER_FLAG_UPDATE | POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT,
+        )
+    }
+
+    /// Lift the touch point with the id from the touch screen.
+    ///
+    /// This is only available on Windows.
+    ///
+    /// # Errors
+    /// An [`InputError::InvalidInput`] is returned if there is no touch point
+    /// with the id in contact. An [`InputError::Simulate`] is returned if the
+    /// input could not be injected
+    #[cfg(feature = "platform_specific")]
+    pub fn touch_up(&mut self, id: u32) -> InputResult<()> {
+        debug!("\x1b[93m{}touch_up(id: {id:?})\x1b[0m", self.log_prefix);
+        if !self.touches.iter().any(|t| t.pointerInfo.pointerId == id) {
+            return Err(InputError::InvalidInput(
+                "the touch point is not in contact",
+            ));
+        }
+        let result = self.inject_touches(id, POINTER_FLAG_UP);
+        // The touch point is no longer tracked, even if lifting it failed so it
+        // does not get stuck
+        self.touches.retain(|t| t.pointerInfo.pointerId != id);
+        result
+    }
+
+    /// Inject a frame containing all touch points that are in contact. The
+    /// touch point with the id gets the given flags and all others are
+    /// updated without changes. Windows requires all touch points in contact
+    /// to be part of each frame
+    #[cfg(feature = "platform_specific")]
+    fn inject_touches(&mut self, id: u32, flags: POINTER_FLAGS) -> InputResult<()> {
+        for touch in &mut self.touches {
+            touch.pointerInfo.pointerFlags = if touch.pointerInfo.pointerId == id {
+                flags
+            } else {
+                POINTER_FLAG_UPDATE | POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT
+            };
+        }
+        if let Err(e) = unsafe { InjectTouchInput(&self.touches) } {
+            error!("{}{e}", self.log_prefix);
+            return Err(InputError::Simulate("unable to inject the touch input"));
+        }
+        Ok(())
+    }
 }
 
-fn get_key_flags(vk: VIRTUAL_KEY) -> KEYBD_EVENT_FLAGS {
+/// Create the info of a touch point at the given location. The flags of the
+/// pointer are set when the touch point gets injected
+#[cfg(feature = "platform_specific")]
+fn touch_info(id: u32, x: i32, y: i32) -> POINTER_TOUCH_INFO {
+    POINTER_TOUCH_INFO {
+        pointerInfo: POINTER_INFO {
+            pointerType: PT_TOUCH,
+            pointerId: id,
+            ptPixelLocation: POINT { x, y },
+            ..Default::default()
+        },
+        touchFlags: TOUCH_FLAG_NONE,
+        touchMask: TOUCH_MASK_CONTACTAREA,
+        rcContact: RECT {
+            left: x - TOUCH_RADIUS,
+            top: y - TOUCH_RADIUS,
+            right: x + TOUCH_RADIUS,
+            bottom: y + TOUCH_RADIUS,
+        },
+        ..Default::default()
+    }
+}
+
+fn get_key_flags(vk: VIRTUAL_KEY, log_prefix: &str) -> KEYBD_EVENT_FLAGS {
     use windows::Win32::UI::Input::KeyboardAndMouse::{
         VK_DELETE, VK_DIVIDE, VK_DOWN, VK_END, VK_HOME, VK_INSERT, VK_LEFT, VK_NEXT, VK_NUMLOCK,
         VK_PRIOR, VK_RCONTROL, VK_RIGHT, VK_RMENU, VK_SNAPSHOT, VK_UP,
@@ -493,10 +1120,12 @@ fn get_key_flags(vk: VIRTUAL_KEY) -> KEYBD_EVENT_FLAGS {
         // may not have the expected result and depends on whether NUMLOCK is enabled/disabled.
         // A list of the extended keys can be found here:
         // https://learn.microsoft.com/en-us/windows/win32/inputdev/about-keyboard-input#extended-key-flag
-        // TODO: The keys "BREAK (CTRL+PAUSE) key" and "ENTER key in the numeric keypad" are missing
+        // TODO: The key "BREAK (CTRL+PAUSE) key" is missing. The "ENTER key in the numeric
+        // keypad" is handled in the key function, because it has the same virtual key as
+        // the regular ENTER key
         VK_RMENU | VK_RCONTROL | VK_UP | VK_DOWN | VK_LEFT | VK_RIGHT | VK_INSERT | VK_DELETE
         | VK_HOME | VK_END | VK_PRIOR | VK_NEXT | VK_NUMLOCK | VK_SNAPSHOT | VK_DIVIDE => {
-            debug!("extended key detected");
+            debug!("{log_prefix}extended key detected");
             KEYBD_EVENT_FLAGS::default() | KEYEVENTF_EXTENDEDKEY
         }
         _ => KEYBD_EVENT_FLAGS::default(),
@@ -510,16 +1139,18 @@ impl Drop for Enigo {
             return;
         }
         let (held_keys, held_keycodes) = self.held();
-        for key in held_keys {
-            if self.key(key, Direction::Release).is_err() {
-                error!("unable to release {key:?}");
-            };
-        }
-        for keycode in held_keycodes {
-            if self.raw(keycode, Direction::Release).is_err() {
-                error!("unable to release {keycode:?}");
+        crate::release_held(self, &held_keys, &held_keycodes);
+        #[cfg(feature = "platform_specific")]
+        for id in self
+            .touches
+            .iter()
+            .map(|t| t.pointerInfo.pointerId)
+            .collect::<Vec<_>>()
+        {
+            if self.touch_up(id).is_err() {
+                error!("{}unable to lift the touch point {id:?}", self.log_prefix);
             };
         }
-        debug!("released all held keys");
+        debug!("{}released all held keys", self.log_prefix);
     }
 }
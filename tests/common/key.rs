@@ -1,6 +1,7 @@
 use std::sync::mpsc::Receiver;
 
 use enigo::{
+    ArrowDirection,
     Direction::{Press, Release},
     Enigo, Key, Keyboard, Settings,
 };
@@ -11,10 +12,51 @@ pub fn run(recv: &Receiver<BrowserEvent>) {
     press(recv, Key::F1);
     press(recv, Key::Control);
     press(recv, Key::Backspace);
+    // The menu key is called ContextMenu in the browser
+    press_named(recv, Key::Menu, "ContextMenu");
     // press(recv, Key::PageUp); Failing on Windows
+    // xdo does not allow disabling the auto-repeat
+    #[cfg(any(not(target_os = "linux"), feature = "x11rb"))]
+    hold_without_repeat(recv, Key::Backspace);
+    // Tabs and newlines have to be entered as key events
+    text(recv, "a\tb\nc", &["a", "Tab", "b", "Enter", "c"]);
+    // The enter key of the numeric keypad is distinct from the regular one
+    press(recv, Key::NumpadEnter);
+    // Repeated spaces must not get dropped
+    text(recv, "  a  b  ", &[" ", " ", "a", " ", " ", "b", " ", " "]);
+    // Mixed right-to-left and left-to-right text is entered in logical order
+    text(recv, "a سلام b", &["a", " ", "س", "ل", "ا", "م", " ", "b"]);
+    // A combining mark has to be entered on its own so it combines with the
+    // previous char in the target
+    text(recv, "e\u{301}", &["e", "\u{301}"]);
+    // Consecutive different arrow keys must not get lost
+    move_cursor(recv, ArrowDirection::Left, 2, "ArrowLeft");
+    move_cursor(recv, ArrowDirection::Right, 1, "ArrowRight");
+    move_cursor(recv, ArrowDirection::Up, 1, "ArrowUp");
+    // The modifier of a shortcut must not get lost
+    for _ in 0..10 {
+        key_combo(recv, Key::Control, Key::Unicode('a'), ["Control", "a"]);
+    }
+    // All keys have to be held at the same time
+    press_keys(
+        recv,
+        &[
+            Key::Unicode('w'),
+            Key::Unicode('a'),
+            Key::Unicode('s'),
+            Key::Unicode('d'),
+        ],
+        &["w", "a", "s", "d"],
+    );
 }
 
 fn press(recv: &Receiver<BrowserEvent>, key: Key) {
+    press_named(recv, key, &format!("{key:?}"));
+}
+
+/// Press and release the key and check that the browser received the events
+/// of the key with the name
+fn press_named(recv: &Receiver<BrowserEvent>, key: Key, name: &str) {
     let mut enigo = Enigo::new(&Settings::default()).unwrap();
 
     enigo.key(key, Press).unwrap();
@@ -22,7 +64,7 @@ fn press(recv: &Receiver<BrowserEvent>, key: Key) {
         .recv_timeout(std::time::Duration::from_millis(5000))
         .unwrap();
     if let BrowserEvent::KeyDown(pressed) = ev {
-        assert_eq!(format!("{key:?}").to_lowercase(), pressed.to_lowercase());
+        assert_eq!(name.to_lowercase(), pressed.to_lowercase());
     } else {
         panic!("Event wasn't KeyDown after mouse::press. {ev:?}");
     }
@@ -31,8 +73,138 @@ fn press(recv: &Receiver<BrowserEvent>, key: Key) {
         .recv_timeout(std::time::Duration::from_millis(5000))
         .unwrap();
     if let BrowserEvent::KeyUp(pressed) = ev {
-        assert_eq!(format!("{key:?}").to_lowercase(), pressed.to_lowercase());
+        assert_eq!(name.to_lowercase(), pressed.to_lowercase());
     } else {
         panic!("Event wasn't KeyUp after mouse::press. {ev:?}");
     }
 }
+
+// Hold the key for longer than the usual auto-repeat delay and make sure it was
+// only pressed once
+#[cfg(any(not(target_os = "linux"), feature = "x11rb"))]
+fn hold_without_repeat(recv: &Receiver<BrowserEvent>, key: Key) {
+    let mut enigo = Enigo::new(&Settings {
+        disable_autorepeat_on_press: true,
+        ..Default::default()
+    })
+    .unwrap();
+
+    enigo.key(key, Press).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(2000));
+    enigo.key(key, Release).unwrap();
+    let ev = recv
+        .recv_timeout(std::time::Duration::from_millis(5000))
+        .unwrap();
+    if let BrowserEvent::KeyDown(pressed) = ev {
+        assert_eq!(format!("{key:?}").to_lowercase(), pressed.to_lowercase());
+    } else {
+        panic!("Event wasn't KeyDown after key::hold_without_repeat. {ev:?}");
+    }
+    let ev = recv
+        .recv_timeout(std::time::Duration::from_millis(5000))
+        .unwrap();
+    if let BrowserEvent::KeyUp(pressed) = ev {
+        assert_eq!(format!("{key:?}").to_lowercase(), pressed.to_lowercase());
+    } else {
+        panic!("Event wasn't KeyUp after key::hold_without_repeat. The key was repeated. {ev:?}");
+    }
+}
+
+fn text(recv: &Receiver<BrowserEvent>, text: &str, expected_keys: &[&str]) {
+    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+
+    enigo.text(text).unwrap();
+    for expected in expected_keys {
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        if let BrowserEvent::KeyDown(pressed) = ev {
+            assert_eq!(*expected, pressed);
+        } else {
+            panic!("Event wasn't KeyDown after key::text. {ev:?}");
+        }
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        if let BrowserEvent::KeyUp(pressed) = ev {
+            assert_eq!(*expected, pressed);
+        } else {
+            panic!("Event wasn't KeyUp after key::text. {ev:?}");
+        }
+    }
+}
+
+fn move_cursor(
+    recv: &Receiver<BrowserEvent>,
+    direction: ArrowDirection,
+    count: u32,
+    expected: &str,
+) {
+    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+
+    enigo.move_cursor(direction, count).unwrap();
+    for _ in 0..count {
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        if let BrowserEvent::KeyDown(pressed) = ev {
+            assert_eq!(expected, pressed);
+        } else {
+            panic!("Event wasn't KeyDown after key::move_cursor. {ev:?}");
+        }
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        if let BrowserEvent::KeyUp(pressed) = ev {
+            assert_eq!(expected, pressed);
+        } else {
+            panic!("Event wasn't KeyUp after key::move_cursor. {ev:?}");
+        }
+    }
+}
+
+fn key_combo(recv: &Receiver<BrowserEvent>, modifier: Key, key: Key, expected: [&str; 2]) {
+    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+
+    enigo.key_combo(&[modifier], key).unwrap();
+    // The modifier has to be down before the key and up after it
+    let [modifier, key] = expected;
+    for expected in [
+        BrowserEvent::KeyDown(modifier.to_string()),
+        BrowserEvent::KeyDown(key.to_string()),
+        BrowserEvent::KeyUp(key.to_string()),
+        BrowserEvent::KeyUp(modifier.to_string()),
+    ] {
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        assert_eq!(expected, ev, "wrong event after key::key_combo");
+    }
+}
+
+fn press_keys(recv: &Receiver<BrowserEvent>, keys: &[Key], expected_keys: &[&str]) {
+    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+
+    enigo.press_keys(keys).unwrap();
+    for expected in expected_keys {
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        if let BrowserEvent::KeyDown(pressed) = ev {
+            assert_eq!(*expected, pressed);
+        } else {
+            panic!("Event wasn't KeyDown after key::press_keys. {ev:?}");
+        }
+    }
+    enigo.release_keys(keys).unwrap();
+    for expected in expected_keys {
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        if let BrowserEvent::KeyUp(pressed) = ev {
+            assert_eq!(*expected, pressed);
+        } else {
+            panic!("Event wasn't KeyUp after key::release_keys. {ev:?}");
+        }
+    }
+}
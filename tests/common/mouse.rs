@@ -1,6 +1,6 @@
 use std::sync::mpsc::Receiver;
 
-use enigo::{Axis, Coordinate, Enigo, Mouse, Settings};
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, Mouse, Settings};
 
 use super::BrowserEvent;
 
@@ -21,6 +21,10 @@ pub fn run(recv: &Receiver<BrowserEvent>) {
     rel(recv, (-20, -20));
     println!("Scroll");
     scroll(recv);
+    println!("Inverted scroll");
+    inverted_scroll(recv);
+    println!("Click");
+    click(recv, Button::Left);
 }
 
 fn set(recv: &Receiver<BrowserEvent>, position: (i32, i32)) {
@@ -28,6 +32,9 @@ fn set(recv: &Receiver<BrowserEvent>, position: (i32, i32)) {
     enigo
         .move_mouse(position.0, position.1, Coordinate::Abs)
         .unwrap();
+    // The location has to be up to date right after the move
+    #[cfg(target_os = "linux")]
+    assert_eq!(enigo.location().unwrap(), position);
     println!("Executed Enigo");
     let ev = recv
         .recv_timeout(std::time::Duration::from_millis(5000))
@@ -61,33 +68,122 @@ fn rel(recv: &Receiver<BrowserEvent>, offset: (i32, i32)) {
     }
 }
 
-fn scroll(recv: &Receiver<BrowserEvent>) {
+fn click(recv: &Receiver<BrowserEvent>, button: Button) {
     let mut enigo = Enigo::new(&Settings::default()).unwrap();
-    enigo.scroll(1, Axis::Horizontal).unwrap();
+    enigo.button(button, Direction::Click).unwrap();
     println!("Executed Enigo");
+    let expected = button.to_web_button().unwrap();
     let ev = recv
         .recv_timeout(std::time::Duration::from_millis(5000))
         .unwrap();
-    println!("Done waiting");
-    if let BrowserEvent::MouseWheel(length) = ev {
-        println!("Scroll success");
-        assert!(length.0 > 0);
-        assert!(length.1 == 0);
+    if let BrowserEvent::MouseDown(pressed) = ev {
+        assert_eq!(
+            Button::from_web_button(pressed.parse().unwrap()),
+            Some(button)
+        );
+    } else {
+        panic!("Event wasn't MouseDown after mouse::click. {ev:?}");
+    }
+    let ev = recv
+        .recv_timeout(std::time::Duration::from_millis(5000))
+        .unwrap();
+    if let BrowserEvent::MouseUp(released) = ev {
+        assert_eq!(expected, released.parse::<u32>().unwrap());
     } else {
-        panic!("Event wasn't MouseWheel after mouse::scroll. {ev:?}");
+        panic!("Event wasn't MouseUp after mouse::click. {ev:?}");
     }
+    println!("Click success");
+}
 
-    enigo.scroll(1, Axis::Vertical).unwrap();
-    println!("Executed Enigo");
+fn scroll(recv: &Receiver<BrowserEvent>) {
+    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+    scroll_events(recv, &mut enigo, 1, Axis::Horizontal, |(x, y)| {
+        x > 0 && y == 0
+    });
+    scroll_events(recv, &mut enigo, -1, Axis::Horizontal, |(x, y)| {
+        x < 0 && y == 0
+    });
+    scroll_events(recv, &mut enigo, 1, Axis::Vertical, |(x, y)| {
+        x == 0 && y > 0
+    });
+    // On X11 each unit of the length is a separate event
+    scroll_events(recv, &mut enigo, -3, Axis::Vertical, |(x, y)| {
+        x == 0 && y < 0
+    });
+    // Each notch scrolls the same distance, no matter how the platform
+    // represents it
+    let (_, one_notch) = scroll_events(recv, &mut enigo, 1, Axis::Vertical, |(x, y)| {
+        x == 0 && y > 0
+    });
+    let (_, two_notches) = scroll_events(recv, &mut enigo, 2, Axis::Vertical, |(x, y)| {
+        x == 0 && y > 0
+    });
+    assert_eq!(two_notches, 2 * one_notch);
+}
+
+/// Check the direction of each axis with all combinations of the inverted axes.
+/// The scroll buttons are inverted like the scroll function
+fn inverted_scroll(recv: &Receiver<BrowserEvent>) {
+    for (invert_scroll_x, invert_scroll_y) in [(true, false), (false, true), (true, true)] {
+        let mut enigo = Enigo::new(&Settings {
+            invert_scroll_x,
+            invert_scroll_y,
+            ..Default::default()
+        })
+        .unwrap();
+        let sign_x = if invert_scroll_x { -1 } else { 1 };
+        let sign_y = if invert_scroll_y { -1 } else { 1 };
+        scroll_events(recv, &mut enigo, 1, Axis::Horizontal, |(x, y)| {
+            x.signum() == sign_x && y == 0
+        });
+        scroll_events(recv, &mut enigo, 1, Axis::Vertical, |(x, y)| {
+            x == 0 && y.signum() == sign_y
+        });
+        enigo.button(Button::ScrollRight, Direction::Click).unwrap();
+        assert_wheel(recv, |(x, y)| x.signum() == sign_x && y == 0);
+        enigo.button(Button::ScrollDown, Direction::Click).unwrap();
+        assert_wheel(recv, |(x, y)| x == 0 && y.signum() == sign_y);
+    }
+}
+
+/// Check that the next event is a scroll event with the expected length
+fn assert_wheel(recv: &Receiver<BrowserEvent>, is_expected: impl Fn((i32, i32)) -> bool) {
     let ev = recv
         .recv_timeout(std::time::Duration::from_millis(5000))
         .unwrap();
-    println!("Done waiting");
     if let BrowserEvent::MouseWheel(length) = ev {
-        println!("Scroll success");
-        assert!(length.0 == 0);
-        assert!(length.1 > 0);
+        assert!(is_expected(length), "unexpected scroll of {length:?}");
     } else {
-        panic!("Event wasn't MouseWheel after mouse::scroll. {ev:?}");
+        panic!("Event wasn't MouseWheel after mouse::button. {ev:?}");
+    }
+}
+
+/// Scroll and check that the browser received as many scroll events as were
+/// sent. Returns the distance that was scrolled in total
+fn scroll_events(
+    recv: &Receiver<BrowserEvent>,
+    enigo: &mut Enigo,
+    length: i32,
+    axis: Axis,
+    is_expected: impl Fn((i32, i32)) -> bool,
+) -> (i32, i32) {
+    let mut distance = (0, 0);
+    let events = enigo.scroll(length, axis).unwrap();
+    println!("Executed Enigo");
+    assert!(events > 0);
+    for _ in 0..events {
+        let ev = recv
+            .recv_timeout(std::time::Duration::from_millis(5000))
+            .unwrap();
+        println!("Done waiting");
+        if let BrowserEvent::MouseWheel(length) = ev {
+            println!("Scroll success");
+            assert!(is_expected(length), "unexpected scroll of {length:?}");
+            distance.0 += length.0;
+            distance.1 += length.1;
+        } else {
+            panic!("Event wasn't MouseWheel after mouse::scroll. {ev:?}");
+        }
     }
+    distance
 }